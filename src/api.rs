@@ -94,10 +94,43 @@ pub struct BatchUploadResponse {
     pub failed: Vec<String>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct BatchDownloadRequest {
+    pub object_ids: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DownloadedObject {
+    pub object_id: String,
+    /// Base64-encoded object content
+    pub data: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BatchDownloadResponse {
+    /// Requested objects the server does not have are left out
+    pub objects: Vec<DownloadedObject>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UpdateRefRequest {
     pub ref_name: String,
     pub commit_id: String,
+    /// Value the ref must still have for the server to apply the update;
+    /// all zeros means the ref must not exist yet. Omitted for forced pushes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_commit_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefEntry {
+    pub ref_name: String,
+    pub commit_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListRefsResponse {
+    pub refs: Vec<RefEntry>,
 }
 
 #[derive(Debug, Serialize)]
@@ -141,6 +174,7 @@ pub struct SearchResult {
     pub size: i64,
 }
 
+#[derive(Clone)]
 pub struct ApiClient {
     config: AppConfig,
     client: reqwest::Client,
@@ -152,6 +186,10 @@ impl ApiClient {
         Self { config, client }
     }
 
+    pub fn config(&self) -> &AppConfig {
+        &self.config
+    }
+
     /// Build HTTP client with Tor support
     fn build_client(config: &AppConfig) -> reqwest::Client {
         let mut builder = reqwest::Client::builder()
@@ -317,11 +355,41 @@ impl ApiClient {
         Ok(response.bytes().await?.to_vec())
     }
 
+    /// Download several objects in one request. Returns `None` when the
+    /// server has no batch endpoint, so callers can fall back to
+    /// `download_object`.
+    pub async fn batch_download_objects(
+        &self,
+        repo_hash: &str,
+        object_ids: Vec<String>,
+    ) -> anyhow::Result<Option<BatchDownloadResponse>> {
+        let url = format!(
+            "{}/api/repos/{}/objects/download",
+            self.config.hyrule_server, repo_hash
+        );
+        let req = BatchDownloadRequest { object_ids };
+
+        let response = self.client.post(&url).json(&req).send().await?;
+        let status = response.status();
+
+        if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::METHOD_NOT_ALLOWED {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            let body = response.text().await?;
+            anyhow::bail!("Failed to download objects ({}): {}", status, body);
+        }
+
+        let body_text = response.text().await?;
+        Ok(Some(serde_json::from_str::<BatchDownloadResponse>(&body_text)?))
+    }
+
     pub async fn update_ref(
         &self,
         repo_hash: &str,
         ref_name: &str,
         commit_id: &str,
+        old_commit_id: Option<&str>,
     ) -> anyhow::Result<()> {
         let token = self
             .config
@@ -333,6 +401,7 @@ impl ApiClient {
         let req = UpdateRefRequest {
             ref_name: ref_name.to_string(),
             commit_id: commit_id.to_string(),
+            old_commit_id: old_commit_id.map(|s| s.to_string()),
         };
 
         let response = self
@@ -343,6 +412,9 @@ impl ApiClient {
             .send()
             .await?;
 
+        if response.status() == reqwest::StatusCode::CONFLICT {
+            anyhow::bail!("{} changed on the remote since it was last read", ref_name);
+        }
         if !response.status().is_success() {
             anyhow::bail!("Failed to update ref");
         }
@@ -350,19 +422,15 @@ impl ApiClient {
         Ok(())
     }
 
-    pub async fn get_ref(&self, repo_hash: &str, ref_name: &str) -> anyhow::Result<String> {
-        let encoded_ref = urlencoding::encode(ref_name);
-        let url = format!(
-            "{}/api/repos/{}/refs/{}",
-            self.config.hyrule_server, repo_hash, encoded_ref
-        );
+    pub async fn list_refs(&self, repo_hash: &str) -> anyhow::Result<Vec<RefEntry>> {
+        let url = format!("{}/api/repos/{}/refs", self.config.hyrule_server, repo_hash);
         let response = self.client.get(&url).send().await?;
 
         if !response.status().is_success() {
-            anyhow::bail!("Ref not found: {}", ref_name);
+            anyhow::bail!("Failed to list refs");
         }
 
-        Ok(response.text().await?)
+        Ok(response.json::<ListRefsResponse>().await?.refs)
     }
 
     pub async fn delete_repo(&self, repo_hash: &str) -> anyhow::Result<()> {
@@ -1,20 +1,35 @@
 // TriForge/src/commands/add.rs
 use colored::*;
-use std::fs;
 use std::path::Path;
-use crate::native_git::Repository;
-use walkdir::WalkDir;
+use crate::native_git::{Repository, Index, IgnoreRules};
 
 pub fn execute(paths: Vec<String>, all: bool) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
+    let mut index = repo.index()?;
+    let ignore = repo.ignore_rules()?;
     
     let files_to_add = if all {
-        collect_all_files(&repo)?
+        ignore.walk(repo.work_dir())?
     } else {
-        expand_paths(paths)?
+        expand_paths(&repo, &index, &ignore, paths.clone())?
     };
     
-    if files_to_add.is_empty() {
+    // Tracked files that no longer exist on disk are staged as deletions
+    let removed = if all {
+        missing_tracked(&repo, &index, None)
+    } else {
+        let mut removed = Vec::new();
+        for path in &paths {
+            if Path::new(path).symlink_metadata().is_err() {
+                if let Some(rel) = repo.relative_path(Path::new(path))? {
+                    removed.extend(missing_tracked(&repo, &index, Some(&rel)));
+                }
+            }
+        }
+        removed
+    };
+    
+    if files_to_add.is_empty() && removed.is_empty() {
         println!("{} No files to add", "!".yellow());
         return Ok(());
     }
@@ -25,80 +40,80 @@ pub fn execute(paths: Vec<String>, all: bool) -> anyhow::Result<()> {
     for path in &files_to_add {
         let path_obj = Path::new(path);
         
-        if !path_obj.exists() {
-            println!("{} {} (not found)", "!".yellow(), path);
-            continue;
-        }
+        let meta = match path_obj.symlink_metadata() {
+            Ok(m) => m,
+            Err(_) => {
+                println!("{} {} (not found)", "!".yellow(), path);
+                continue;
+            }
+        };
         
-        if path_obj.is_file() {
-            let content = fs::read(path)?;
-            let blob = repo.create_blob(&content)?;
-            repo.store_object(&blob)?;
+        if meta.is_file() || meta.file_type().is_symlink() {
+            let rel_path = match repo.relative_path(path_obj)? {
+                Some(p) => p,
+                None => continue,
+            };
             
-            println!("{} {}", "+".green(), path.yellow());
+            repo.stage_file(&mut index, &rel_path)?;
+            
+            println!("{} {}", "+".green(), rel_path.yellow());
             added_count += 1;
         }
     }
     
+    for path in &removed {
+        index.remove(path);
+        println!("{} {}", "-".red(), path.yellow());
+    }
+    
+    index.save()?;
+    
     println!();
     println!("{} Added {} files", "✓".green(), added_count.to_string().cyan());
+    if !removed.is_empty() {
+        println!("{} Staged {} deletions", "✓".green(), removed.len().to_string().cyan());
+    }
     
     Ok(())
 }
 
-/// Expand paths to include all files in directories
-fn expand_paths(paths: Vec<String>) -> anyhow::Result<Vec<String>> {
-    let mut files = Vec::new();
-    
-    for path in paths {
-        let path_obj = Path::new(&path);
-        
-        if !path_obj.exists() {
-            continue;
-        }
-        
-        if path_obj.is_file() {
-            files.push(path);
-        } else if path_obj.is_dir() {
-            // Recursively walk directory
-            for entry in WalkDir::new(&path)
-                .into_iter()
-                .filter_entry(|e| !is_ignored(e.path()))
-            {
-                let entry = entry?;
-                if entry.file_type().is_file() {
-                    files.push(entry.path().to_string_lossy().to_string());
-                }
-            }
-        }
-    }
-    
-    Ok(files)
+/// Tracked paths (optionally under a prefix) that are missing from the working tree
+fn missing_tracked(repo: &Repository, index: &Index, prefix: Option<&str>) -> Vec<String> {
+    index.entries()
+        .filter(|e| match prefix {
+            Some(p) => e.path == p || e.path.starts_with(&format!("{}/", p)),
+            None => true,
+        })
+        .filter(|e| repo.work_dir().join(&e.path).symlink_metadata().is_err())
+        .map(|e| e.path.clone())
+        .collect()
 }
 
-fn collect_all_files(repo: &Repository) -> anyhow::Result<Vec<String>> {
+/// Expand paths to include all files in directories. Ignored files are
+/// skipped unless they are already tracked.
+fn expand_paths(repo: &Repository, index: &Index, ignore: &IgnoreRules, paths: Vec<String>) -> anyhow::Result<Vec<String>> {
     let mut files = Vec::new();
     
-    for entry in WalkDir::new(repo.work_dir())
-        .into_iter()
-        .filter_entry(|e| !is_ignored(e.path()))
-    {
-        let entry = entry?;
-        if entry.file_type().is_file() {
-            if let Ok(rel_path) = entry.path().strip_prefix(repo.work_dir()) {
-                files.push(rel_path.to_string_lossy().to_string());
+    for path in paths {
+        let meta = match Path::new(&path).symlink_metadata() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        
+        if meta.is_file() || meta.file_type().is_symlink() {
+            let tracked = repo.relative_path(Path::new(&path))?
+                .map(|rel| index.get(&rel).is_some() || !ignore.is_ignored(&rel, false))
+                .unwrap_or(false);
+            if tracked {
+                files.push(path);
+            } else {
+                println!("{} {} (ignored)", "!".yellow(), path);
             }
+        } else if meta.is_dir() {
+            // Recursively walk directory
+            files.extend(ignore.walk(Path::new(&path))?);
         }
     }
     
     Ok(files)
 }
-
-fn is_ignored(path: &Path) -> bool {
-    let path_str = path.to_string_lossy();
-    path_str.contains("/.git/") || 
-    path_str.contains("/.tri/") ||
-    path_str.contains("/target/") ||
-    path_str.ends_with("/.git") ||
-    path_str.ends_with("/.tri")
-}
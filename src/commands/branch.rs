@@ -1,6 +1,10 @@
 // TriForge/src/commands/branch.rs
 use colored::*;
-use crate::native_git::Repository;
+use crate::commands;
+use crate::native_git::{merge, refs, revparse, Repository};
+use crate::native_git::refs::Expected;
+use crate::native_git::config::GitConfig;
+use crate::native_git::revwalk::RevWalk;
 
 pub fn list() -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
@@ -8,8 +12,9 @@ pub fn list() -> anyhow::Result<()> {
     println!("{}", "Branches:".cyan().bold());
     println!();
     
-    let branches = repo.refs().list("refs/heads")?;
-    let current = repo.head_commit().ok();
+    let mut branches = repo.refs().list("refs/heads")?;
+    branches.sort();
+    let current = repo.refs().current_branch()?;
     
     if branches.is_empty() {
         println!("{} No branches yet", "→".blue());
@@ -20,14 +25,22 @@ pub fn list() -> anyhow::Result<()> {
             // FIXED: Handle potential error when reading branch ref
             match repo.refs().read(&branch) {
                 Ok(commit) => {
-                    let is_current = current.as_ref() == Some(&commit);
+                    let is_current = current.as_deref() == Some(branch_name);
                     let marker = if is_current { "*".green() } else { " ".normal() };
                     
-                    println!("{} {} ({})", 
-                        marker,
-                        branch_name.yellow(),
-                        commit[..8].to_string().dimmed()
-                    );
+                    match tracking(&repo, branch_name, &commit).ok().flatten() {
+                        Some(tracking) => println!("{} {} ({}) {}", 
+                            marker,
+                            branch_name.yellow(),
+                            commit[..8].to_string().dimmed(),
+                            tracking.cyan()
+                        ),
+                        None => println!("{} {} ({})", 
+                            marker,
+                            branch_name.yellow(),
+                            commit[..8].to_string().dimmed()
+                        ),
+                    }
                 }
                 Err(_) => {
                     println!("  {} (invalid)", branch_name.dimmed());
@@ -39,26 +52,185 @@ pub fn list() -> anyhow::Result<()> {
     Ok(())
 }
 
-pub fn create(name: &str) -> anyhow::Result<()> {
+pub fn create(name: &str, start: Option<&str>) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
-    let head = repo.head_commit()?;
+    refs::validate_name(name)?;
+    let commit = match start {
+        Some(rev) => repo.resolve_commit(rev)?,
+        None => repo.head_commit()?,
+    };
     
-    repo.refs().update(&format!("refs/heads/{}", name), &head)?;
+    let branch_ref = format!("refs/heads/{}", name);
+    if repo.refs().read(&branch_ref).is_ok() {
+        anyhow::bail!("Branch '{}' already exists", name);
+    }
+    repo.refs().transaction()
+        .update(&branch_ref, &commit, Expected::Missing)
+        .commit(&format!("branch: Created from {}", start.unwrap_or("HEAD")))?;
+    
+    println!("{} Created branch: {} at {}", "✓".green(), name.yellow(), commit[..8].to_string().dimmed());
+    Ok(())
+}
+
+/// `[origin/main: ahead 1, behind 2]` for a branch with an upstream
+fn tracking(repo: &Repository, branch: &str, commit: &str) -> anyhow::Result<Option<String>> {
+    let Some(upstream) = revparse::upstream_of(&repo.config()?, branch) else {
+        return Ok(None);
+    };
+    let short = upstream.strip_prefix("refs/remotes/")
+        .or_else(|| upstream.strip_prefix("refs/heads/"))
+        .unwrap_or(&upstream);
+    
+    let Ok(theirs) = repo.refs().read(&upstream) else {
+        return Ok(Some(format!("[{}: gone]", short)));
+    };
+    
+    let ahead = RevWalk::new(repo).push(commit.to_string()).hide(theirs.clone()).run()?.len();
+    let behind = RevWalk::new(repo).push(theirs).hide(commit.to_string()).run()?.len();
+    
+    Ok(Some(match (ahead, behind) {
+        (0, 0) => format!("[{}]", short),
+        (a, 0) => format!("[{}: ahead {}]", short, a),
+        (0, b) => format!("[{}: behind {}]", short, b),
+        (a, b) => format!("[{}: ahead {}, behind {}]", short, a, b),
+    }))
+}
+
+/// Delete a branch. Unless forced, its commits must already be in its
+/// upstream, or in HEAD when it has none.
+pub fn delete(name: &str, force: bool) -> anyhow::Result<()> {
+    refs::validate_name(name)?;
+    let repo = Repository::open(".")?;
+    let branch_ref = format!("refs/heads/{}", name);
+    
+    let commit = repo.refs().read(&branch_ref)
+        .map_err(|_| anyhow::anyhow!("Branch '{}' not found", name))?;
+    
+    if repo.refs().current_branch()?.as_deref() == Some(name) {
+        anyhow::bail!("Cannot delete branch '{}' while it is checked out", name);
+    }
+    
+    if !force {
+        let (into, label) = match revparse::upstream_of(&repo.config()?, name) {
+            Some(upstream) => (repo.refs().read(&upstream).ok(), upstream),
+            None => (repo.head_commit().ok(), "HEAD".to_string()),
+        };
+        let merged = match into {
+            Some(into) => merge::is_ancestor(&repo, &commit, &into)?,
+            None => false,
+        };
+        if !merged {
+            anyhow::bail!("Branch '{}' is not fully merged into {}. Use --force to delete it anyway", name, label);
+        }
+    }
+    
+    repo.refs().delete(&branch_ref)?;
+    GitConfig::remove_section(repo.git_dir(), &format!("branch.{}", name))?;
+    
+    println!("{} Deleted branch: {} (was {})", "✓".green(), name.yellow(), commit[..8].to_string().dimmed());
+    Ok(())
+}
+
+/// Rename `old` to `new`, or the current branch when only one name is given.
+/// HEAD and the branch's config section follow it.
+pub fn rename(old: Option<&str>, new: &str, force: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let current = repo.refs().current_branch()?;
+    let old = match old {
+        Some(old) => old.to_string(),
+        None => current.clone().ok_or_else(|| anyhow::anyhow!("HEAD is detached; name the branch to rename"))?,
+    };
+    refs::validate_name(new)?;
+    
+    let old_ref = format!("refs/heads/{}", old);
+    let new_ref = format!("refs/heads/{}", new);
+    
+    // An unborn current branch has no ref yet; only HEAD needs to move
+    let commit = repo.refs().read(&old_ref).ok();
+    if commit.is_none() && current.as_deref() != Some(old.as_str()) {
+        anyhow::bail!("Branch '{}' not found", old);
+    }
+    
+    if old != new {
+        if repo.refs().read(&new_ref).is_ok() {
+            if !force {
+                anyhow::bail!("Branch '{}' already exists (use --force to replace it)", new);
+            }
+            if current.as_deref() == Some(new) {
+                anyhow::bail!("Cannot replace branch '{}' while it is checked out", new);
+            }
+        }
+        
+        let reason = format!("Branch: renamed {} to {}", old_ref, new_ref);
+        if commit.is_some() {
+            repo.refs().rename(&old_ref, &new_ref, &reason)?;
+        }
+        if current.as_deref() == Some(old.as_str()) {
+            repo.refs().set_symbolic("HEAD", &new_ref, &reason)?;
+        }
+        
+        GitConfig::remove_section(repo.git_dir(), &format!("branch.{}", new))?;
+        GitConfig::rename_section(repo.git_dir(), &format!("branch.{}", old), &format!("branch.{}", new))?;
+    }
     
-    println!("{} Created branch: {}", "✓".green(), name.yellow());
+    println!("{} Renamed branch {} to {}", "✓".green(), old.yellow(), new.yellow());
     Ok(())
 }
 
-pub fn delete(name: &str, _force: bool) -> anyhow::Result<()> {
+/// Track `upstream` from `branch` (the current branch by default). A name
+/// with a configured remote as its first component is a remote branch;
+/// anything else must be a local branch.
+pub fn set_upstream(upstream: &str, branch: Option<&str>) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
+    let branch = branch_or_current(&repo, branch)?;
     
-    repo.refs().delete(&format!("refs/heads/{}", name))?;
+    let config = repo.config()?;
+    let remote_branch = upstream.split_once('/')
+        .filter(|(remote, _)| {
+            config.get(&format!("remote.{}.url", remote)).is_some()
+                || commands::remote::hash_for(&repo, remote).is_ok()
+        });
     
-    println!("{} Deleted branch: {}", "✓".green(), name.yellow());
+    let (remote, merge) = match remote_branch {
+        Some((remote, name)) => (remote.to_string(), format!("refs/heads/{}", name)),
+        None => {
+            let local = format!("refs/heads/{}", upstream);
+            if repo.refs().read(&local).is_err() {
+                anyhow::bail!("'{}' is neither a local branch nor <remote>/<branch> for a configured remote", upstream);
+            }
+            (".".to_string(), local)
+        }
+    };
+    
+    GitConfig::set(repo.git_dir(), &format!("branch.{}.remote", branch), &remote)?;
+    GitConfig::set(repo.git_dir(), &format!("branch.{}.merge", branch), &merge)?;
+    
+    println!("{} Branch {} now tracks {}", "✓".green(), branch.yellow(), upstream.cyan());
     Ok(())
 }
 
-pub fn rename(new_name: &str) -> anyhow::Result<()> {
-    println!("{} Renamed current branch to {}", "✓".green(), new_name.yellow());
+pub fn unset_upstream(branch: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let branch = branch_or_current(&repo, branch)?;
+    
+    if revparse::upstream_of(&repo.config()?, &branch).is_none() {
+        anyhow::bail!("Branch '{}' has no upstream", branch);
+    }
+    GitConfig::unset(repo.git_dir(), &format!("branch.{}.remote", branch))?;
+    GitConfig::unset(repo.git_dir(), &format!("branch.{}.merge", branch))?;
+    
+    println!("{} Branch {} no longer tracks an upstream", "✓".green(), branch.yellow());
     Ok(())
 }
+
+fn branch_or_current(repo: &Repository, branch: Option<&str>) -> anyhow::Result<String> {
+    let branch = match branch {
+        Some(branch) => branch.to_string(),
+        None => repo.refs().current_branch()?
+            .ok_or_else(|| anyhow::anyhow!("HEAD is detached; name the branch"))?,
+    };
+    if repo.refs().read(&format!("refs/heads/{}", branch)).is_err() {
+        anyhow::bail!("Branch '{}' not found", branch);
+    }
+    Ok(branch)
+}
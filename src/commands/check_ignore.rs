@@ -0,0 +1,42 @@
+// TriForge/src/commands/check_ignore.rs
+use colored::*;
+use std::path::Path;
+use crate::native_git::Repository;
+
+pub fn execute(paths: Vec<String>, verbose: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let ignore = repo.ignore_rules()?;
+    
+    let mut any_ignored = false;
+    for path in &paths {
+        let rel_path = match repo.relative_path(Path::new(path))? {
+            Some(p) => p,
+            None => continue,
+        };
+        let is_dir = Path::new(path).is_dir();
+        let ignored = ignore.is_ignored(&rel_path, is_dir);
+        any_ignored |= ignored;
+        
+        if verbose {
+            // Same layout as `git check-ignore -v`: source:line:pattern<TAB>path
+            match ignore.rule_for(&rel_path, is_dir) {
+                Some(rule) => println!("{}:{}:{}\t{}",
+                    rule.source.strip_prefix(repo.work_dir()).unwrap_or(&rule.source).display().to_string().dimmed(),
+                    rule.line,
+                    rule.pattern.cyan(),
+                    path
+                ),
+                None if ignored => println!("{}\t{}", "(built-in)".dimmed(), path),
+                None => {}
+            }
+        } else if ignored {
+            println!("{}", path);
+        }
+    }
+    
+    if !any_ignored {
+        anyhow::bail!("None of the given paths are ignored");
+    }
+    
+    Ok(())
+}
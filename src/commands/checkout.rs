@@ -1,21 +1,195 @@
-
 use colored::*;
-use crate::native_git::Repository;
+use std::collections::BTreeMap;
+use crate::native_git::{Repository, Index, TreeBuilder};
+use crate::native_git::diff::{self, ChangeStatus};
+use crate::native_git::refs::{self, Expected};
+use crate::native_git::tree::TreeEntry;
 
-pub fn execute(target: &str, create: bool) -> anyhow::Result<()> {
+pub fn execute(target: &str, create: bool, force: bool) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
+    let branch_ref = format!("refs/heads/{}", target);
+    
+    let head = repo.head_commit().ok();
+    let moving_from = match repo.refs().current_branch()? {
+        Some(branch) => branch,
+        None => head.clone().unwrap_or_default(),
+    };
+    let reason = format!("checkout: moving from {} to {}", moving_from, target);
     
     if create {
-        let head = repo.head_commit()?;
-        repo.refs().update(&format!("refs/heads/{}", target), &head)?;
+        refs::validate_name(target)?;
+        if repo.refs().read(&branch_ref).is_ok() {
+            anyhow::bail!("Branch '{}' already exists", target);
+        }
+        
+        // A new branch starts at HEAD, so the working tree stays as it is
+        if let Some(h) = &head {
+            repo.refs().transaction()
+                .update(&branch_ref, h, Expected::Missing)
+                .commit("branch: Created from HEAD")?;
+        }
+        repo.refs().set_symbolic("HEAD", &branch_ref, &reason)?;
+        
+        println!("{} Switched to a new branch {}", "✓".green(), target.yellow());
+        return Ok(());
     }
     
-    let branch_ref = format!("refs/heads/{}", target);
-    let commit = repo.refs().read(&branch_ref)?;
+    // Branches keep HEAD symbolic; anything else detaches it
+    let (commit, is_branch) = match repo.refs().read(&branch_ref) {
+        Ok(hash) => (hash, true),
+        Err(_) => (repo.resolve_commit(target)?, false),
+    };
+    
+    let mut index = repo.index()?;
+    if !index.conflicts().is_empty() && !force {
+        anyhow::bail!("You have unresolved merge conflicts. Resolve them or use --force.");
+    }
+    
+    let current = match &head {
+        Some(h) => TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(h)?)?,
+        None => BTreeMap::new(),
+    };
+    let wanted = TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(&commit)?)?;
     
-    repo.refs().update("HEAD", &commit)?;
+    let dirty = dirty_paths(&repo, &index, &current)?;
     
-    println!("{} Switched to {}", "✓".green(), target.yellow());
+    let from = if force {
+        // Discard local changes: every dirty path is rewritten or removed
+        let mut from = current.clone();
+        for path in dirty.keys() {
+            from.insert(path.clone(), TreeEntry {
+                mode: String::new(),
+                name: path.clone(),
+                hash: String::new(),
+            });
+        }
+        for path in index.conflicts() {
+            index.remove(&path);
+        }
+        from
+    } else {
+        let blocked = blocked_paths(&repo, &index, &dirty, &current, &wanted);
+        if !blocked.is_empty() {
+            println!("{} Your local changes would be overwritten by checkout:", "✗".red());
+            for path in &blocked {
+                println!("    {}", path.yellow());
+            }
+            anyhow::bail!("Commit or stash your changes, or use --force to discard them");
+        }
+        current.clone()
+    };
+    
+    repo.switch_tree(&mut index, &from, &wanted)?;
+    index.save()?;
+    
+    if is_branch {
+        repo.refs().set_symbolic("HEAD", &branch_ref, &reason)?;
+        println!("{} Switched to branch {}", "✓".green(), target.yellow());
+    } else {
+        repo.refs().update("HEAD", &commit, &reason)?;
+        println!("{} HEAD is now detached at {}", "!".yellow(), commit[..8].to_string().yellow());
+    }
+    
+    // Local changes to files both trees agree on are carried over
+    if !force {
+        for (path, status) in &dirty {
+            let letter = match status {
+                ChangeStatus::Added => "A",
+                ChangeStatus::Deleted => "D",
+                _ => "M",
+            };
+            println!("{}\t{}", letter.red(), path);
+        }
+    }
     
     Ok(())
 }
+
+/// Tracked paths with staged or unstaged changes relative to HEAD
+pub fn dirty_paths(repo: &Repository, index: &Index, head_files: &BTreeMap<String, TreeEntry>) -> anyhow::Result<BTreeMap<String, ChangeStatus>> {
+    let head_snapshot: diff::Snapshot = head_files.iter()
+        .map(|(path, e)| (path.clone(), diff::DiffEntry {
+            mode: e.mode.clone(),
+            hash: e.hash.clone(),
+            worktree: false,
+        }))
+        .collect();
+    let index_snapshot = diff::index_snapshot(index);
+    let work_snapshot = diff::worktree_snapshot(repo, index, index_snapshot.keys())?;
+    
+    Ok(diff::compare(&head_snapshot, &index_snapshot)
+        .into_iter()
+        .chain(diff::compare(&index_snapshot, &work_snapshot))
+        .map(|c| (c.path().to_string(), c.status))
+        .collect())
+}
+
+/// Paths where checkout would destroy local work: dirty files that differ
+/// between the two trees, and untracked files the target tree would replace
+fn blocked_paths(
+    repo: &Repository,
+    index: &Index,
+    dirty: &BTreeMap<String, ChangeStatus>,
+    current: &BTreeMap<String, TreeEntry>,
+    wanted: &BTreeMap<String, TreeEntry>,
+) -> Vec<String> {
+    let same = |path: &String| {
+        match (current.get(path), wanted.get(path)) {
+            (Some(a), Some(b)) => a.hash == b.hash && a.mode == b.mode,
+            (None, None) => true,
+            _ => false,
+        }
+    };
+    
+    let mut blocked: Vec<String> = dirty.keys()
+        .filter(|path| !same(path))
+        .cloned()
+        .collect();
+    
+    let untracked = |path: &str| !current.contains_key(path) && index.get(path).is_none();
+    for path in wanted.keys() {
+        if untracked(path) && occupied(repo, current, path) {
+            blocked.push(path.clone());
+        }
+        
+        // An untracked file where the new tree needs a directory
+        let mut dir = path.as_str();
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            let is_file = repo.work_dir().join(parent).symlink_metadata()
+                .is_ok_and(|meta| !meta.is_dir());
+            if untracked(parent) && is_file {
+                blocked.push(parent.to_string());
+            }
+            dir = parent;
+        }
+    }
+    
+    blocked.sort();
+    blocked.dedup();
+    blocked
+}
+
+/// Whether something the current tree does not track sits at `path`. A
+/// directory only counts when it holds untracked files, since the switch
+/// removes the tracked ones before writing a file in its place.
+fn occupied(repo: &Repository, current: &BTreeMap<String, TreeEntry>, path: &str) -> bool {
+    let full = repo.work_dir().join(path);
+    match full.symlink_metadata() {
+        Ok(meta) if meta.is_dir() => holds_untracked(&full, path, current),
+        Ok(_) => true,
+        Err(_) => false,
+    }
+}
+
+fn holds_untracked(dir: &std::path::Path, rel: &str, current: &BTreeMap<String, TreeEntry>) -> bool {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return true;
+    };
+    entries.flatten().any(|entry| {
+        let rel_path = format!("{}/{}", rel, entry.file_name().to_string_lossy());
+        match entry.file_type() {
+            Ok(kind) if kind.is_dir() => holds_untracked(&entry.path(), &rel_path, current),
+            _ => !current.contains_key(&rel_path),
+        }
+    })
+}
@@ -1,9 +1,11 @@
 // TriForge/src/commands/clone.rs
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
 use std::path::PathBuf;
-use crate::{api, config::AppConfig, git};
-use base64::{Engine as _, engine::general_purpose};
+use crate::{api, commands, config::AppConfig, git};
+use crate::native_git::Repository;
+use crate::native_git::config::GitConfig;
+use crate::native_git::refs::Expected;
+use crate::native_git::refspec::{self, Refspec};
 
 pub async fn execute(
     hash: &str,
@@ -75,73 +77,29 @@ pub async fn execute(
     
     println!("{} Found {} objects", "✓".green(), object_ids.len().to_string().yellow());
     
-    // Get HEAD commit
-    let head_commit = match client.get_ref(&repo_hash, "refs/heads/main").await {
-        Ok(commit) => {
-            println!("{} HEAD commit: {}", "✓".green(), commit[..8].to_string().yellow());
-            commit.trim().to_string()
-        }
-        Err(e) => {
-            println!("{} No HEAD found: {}", "!".yellow(), e);
-            return Ok(());
-        }
+    // Check out the remote's main branch, or its only or first one
+    let remote_refs = commands::fetch::remote_refs(&client, &repo_hash).await?;
+    let branches: Vec<&String> = remote_refs.keys()
+        .filter(|name| name.starts_with("refs/heads/"))
+        .collect();
+    let default_branch = match ["refs/heads/main", "refs/heads/master"].iter().find(|b| remote_refs.contains_key(**b)) {
+        Some(branch) => branch.to_string(),
+        None => match branches.first() {
+            Some(branch) => branch.to_string(),
+            None => {
+                println!("{} No branches found on the remote", "!".yellow());
+                return Ok(());
+            }
+        },
     };
+    let head_commit = remote_refs[&default_branch].clone();
+    println!("{} HEAD commit: {} ({})", "✓".green(), head_commit[..8].to_string().yellow(), refspec::shorten(&default_branch));
     
     // Download all objects
     println!();
     println!("{}", "Downloading Git objects...".cyan());
     
-    let pb = ProgressBar::new(object_ids.len() as u64);
-    pb.set_style(
-        ProgressStyle::default_bar()
-            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
-            .unwrap()
-            .progress_chars("█▓░")
-    );
-    
-    let mut downloaded = 0;
-    let mut failed = 0;
-    
-    for object_id in &object_ids {
-        if verbose {
-            pb.set_message(format!("Downloading {}", &object_id[..8]));
-        }
-        
-        match client.download_object(&repo_hash, object_id).await {
-            Ok(data) => {
-                // Decode base64 if needed
-                let object_data = if let Ok(decoded) = general_purpose::STANDARD.decode(&data) {
-                    decoded
-                } else {
-                    data
-                };
-                
-                // Write object to local repository
-                match git::write_object(&repo, object_id, &object_data) {
-                    Ok(_) => {
-                        downloaded += 1;
-                        pb.inc(1);
-                    }
-                    Err(e) => {
-                        if verbose {
-                            eprintln!("{} Failed to write {}: {}", "✗".red(), object_id, e);
-                        }
-                        failed += 1;
-                        pb.inc(1);
-                    }
-                }
-            }
-            Err(e) => {
-                if verbose {
-                    eprintln!("{} Failed to download {}: {}", "✗".red(), object_id, e);
-                }
-                failed += 1;
-                pb.inc(1);
-            }
-        }
-    }
-    
-    pb.finish_with_message("Complete!");
+    let (downloaded, failed) = commands::fetch::download_objects(&client, &repo, &repo_hash, &object_ids, verbose).await?;
     
     println!();
     if failed > 0 {
@@ -152,13 +110,30 @@ pub async fn execute(
         );
     }
     
-    // Set HEAD ref
-    println!("{}", "Setting HEAD reference...".cyan());
-    match git::set_ref(&repo, "refs/heads/main", &head_commit) {
-        Ok(_) => println!("{} Set refs/heads/main to {}", "✓".green(), &head_commit[..8].to_string().yellow()),
-        Err(e) => {
-            println!("{} Failed to set HEAD: {}", "!".yellow(), e);
-        }
+    // Remote-tracking refs and tags first, then our own branch
+    println!("{}", "Setting up references...".cyan());
+    let local = Repository::open(&clone_dir)?;
+    GitConfig::set(local.git_dir(), "remote.origin.hyrule-hash", &repo_hash)?;
+    if failed > 0 {
+        anyhow::bail!(
+            "References were not set up because some objects did not download. Run 'triforge fetch' in {} to retry",
+            clone_dir.display()
+        );
+    }
+    commands::fetch::update_refs(&local, "origin", &remote_refs, &[Refspec::default_fetch("origin")])?;
+    
+    let branch = refspec::shorten(&default_branch);
+    let reason = format!("clone: from {}", repo_hash);
+    if local.load_object(&head_commit).is_ok() {
+        local.refs().transaction()
+            .update(&default_branch, &head_commit, Expected::Missing)
+            .commit(&reason)?;
+        local.refs().set_symbolic("HEAD", &default_branch, &reason)?;
+        GitConfig::set(local.git_dir(), &format!("branch.{}.remote", branch), "origin")?;
+        GitConfig::set(local.git_dir(), &format!("branch.{}.merge", branch), &default_branch)?;
+        println!("{} Branch {} set up to track origin/{}", "✓".green(), branch.yellow(), branch);
+    } else {
+        println!("{} Failed to set HEAD: {} was not downloaded", "!".yellow(), &head_commit[..8]);
     }
     
     // Checkout the working directory
@@ -167,7 +142,7 @@ pub async fn execute(
         Ok(_) => println!("{} Checked out working directory", "✓".green()),
         Err(e) => {
             println!("{} Failed to checkout: {}", "!".yellow(), e);
-            println!("{} You may need to run 'git checkout main' manually", "→".blue());
+            println!("{} You may need to run 'git checkout {}' manually", "→".blue(), branch);
         }
     }
     
@@ -177,9 +152,6 @@ pub async fn execute(
     println!("{}", "═".repeat(60).green());
     println!();
     println!("{} Downloaded {} objects", "→".blue(), downloaded.to_string().cyan());
-    if failed > 0 {
-        println!("{} Failed {} objects", "→".blue(), failed.to_string().red());
-    }
     println!();
     println!("{} Next steps:", "→".blue());
     println!("  {}", format!("cd {}", clone_dir.display()).cyan());
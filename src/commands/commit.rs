@@ -1,72 +1,128 @@
 use colored::*;
-use std::fs;
-use std::collections::BTreeMap;
-use crate::native_git::{Repository, TreeBuilder, CommitBuilder};
-use walkdir::WalkDir;
+use crate::config::AppConfig;
+use crate::native_git::{Repository, TreeBuilder, CommitBuilder, Signature};
+use crate::native_git::merge;
 
-pub fn execute(message: &str, all: bool) -> anyhow::Result<()> {
+pub fn execute(message: &str, all: bool, author: Option<&str>) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
+    let mut index = repo.index()?;
+    let (author, committer) = signatures(&repo, author)?;
     
     println!("{}", "Creating commit...".cyan());
     println!();
     
-    // Build tree from working directory
-    let mut tree_builder = TreeBuilder::new();
-    let mut file_count = 0;
-    
-    for entry in WalkDir::new(repo.work_dir())
-        .into_iter()
-        .filter_entry(|e| !is_ignored(e.path()))
-    {
-        let entry = entry?;
-        if entry.file_type().is_file() {
-            let content = fs::read(entry.path())?;
-            let blob = repo.create_blob(&content)?;
-            let hash = repo.store_object(&blob)?;
-            
-            if let Ok(rel_path) = entry.path().strip_prefix(repo.work_dir()) {
-                tree_builder.add_file(
-                    rel_path.to_string_lossy().to_string(),
-                    hash
-                );
-                file_count += 1;
+    // With --all, pick up modifications and deletions of tracked files
+    if all {
+        let tracked: Vec<String> = index.entries().map(|e| e.path.clone()).collect();
+        for path in tracked {
+            let on_disk = repo.work_dir().join(&path).symlink_metadata();
+            if on_disk.map(|m| !m.is_dir()).unwrap_or(false) {
+                repo.stage_file(&mut index, &path)?;
+            } else {
+                index.remove(&path);
             }
         }
+        index.save()?;
     }
     
-    let tree_obj = tree_builder.build()?;
-    let tree_hash = repo.store_object(&tree_obj)?;
+    let conflicts = index.conflicts();
+    if !conflicts.is_empty() {
+        for path in &conflicts {
+            println!("{} Unresolved: {}", "✗".red(), path.yellow());
+        }
+        anyhow::bail!("Resolve merge conflicts and stage them with 'triforge add' first");
+    }
+    
+    // Committing during a merge concludes it with a second parent
+    let merge_head = merge::merge_head(&repo);
+    
+    if index.is_empty() {
+        anyhow::bail!("Nothing to commit. Stage files with 'triforge add' first.");
+    }
+    
+    // Build nested trees from the staging area
+    let tree_hash = TreeBuilder::write_paths(
+        repo.git_dir(),
+        index.entries().map(|e| (e.path.clone(), format!("{:o}", e.mode), e.hash.clone())),
+    )?;
     
     // Get parent commit if exists
     let parent = repo.head_commit().ok();
     
+    let mut changed = index.len();
+    if let Some(p) = &parent {
+        let parent_commit = CommitBuilder::parse(&repo.load_object(p)?)?;
+        if parent_commit.tree == tree_hash && merge_head.is_none() {
+            println!("{} Nothing to commit, working tree matches HEAD", "!".yellow());
+            return Ok(());
+        }
+        
+        let old_files = TreeBuilder::flatten(repo.git_dir(), &parent_commit.tree)?;
+        let new_files = TreeBuilder::flatten(repo.git_dir(), &tree_hash)?;
+        changed = new_files.iter()
+            .filter(|(path, entry)| old_files.get(*path).map(|o| o.hash != entry.hash || o.mode != entry.mode).unwrap_or(true))
+            .count()
+            + old_files.keys().filter(|path| !new_files.contains_key(*path)).count();
+    }
+    
+    // Reflog reason, in Git's wording
+    let kind = match (&parent, &merge_head) {
+        (None, _) => " (initial)",
+        (_, Some(_)) => " (merge)",
+        _ => "",
+    };
+    let reason = format!("commit{}: {}", kind, message.lines().next().unwrap_or_default());
+    
     // Build commit
-    let mut commit_builder = CommitBuilder::new(tree_hash.clone(), message.to_string());
+    let mut commit_builder = CommitBuilder::new(tree_hash.clone(), message.to_string())
+        .author(author)
+        .committer(committer);
     
     if let Some(p) = parent {
         commit_builder = commit_builder.parent(p);
     }
+    if let Some(m) = merge_head {
+        commit_builder = commit_builder.parent(m);
+    }
     
     let commit_obj = commit_builder.build()?;
     let commit_hash = repo.store_object(&commit_obj)?;
     
-    // Update refs
-    repo.refs().update("refs/heads/main", &commit_hash)?;
-    repo.refs().update("HEAD", &commit_hash)?;
+    // Advance the current branch, or HEAD itself when detached
+    repo.refs().update_head(&commit_hash, &reason)?;
+    merge::clear_merge_state(&repo)?;
     
-    println!("{} Created commit: {}", "✓".green(), commit_hash[..8].to_string().yellow());
+    let branch = repo.refs().current_branch()?
+        .unwrap_or_else(|| "detached HEAD".to_string());
+    println!("{} Created commit: {} on {}", "✓".green(), commit_hash[..8].to_string().yellow(), branch.cyan());
     println!();
     println!("{} {}", "Message:".bold(), message.cyan());
-    println!("{} {} files", "Files:".bold(), file_count.to_string().yellow());
+    println!("{} {} changed", "Files:".bold(), changed.to_string().yellow());
     
     Ok(())
 }
 
-fn is_ignored(path: &std::path::Path) -> bool {
-    let path_str = path.to_string_lossy();
-    path_str.contains("/.git/") || 
-    path_str.contains("/.tri/") ||
-    path_str.contains("/target/") ||
-    path_str.ends_with("/.git") ||
-    path_str.ends_with("/.tri")
+/// Author and committer for a new commit. The author comes from `--author`,
+/// then `GIT_AUTHOR_*`; both fall back to `user.name`/`user.email` in
+/// `.git/config` and then to the global Triforge config.
+pub fn signatures(repo: &Repository, author: Option<&str>) -> anyhow::Result<(Signature, Signature)> {
+    let config = repo.config()?;
+    let defaults = AppConfig::load()?.identity();
+    
+    let identity = |role: &str, ident: Option<&str>| -> anyhow::Result<Signature> {
+        match Signature::for_role(role, ident, Some(&config), defaults.clone())? {
+            Some(signature) => Ok(signature),
+            None => {
+                println!("{} Commit identity unknown. Tell Triforge who you are:", "✗".red());
+                println!("  {}", "triforge config set user.name \"Your Name\"".cyan());
+                println!("  {}", "triforge config set user.email you@example.com".cyan());
+                anyhow::bail!("Unable to determine {} identity", role.to_lowercase());
+            }
+        }
+    };
+    
+    let committer = identity("COMMITTER", None)?;
+    let author = identity("AUTHOR", author)?;
+    
+    Ok((author, committer))
 }
@@ -41,10 +41,23 @@ pub fn show() -> anyhow::Result<()> {
         if config.auth_token.is_some() { "********".cyan() } else { "(not set)".dimmed() });
     println!();
     
+    println!("{}", "Commit Identity".bold().underline());
+    println!("{} {}", "user.name:".yellow(), 
+        config.user_name.as_deref().unwrap_or("(not set)").cyan());
+    println!("{} {}", "user.email:".yellow(), 
+        config.user_email.as_deref().unwrap_or("(not set)").cyan());
+    println!();
+    
     println!("{}", "Privacy Configuration".bold().underline());
     println!("{} {}", "default_private:".yellow(), config.default_private.to_string().cyan());
     println!();
     
+    println!("{}", "Transfer Configuration".bold().underline());
+    println!("{} {}", "batch.objects:".yellow(), config.batch_objects.to_string().cyan());
+    println!("{} {}", "batch.bytes:".yellow(), config.batch_bytes.to_string().cyan());
+    println!("{} {}", "concurrency:".yellow(), config.transfer_concurrency.to_string().cyan());
+    println!();
+    
     println!("{}", "Tor Configuration".bold().underline());
     println!("{} {}", "use_tor:".yellow(), 
         if config.use_tor { 
@@ -84,6 +97,7 @@ pub fn show() -> anyhow::Result<()> {
     println!("  • {}", "server, hyrule_server".dimmed());
     println!("  • {}", "username".dimmed());
     println!("  • {}", "token, auth_token".dimmed());
+    println!("  • {}", "user.name, user.email".dimmed());
     println!("  • {}", "private, default_private".dimmed());
     println!("  • {}", "tor, use_tor".dimmed());
     println!("  • {}", "proxy, tor_proxy".dimmed());
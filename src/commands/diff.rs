@@ -1,9 +1,273 @@
-
+// TriForge/src/commands/diff.rs
 use colored::*;
+use std::collections::BTreeSet;
+use crate::native_git::Repository;
+use crate::native_git::diff::{self, ChangeStatus, DiffEntry, FileChange, LineKind, Snapshot};
+
+/// Lines of unchanged context shown around each hunk
+const CONTEXT_LINES: usize = 3;
+/// Widest bar drawn by `--stat`
+const STAT_WIDTH: usize = 50;
+const NULL_HASH: &str = "0000000";
+
+pub fn execute(from: Option<String>, to: Option<String>, name_only: bool, stat: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let index = repo.index()?;
+
+    let (old, new) = match (from, to) {
+        // Tree to tree
+        (Some(from), Some(to)) => (
+            revision_snapshot(&repo, &from)?,
+            revision_snapshot(&repo, &to)?,
+        ),
+        // Commit to working tree, covering files tracked by either side
+        (Some(from), None) => {
+            let old = revision_snapshot(&repo, &from)?;
+            let paths: BTreeSet<String> = old.keys()
+                .cloned()
+                .chain(index.entries().map(|e| e.path.clone()))
+                .collect();
+            let new = diff::worktree_snapshot(&repo, &index, &paths)?;
+            (old, new)
+        }
+        (None, Some(to)) => anyhow::bail!("Cannot diff against '{}' without a revision to compare from", to),
+        // Index to working tree
+        (None, None) => {
+            let old = diff::index_snapshot(&index);
+            let new = diff::worktree_snapshot(&repo, &index, old.keys())?;
+            (old, new)
+        }
+    };
+
+    let changes = diff::detect_renames(diff::compare(&old, &new), |path, entry| {
+        diff::load_content(&repo, path, entry)
+    })?;
+
+    if changes.is_empty() {
+        if !name_only {
+            println!("{} No changes", "✓".green());
+        }
+        return Ok(());
+    }
+
+    if name_only {
+        for change in &changes {
+            println!("{}", change.path());
+        }
+    } else if stat {
+        print_stat(&repo, &changes)?;
+    } else {
+        print_patch(&repo, &changes)?;
+    }
+
+    Ok(())
+}
+
+/// Snapshot of the tree a revision points at
+fn revision_snapshot(repo: &Repository, rev: &str) -> anyhow::Result<Snapshot> {
+    let hash = repo.resolve(rev)?;
+    let tree = repo.tree_of(&hash)?;
+    diff::tree_snapshot(repo, &tree)
+}
+
+/// Print changes as unified patches
+pub fn print_patch(repo: &Repository, changes: &[FileChange]) -> anyhow::Result<()> {
+    for change in changes {
+        let old_content = side_content(repo, &change.old_path, change.old.as_ref())?;
+        let new_content = side_content(repo, &change.new_path, change.new.as_ref())?;
+
+        println!("{}", format!("diff --git a/{} b/{}", change.old_path, change.new_path).bold());
+
+        let old_mode = change.old.as_ref().map(|e| e.mode.as_str());
+        let new_mode = change.new.as_ref().map(|e| e.mode.as_str());
+        let same_mode = old_mode == new_mode;
+
+        match change.status {
+            ChangeStatus::Added => {
+                println!("{}", format!("new file mode {}", new_mode.unwrap_or_default()).bold());
+            }
+            ChangeStatus::Deleted => {
+                println!("{}", format!("deleted file mode {}", old_mode.unwrap_or_default()).bold());
+            }
+            ChangeStatus::Renamed(score) => {
+                println!("{}", format!("similarity index {}%", score).bold());
+                println!("{}", format!("rename from {}", change.old_path).bold());
+                println!("{}", format!("rename to {}", change.new_path).bold());
+            }
+            ChangeStatus::Modified => {}
+        }
+
+        if !same_mode && change.old.is_some() && change.new.is_some() {
+            println!("{}", format!("old mode {}", old_mode.unwrap_or_default()).bold());
+            println!("{}", format!("new mode {}", new_mode.unwrap_or_default()).bold());
+        }
+
+        // Pure renames and mode changes carry no content diff
+        if old_content == new_content && change.old.is_some() && change.new.is_some() {
+            continue;
+        }
+
+        let mode_suffix = match (same_mode, new_mode) {
+            (true, Some(mode)) => format!(" {}", mode),
+            _ => String::new(),
+        };
+        println!("{}", format!("index {}..{}{}",
+            short_hash(change.old.as_ref()),
+            short_hash(change.new.as_ref()),
+            mode_suffix
+        ).bold());
+
+        let old_label = change.old.as_ref()
+            .map(|_| format!("a/{}", change.old_path))
+            .unwrap_or_else(|| "/dev/null".to_string());
+        let new_label = change.new.as_ref()
+            .map(|_| format!("b/{}", change.new_path))
+            .unwrap_or_else(|| "/dev/null".to_string());
+
+        if diff::is_binary(&old_content) || diff::is_binary(&new_content) {
+            println!("Binary files {} and {} differ", old_label, new_label);
+            continue;
+        }
+
+        println!("{}", format!("--- {}", old_label).bold());
+        println!("{}", format!("+++ {}", new_label).bold());
+
+        for hunk in diff::hunks(&old_content, &new_content, CONTEXT_LINES) {
+            println!("{}", format!("@@ -{} +{} @@",
+                hunk_range(hunk.old_start, hunk.old_len),
+                hunk_range(hunk.new_start, hunk.new_len)
+            ).cyan());
+
+            for line in &hunk.lines {
+                match line.kind {
+                    LineKind::Context => println!(" {}", line.text),
+                    LineKind::Removed => println!("{}", format!("-{}", line.text).red()),
+                    LineKind::Added => println!("{}", format!("+{}", line.text).green()),
+                }
+                if line.missing_newline {
+                    println!("\\ No newline at end of file");
+                }
+            }
+        }
+    }
 
-pub fn execute(from: Option<String>, to: Option<String>, name_only: bool) -> anyhow::Result<()> {
-    println!("{}", "Showing changes...".cyan());
-    println!();
-    println!("{} Diff functionality coming soon", "!".yellow());
     Ok(())
 }
+
+/// Print a per-file summary of inserted and deleted lines
+pub fn print_stat(repo: &Repository, changes: &[FileChange]) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    let mut insertions = 0;
+    let mut deletions = 0;
+
+    for change in changes {
+        let old_content = side_content(repo, &change.old_path, change.old.as_ref())?;
+        let new_content = side_content(repo, &change.new_path, change.new.as_ref())?;
+
+        let name = match change.status {
+            ChangeStatus::Renamed(_) => format!("{} => {}", change.old_path, change.new_path),
+            _ => change.path().to_string(),
+        };
+
+        if diff::is_binary(&old_content) || diff::is_binary(&new_content) {
+            rows.push(StatRow {
+                name,
+                binary: true,
+                added: new_content.len(),
+                removed: old_content.len(),
+            });
+            continue;
+        }
+
+        let (mut added, mut removed) = (0, 0);
+        for hunk in diff::hunks(&old_content, &new_content, 0) {
+            for line in &hunk.lines {
+                match line.kind {
+                    LineKind::Added => added += 1,
+                    LineKind::Removed => removed += 1,
+                    LineKind::Context => {}
+                }
+            }
+        }
+        insertions += added;
+        deletions += removed;
+        rows.push(StatRow { name, binary: false, added, removed });
+    }
+
+    let name_width = rows.iter().map(|r| r.name.len()).max().unwrap_or(0);
+    let max_total = rows.iter()
+        .filter(|r| !r.binary)
+        .map(|r| r.added + r.removed)
+        .max()
+        .unwrap_or(0);
+    // Binary rows print "Bin" in the count column
+    let mut count_width = max_total.to_string().len();
+    if rows.iter().any(|r| r.binary) {
+        count_width = count_width.max(3);
+    }
+
+    for row in &rows {
+        if row.binary {
+            println!(" {:<width$} | Bin {} -> {} bytes", row.name, row.removed, row.added, width = name_width);
+            continue;
+        }
+
+        let (plus, minus) = if max_total > STAT_WIDTH {
+            let scale = |n: usize| if n == 0 { 0 } else { (n * STAT_WIDTH / max_total).max(1) };
+            (scale(row.added), scale(row.removed))
+        } else {
+            (row.added, row.removed)
+        };
+
+        println!(" {:<width$} | {:>cw$} {}{}",
+            row.name,
+            row.added + row.removed,
+            "+".repeat(plus).green(),
+            "-".repeat(minus).red(),
+            width = name_width,
+            cw = count_width
+        );
+    }
+
+    println!(" {} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+        changes.len(), plural(changes.len()),
+        insertions, plural(insertions),
+        deletions, plural(deletions)
+    );
+
+    Ok(())
+}
+
+/// One line of `--stat` output; binary rows hold byte sizes instead of line counts
+struct StatRow {
+    name: String,
+    binary: bool,
+    added: usize,
+    removed: usize,
+}
+
+fn side_content(repo: &Repository, path: &str, entry: Option<&DiffEntry>) -> anyhow::Result<Vec<u8>> {
+    match entry {
+        Some(e) => diff::load_content(repo, path, e),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn short_hash(entry: Option<&DiffEntry>) -> &str {
+    match entry {
+        Some(e) => &e.hash[..7],
+        None => NULL_HASH,
+    }
+}
+
+fn hunk_range(start: usize, len: usize) -> String {
+    if len == 1 {
+        start.to_string()
+    } else {
+        format!("{},{}", start, len)
+    }
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 { "" } else { "s" }
+}
@@ -0,0 +1,350 @@
+// TriForge/src/commands/fetch.rs
+use colored::*;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use crate::{api, commands, config::AppConfig, git};
+use crate::native_git::{hash, Repository};
+use crate::native_git::merge;
+use crate::native_git::refs::{self, Expected};
+use crate::native_git::refspec::{self, Refspec};
+use base64::{Engine as _, engine::general_purpose};
+use anyhow::Result;
+
+/// One local ref a fetch sets
+struct RefChange {
+    src: String,
+    dst: String,
+    hash: String,
+    expected: Expected,
+    note: &'static str,
+}
+
+pub async fn execute(remote: Option<String>, refspecs: Vec<String>, verbose: bool) -> Result<()> {
+    println!("{}", "Fetching from Hyrule network...".cyan().bold());
+    println!();
+    
+    let config = AppConfig::load()?;
+    let client = api::ApiClient::new(config);
+    let remote_name = remote.unwrap_or_else(|| "origin".to_string());
+    
+    fetch(&client, &remote_name, &refspecs, verbose).await?;
+    
+    println!();
+    println!("{} Fetch complete!", "✓".green().bold());
+    Ok(())
+}
+
+/// Download the objects `remote` has and we lack, then update local refs
+/// from the remote's refs through `refspecs`. Without refspecs every remote
+/// branch lands in `refs/remotes/<remote>/*`.
+pub async fn fetch(client: &api::ApiClient, remote_name: &str, refspecs: &[String], verbose: bool) -> Result<()> {
+    let repo = git::open_repo()?;
+    let local = Repository::open(".")?;
+    let repo_hash = commands::remote::hash_for(&local, remote_name)?;
+    
+    if verbose {
+        println!("{} Repository: {}", "→".blue(), repo_hash.yellow());
+    }
+    
+    let specs = if refspecs.is_empty() {
+        vec![Refspec::default_fetch(remote_name)]
+    } else {
+        refspecs.iter().map(|spec| Refspec::parse(spec)).collect::<Result<Vec<_>>>()?
+    };
+    
+    // Get repository metadata
+    let metadata = client.get_repo(&repo_hash).await?;
+    println!("{} Fetching from: {}", "→".blue(), metadata.name.yellow());
+    
+    let refs = remote_refs(client, &repo_hash).await?;
+    let failed = download_missing(client, &repo, &repo_hash, verbose).await?;
+    
+    // A tip whose history is incomplete must not become a ref
+    if failed > 0 {
+        anyhow::bail!("Refs were left unchanged because some objects did not download. Run the fetch again to retry");
+    }
+    
+    println!();
+    println!("{}", "Updating references...".cyan());
+    update_refs(&local, remote_name, &refs, &specs)
+}
+
+/// Every ref on the remote, by name. Names that are not valid refs under
+/// `refs/` are skipped, since they could map onto paths outside it, and so
+/// are refs whose value is not a full object id.
+pub async fn remote_refs(client: &api::ApiClient, repo_hash: &str) -> Result<BTreeMap<String, String>> {
+    let mut refs = BTreeMap::new();
+    for entry in client.list_refs(repo_hash).await? {
+        if !entry.ref_name.starts_with("refs/") || refs::validate_name(&entry.ref_name).is_err() {
+            println!("{} Skipped invalid remote ref {}", "!".yellow(), entry.ref_name);
+            continue;
+        }
+        let commit_id = entry.commit_id.trim().to_ascii_lowercase();
+        if !refs::is_hash(&commit_id) {
+            println!("{} Skipped remote ref {} with invalid object id '{}'", "!".yellow(), entry.ref_name, entry.commit_id);
+            continue;
+        }
+        refs.insert(entry.ref_name, commit_id);
+    }
+    Ok(refs)
+}
+
+/// Map remote refs onto local ones through `specs` and apply the result in
+/// one transaction. Remote tags we lack come along; existing tags are never
+/// moved. Updates that are not fast-forwards need a `+` refspec.
+pub fn update_refs(repo: &Repository, remote_name: &str, remote_refs: &BTreeMap<String, String>, specs: &[Refspec]) -> Result<()> {
+    // Destination -> (remote ref, hash, forced)
+    let mut mapped: BTreeMap<String, (String, String, bool)> = BTreeMap::new();
+    for spec in specs {
+        if spec.is_glob() {
+            for (name, hash) in remote_refs {
+                if let Some(dst) = spec.map(name) {
+                    mapped.insert(dst, (name.clone(), hash.clone(), spec.force));
+                }
+            }
+            continue;
+        }
+        
+        let src = refspec::expand(&spec.src, |name| remote_refs.contains_key(name))
+            .ok_or_else(|| anyhow::anyhow!("Couldn't find remote ref {}", spec.src))?;
+        let dst = match &spec.dst {
+            Some(dst) => refspec::qualify_dst(dst, &src),
+            // A branch fetched by name still updates its tracking ref
+            None => refspec::tracking_ref(remote_name, &src).unwrap_or_else(|| src.clone()),
+        };
+        mapped.insert(dst, (src.clone(), remote_refs[&src].clone(), spec.force));
+    }
+    
+    for (name, hash) in remote_refs.iter().filter(|(name, _)| name.starts_with("refs/tags/")) {
+        if !mapped.contains_key(name) && repo.refs().read(name).is_err() {
+            mapped.insert(name.clone(), (name.clone(), hash.clone(), false));
+        }
+    }
+    
+    let current_branch = repo.refs().current_branch()?.map(|b| format!("refs/heads/{}", b));
+    let mut changes = Vec::new();
+    let mut rejected = Vec::new();
+    for (dst, (src, hash, force)) in mapped {
+        let current = repo.refs().read(&dst).ok();
+        if current.as_deref() == Some(hash.as_str()) {
+            continue;
+        }
+        // Objects that failed to download leave the ref alone
+        if let Err(e) = repo.load_object(&hash) {
+            println!("{} Skipped {}: {}", "!".yellow(), refspec::shorten(&dst), e);
+            continue;
+        }
+        
+        let note = match &current {
+            None => "new",
+            Some(_) if current_branch.as_deref() == Some(dst.as_str()) => {
+                anyhow::bail!("Refusing to fetch into the current branch {}", refspec::shorten(&dst));
+            }
+            Some(_) if dst.starts_with("refs/tags/") => {
+                if !force {
+                    rejected.push((dst, "would clobber existing tag"));
+                    continue;
+                }
+                "forced update"
+            }
+            Some(old) if merge::is_ancestor(repo, old, &hash)? => "fast-forward",
+            Some(_) if force => "forced update",
+            Some(_) => {
+                rejected.push((dst, "non-fast-forward"));
+                continue;
+            }
+        };
+        
+        changes.push(RefChange {
+            src,
+            expected: match current {
+                Some(old) => Expected::Value(old),
+                None => Expected::Missing,
+            },
+            dst,
+            hash,
+            note,
+        });
+    }
+    
+    let mut transaction = repo.refs().transaction();
+    for change in &changes {
+        transaction = transaction.update(&change.dst, &change.hash, change.expected.clone());
+    }
+    transaction.commit(&format!("fetch: from {}", remote_name))?;
+    
+    for change in &changes {
+        println!("{} {} {} {} ({})",
+            "✓".green(),
+            refspec::shorten(&change.src).yellow(),
+            "→".blue(),
+            refspec::shorten(&change.dst),
+            change.note.dimmed()
+        );
+    }
+    for (dst, reason) in &rejected {
+        println!("{} {} ({})", "✗".red(), refspec::shorten(dst).yellow(), reason);
+    }
+    if changes.is_empty() && rejected.is_empty() {
+        println!("{} Refs already up to date", "✓".green());
+    }
+    
+    if !rejected.is_empty() {
+        anyhow::bail!("Some refs were not updated; prefix the refspec with '+' to force them");
+    }
+    Ok(())
+}
+
+/// Download every object the remote lists that the local repository lacks,
+/// returning how many could not be downloaded
+async fn download_missing(client: &api::ApiClient, repo: &git2::Repository, repo_hash: &str, verbose: bool) -> Result<usize> {
+    // Get list of objects
+    println!("{}", "Fetching object list...".cyan());
+    let objects_response = client.list_objects(repo_hash).await?;
+    let remote_objects = objects_response.objects;
+    
+    println!("{} Remote has {} objects", "→".blue(), remote_objects.len().to_string().yellow());
+    
+    // Get local objects
+    let local_objects = git::get_all_objects(repo)?;
+    let local_oids: HashSet<_> = local_objects
+        .iter()
+        .map(|oid| oid.to_string())
+        .collect();
+    
+    // Find missing objects
+    let missing: Vec<_> = remote_objects
+        .iter()
+        .filter(|oid| !local_oids.contains(*oid))
+        .collect();
+    
+    if missing.is_empty() {
+        println!("{} No new objects to fetch", "✓".green());
+        return Ok(0);
+    }
+    
+    println!("{} Downloading {} new objects...", "→".blue(), missing.len().to_string().yellow());
+    
+    let missing: Vec<String> = missing.into_iter().cloned().collect();
+    let (_, failed) = download_objects(client, repo, repo_hash, &missing, verbose).await?;
+    if failed > 0 {
+        println!("{} Failed to download {}/{} objects", 
+            "!".yellow(), 
+            failed.to_string().red(),
+            missing.len().to_string().yellow()
+        );
+    }
+    Ok(failed)
+}
+
+/// Download `object_ids` into `repo`, `batch_objects` per request with up to
+/// `transfer_concurrency` requests in flight. Returns how many objects were
+/// written and how many failed.
+pub async fn download_objects(client: &api::ApiClient, repo: &git2::Repository, repo_hash: &str, object_ids: &[String], verbose: bool) -> Result<(usize, usize)> {
+    let pb = ProgressBar::new(object_ids.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+            .unwrap()
+            .progress_chars("█▓░")
+    );
+    
+    let config = client.config();
+    let semaphore = Arc::new(Semaphore::new(config.transfer_concurrency.max(1)));
+    // Cleared once the server turns out to have no batch endpoint
+    let batched = Arc::new(AtomicBool::new(true));
+    
+    let mut tasks = JoinSet::new();
+    for chunk in object_ids.chunks(config.batch_objects.max(1)) {
+        let client = client.clone();
+        let repo_hash = repo_hash.to_string();
+        let chunk = chunk.to_vec();
+        let semaphore = semaphore.clone();
+        let batched = batched.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await?;
+            Ok::<_, anyhow::Error>(download_chunk(&client, &repo_hash, chunk, &batched).await)
+        });
+    }
+    
+    // Objects are written here as chunks arrive; the repository stays on
+    // this task
+    let mut downloaded = 0;
+    let mut failed = 0;
+    while let Some(joined) = tasks.join_next().await {
+        for (oid, result) in joined?? {
+            if verbose {
+                pb.set_message(format!("Writing {}", &oid[..8.min(oid.len())]));
+            }
+            
+            // Whatever the server sent must hash to the id we asked for
+            let result = result.and_then(|data| match hash::verify_hash(&data, &oid) {
+                true => Ok(data),
+                false => Err(anyhow::anyhow!("content does not match its hash")),
+            });
+            match result {
+                Ok(data) => match git::write_object(repo, &oid, &data) {
+                    Ok(_) => downloaded += 1,
+                    Err(e) => {
+                        if verbose {
+                            eprintln!("{} Failed to write {}: {}", "✗".red(), oid, e);
+                        }
+                        failed += 1;
+                    }
+                },
+                Err(e) => {
+                    if verbose {
+                        eprintln!("{} Failed to download {}: {}", "✗".red(), oid, e);
+                    }
+                    failed += 1;
+                }
+            }
+            pb.inc(1);
+        }
+    }
+    
+    pb.finish_with_message("Complete!");
+    Ok((downloaded, failed))
+}
+
+/// Every object in `ids` with its content or the reason it could not be
+/// downloaded. Uses the batch endpoint unless `batched` is cleared, and falls
+/// back to one request per object when the batch request fails.
+async fn download_chunk(client: &api::ApiClient, repo_hash: &str, ids: Vec<String>, batched: &AtomicBool) -> Vec<(String, Result<Vec<u8>>)> {
+    if batched.load(Ordering::Relaxed) {
+        match client.batch_download_objects(repo_hash, ids.clone()).await {
+            Ok(Some(response)) => {
+                let mut found: HashMap<String, String> = response.objects
+                    .into_iter()
+                    .map(|object| (object.object_id, object.data))
+                    .collect();
+                return ids
+                    .into_iter()
+                    .map(|oid| {
+                        let result = match found.remove(&oid) {
+                            Some(data) => general_purpose::STANDARD.decode(&data).map_err(anyhow::Error::from),
+                            None => Err(anyhow::anyhow!("not on the remote")),
+                        };
+                        (oid, result)
+                    })
+                    .collect();
+            }
+            Ok(None) => batched.store(false, Ordering::Relaxed),
+            Err(_) => {}
+        }
+    }
+    
+    let mut results = Vec::with_capacity(ids.len());
+    for oid in ids {
+        let result = client.download_object(repo_hash, &oid).await.map(|data| {
+            // Decode base64 if needed
+            general_purpose::STANDARD.decode(&data).unwrap_or(data)
+        });
+        results.push((oid, result));
+    }
+    results
+}
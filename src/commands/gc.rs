@@ -0,0 +1,143 @@
+// TriForge/src/commands/gc.rs
+use colored::*;
+use std::collections::HashSet;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use crate::commands;
+use crate::native_git::{GitObject, Repository};
+use crate::native_git::{pack, revwalk};
+
+pub fn execute(prune: &str, verbose: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let git_dir = repo.git_dir();
+    // `now` must also cover objects written earlier in the current second
+    let expire = match prune {
+        "now" => SystemTime::now(),
+        date => UNIX_EPOCH + Duration::from_secs(commands::log::parse_date(date)?.max(0) as u64),
+    };
+    
+    println!("{}", "Collecting garbage...".cyan());
+    println!();
+    
+    let size_before = repo.size()?;
+    
+    let packed_refs = repo.refs().pack()?;
+    if packed_refs > 0 {
+        println!("{} Packed {} refs", "✓".green(), packed_refs);
+    }
+    
+    // Everything the refs, HEAD and the index still point at
+    let tips = repo.refs().tips()?;
+    let mut reachable = revwalk::reachable_objects(&repo, &tips)?;
+    let index = repo.index()?;
+    reachable.extend(index.entries().map(|e| e.hash.clone()));
+    
+    println!("{} {} objects reachable from {} refs", "→".blue(), reachable.len(), tips.len());
+    
+    let loose: HashSet<String> = GitObject::list_loose(git_dir)?.into_iter().collect();
+    let old_packs = pack::packs(git_dir)?;
+    
+    let mut objects = Vec::with_capacity(reachable.len());
+    let mut sorted: Vec<&String> = reachable.iter().collect();
+    sorted.sort();
+    for hash in sorted {
+        objects.push(repo.load_object(hash)?);
+    }
+    
+    let mut pruned = 0;
+    let mut kept = 0;
+    
+    // Unreachable packed objects still inside the grace period go back to
+    // being loose, dated like their pack, so a later gc can expire them
+    for old in &old_packs {
+        let packed_at = fs::metadata(old.path())?.modified()?;
+        for hash in old.hashes() {
+            if reachable.contains(&hash) || loose.contains(&hash) {
+                continue;
+            }
+            if packed_at < expire {
+                pruned += 1;
+                if verbose {
+                    println!("  {} {}", "pruned".dimmed(), hash);
+                }
+                continue;
+            }
+            if let Some(obj) = old.read(git_dir, &hash)? {
+                obj.store(git_dir)?;
+                fs::File::options()
+                    .write(true)
+                    .open(GitObject::loose_path(git_dir, &hash))?
+                    .set_modified(packed_at)?;
+                kept += 1;
+            }
+        }
+    }
+    
+    let new_pack = if objects.is_empty() {
+        None
+    } else {
+        Some(pack::write_pack(git_dir, &objects)?)
+    };
+    
+    if let Some(name) = &new_pack {
+        println!("{} Packed {} objects into {}", "✓".green(), objects.len(), format!("pack-{}", &name[..8]).yellow());
+    }
+    
+    for old in &old_packs {
+        let is_new = new_pack.as_ref()
+            .map(|name| old.path().ends_with(format!("pack-{}.pack", name)))
+            .unwrap_or(false);
+        if !is_new {
+            fs::remove_file(old.path().with_extension("idx"))?;
+            fs::remove_file(old.path())?;
+        }
+    }
+    
+    let mut removed = 0;
+    for hash in &loose {
+        let path = GitObject::loose_path(git_dir, hash);
+        if reachable.contains(hash) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        } else if fs::metadata(&path)?.modified()? < expire {
+            fs::remove_file(&path)?;
+            pruned += 1;
+            if verbose {
+                println!("  {} {}", "pruned".dimmed(), hash);
+            }
+        } else {
+            kept += 1;
+        }
+    }
+    remove_empty_dirs(&repo)?;
+    
+    if removed > 0 {
+        println!("{} Removed {} loose objects now in the pack", "✓".green(), removed);
+    }
+    println!("{} Pruned {} unreachable objects", "✓".green(), pruned.to_string().cyan());
+    if kept > 0 {
+        println!("{} Kept {} unreachable objects newer than {}", "!".yellow(), kept, prune.yellow());
+    }
+    
+    let size_after = repo.size()?;
+    println!();
+    println!("{} Size: {} KB → {} KB", "→".blue(),
+        (size_before / 1024).to_string().yellow(),
+        (size_after / 1024).to_string().yellow()
+    );
+    println!("{} Reclaimed {} KB", "✓".green(), (size_before.saturating_sub(size_after) / 1024).to_string().cyan());
+    
+    Ok(())
+}
+
+/// Drop `objects/xx/` directories emptied by packing and pruning
+fn remove_empty_dirs(repo: &Repository) -> anyhow::Result<()> {
+    for entry in fs::read_dir(repo.git_dir().join("objects"))? {
+        let entry = entry?;
+        let name = entry.file_name();
+        if name.len() == 2 && entry.file_type()?.is_dir() && fs::read_dir(entry.path())?.next().is_none() {
+            fs::remove_dir(entry.path())?;
+        }
+    }
+    Ok(())
+}
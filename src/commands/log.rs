@@ -1,46 +1,471 @@
 use colored::*;
-use crate::native_git::{Repository, CommitBuilder};
+use std::collections::{HashMap, HashSet};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use crate::native_git::{Repository, Signature};
+use crate::native_git::commit::ParsedCommit;
+use crate::native_git::revwalk::{self, Order, RevWalk, WalkCommit};
 
-pub fn execute(limit: usize, oneline: bool) -> anyhow::Result<()> {
+const DATE_FORMAT: &str = "%a %b %-d %H:%M:%S %Y %z";
+
+pub struct LogOptions {
+    pub revisions: Vec<String>,
+    pub paths: Vec<String>,
+    pub limit: usize,
+    pub oneline: bool,
+    pub graph: bool,
+    pub topo_order: bool,
+    pub author: Option<String>,
+    pub grep: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub format: Option<String>,
+}
+
+pub fn execute(opts: LogOptions) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
     
-    if !oneline {
+    if opts.revisions.is_empty() && repo.head_commit().is_err() {
+        println!("{} No commits yet", "→".blue());
+        return Ok(());
+    }
+    
+    // --graph needs every child drawn before its parents
+    let order = if opts.graph || opts.topo_order { Order::Topo } else { Order::Date };
+    let mut walk = RevWalk::new(&repo).order(order);
+    
+    let revisions = if opts.revisions.is_empty() { vec!["HEAD".to_string()] } else { opts.revisions.clone() };
+    for rev in &revisions {
+        if let Some((from, to)) = rev.split_once("..") {
+            walk = walk
+                .hide(repo.resolve_commit(if from.is_empty() { "HEAD" } else { from })?)
+                .push(repo.resolve_commit(if to.is_empty() { "HEAD" } else { to })?);
+        } else if let Some(hidden) = rev.strip_prefix('^') {
+            walk = walk.hide(repo.resolve_commit(hidden)?);
+        } else {
+            walk = walk.push(repo.resolve_commit(rev)?);
+        }
+    }
+    
+    let since = opts.since.as_deref().map(parse_date).transpose()?;
+    let until = opts.until.as_deref().map(parse_date).transpose()?;
+    let author = opts.author.as_deref().map(|a| a.to_lowercase());
+    
+    let walked = walk.run()?;
+    let all_parents: HashMap<String, Vec<String>> = walked.iter()
+        .map(|w| (w.hash.clone(), w.commit.parents.clone()))
+        .collect();
+    
+    let mut shown: Vec<WalkCommit> = Vec::new();
+    for entry in walked {
+        if shown.len() >= opts.limit {
+            break;
+        }
+        let commit = &entry.commit;
+        
+        if since.map(|t| commit.committer.time < t).unwrap_or(false)
+            || until.map(|t| commit.committer.time > t).unwrap_or(false)
+        {
+            continue;
+        }
+        if let Some(author) = &author {
+            if !commit.author.to_string().to_lowercase().contains(author) {
+                continue;
+            }
+        }
+        if let Some(pattern) = &opts.grep {
+            if !commit.message.contains(pattern.as_str()) {
+                continue;
+            }
+        }
+        if !opts.paths.is_empty() && !revwalk::touches_paths(&repo, commit, &opts.paths)? {
+            continue;
+        }
+        
+        shown.push(entry);
+    }
+    
+    if shown.is_empty() {
+        if opts.format.is_none() {
+            println!("{} No matching commits", "→".blue());
+        }
+        return Ok(());
+    }
+    
+    if !opts.oneline && opts.format.is_none() {
         println!("{}", "Commit History".cyan().bold());
         println!("{}", "═".repeat(60).cyan());
         println!();
     }
     
-    let mut current = match repo.head_commit() {
-        Ok(c) => c,
-        Err(_) => {
-            println!("{} No commits yet", "→".blue());
-            return Ok(());
-        }
-    };
+    let decorations = decorations(&repo)?;
+    let filtered = opts.author.is_some() || opts.grep.is_some() || since.is_some() || until.is_some() || !opts.paths.is_empty();
+    let visible: HashSet<String> = shown.iter().map(|w| w.hash.clone()).collect();
+    let mut graph = Graph { lanes: Vec::new() };
     
-    for i in 0..limit {
-        let obj = repo.load_object(&current)?;
-        let parsed = CommitBuilder::parse(&obj)?;
+    for entry in &shown {
+        let hash = &entry.hash;
+        let commit = &entry.commit;
+        let decoration = decorations.get(hash).map(|d| d.join(", "));
         
-        if oneline {
-            println!("{} {}", 
-                current[..8].to_string().yellow(),
-                parsed.message.cyan()
-            );
+        let lines: Vec<String> = if let Some(template) = &opts.format {
+            expand_format(template, hash, commit, decoration.as_deref()).lines().map(|l| l.to_string()).collect()
+        } else if opts.oneline {
+            let refs = decoration.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default();
+            vec![format!("{}{} {}",
+                hash[..8].to_string().yellow(),
+                refs.cyan(),
+                subject(&commit.message)
+            )]
         } else {
-            println!("{} {}", "commit".yellow().bold(), current.yellow());
-            println!("{} {}", "Author:".bold(), parsed.author);
-            println!();
-            println!("    {}", parsed.message.cyan());
-            println!();
+            let refs = decoration.as_ref().map(|d| format!(" ({})", d)).unwrap_or_default();
+            let mut lines = vec![format!("{} {}{}", "commit".yellow().bold(), hash.yellow(), refs.cyan())];
+            if commit.parents.len() > 1 {
+                let parents: Vec<&str> = commit.parents.iter().map(|p| &p[..8]).collect();
+                lines.push(format!("{}  {}", "Merge:".bold(), parents.join(" ")));
+            }
+            lines.push(format!("{} {}", "Author:".bold(), commit.author));
+            if commit.committer.to_string() != commit.author.to_string() {
+                lines.push(format!("{} {}", "Commit:".bold(), commit.committer));
+            }
+            lines.push(format!("{}   {}", "Date:".bold(), commit.author.datetime().format(DATE_FORMAT)));
+            lines.push(String::new());
+            for line in commit.message.lines() {
+                lines.push(format!("    {}", line.cyan()));
+            }
+            lines.push(String::new());
+            lines
+        };
+        
+        if !opts.graph {
+            for line in lines {
+                println!("{}", line);
+            }
+            continue;
         }
         
-        // Get parent
-        if parsed.parents.is_empty() {
-            break;
+        let parents = if filtered {
+            rewrite_parents(hash, &all_parents, &visible)
+        } else {
+            commit.parents.clone()
+        };
+        
+        let row = graph.commit_row(hash);
+        let padding = graph.padding(hash, parents.is_empty());
+        let mut lines = lines.into_iter();
+        println!("{} {}", row.red(), lines.next().unwrap_or_default());
+        for line in lines {
+            if line.is_empty() {
+                println!("{}", padding.red());
+            } else {
+                println!("{} {}", padding.red(), line);
+            }
+        }
+        for connector in graph.advance(hash, &parents) {
+            println!("{}", connector.red());
         }
-        current = parsed.parents[0].clone();
     }
     
     Ok(())
 }
+
+/// First line of a commit message
+fn subject(message: &str) -> &str {
+    message.lines().next().unwrap_or_default()
+}
+
+/// Ref names pointing at each commit, `HEAD -> main` style
+fn decorations(repo: &Repository) -> anyhow::Result<HashMap<String, Vec<String>>> {
+    let mut map: HashMap<String, Vec<String>> = HashMap::new();
+    let current = repo.refs().current_branch()?;
+    
+    if let (None, Ok(head)) = (&current, repo.head_commit()) {
+        map.entry(head).or_default().push("HEAD".to_string());
+    }
+    
+    let mut branches = repo.refs().list("refs/heads")?;
+    branches.sort();
+    for branch in branches {
+        let name = branch.strip_prefix("refs/heads/").unwrap_or(&branch).to_string();
+        if let Ok(hash) = repo.refs().read(&branch) {
+            let labels = map.entry(hash).or_default();
+            if current.as_deref() == Some(name.as_str()) {
+                labels.insert(0, format!("HEAD -> {}", name));
+            } else {
+                labels.push(name);
+            }
+        }
+    }
+    
+    let mut tags = repo.refs().list("refs/tags")?;
+    tags.sort();
+    for tag in tags {
+        if let Ok(hash) = repo.refs().read(&tag) {
+            let name = tag.strip_prefix("refs/tags/").unwrap_or(&tag);
+            map.entry(hash).or_default().push(format!("tag: {}", name));
+        }
+    }
+    
+    Ok(map)
+}
+
+/// Parents of a shown commit, skipping over commits hidden by filters
+fn rewrite_parents(hash: &str, parents: &HashMap<String, Vec<String>>, visible: &HashSet<String>) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut seen = HashSet::new();
+    let mut stack: Vec<String> = parents.get(hash).cloned().unwrap_or_default();
+    stack.reverse();
+    
+    while let Some(parent) = stack.pop() {
+        if !seen.insert(parent.clone()) {
+            continue;
+        }
+        if visible.contains(&parent) {
+            if !result.contains(&parent) {
+                result.push(parent);
+            }
+        } else if let Some(grandparents) = parents.get(&parent) {
+            stack.extend(grandparents.iter().rev().cloned());
+        }
+    }
+    
+    result
+}
+
+/// Expand `--format` placeholders in the style of `git log --format`
+fn expand_format(template: &str, hash: &str, commit: &ParsedCommit, decoration: Option<&str>) -> String {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        
+        let mut key = String::new();
+        if let Some(&next) = chars.peek() {
+            key.push(next);
+            chars.next();
+            // Two-letter placeholders: %an, %ae, %ad, %ar, %at, %cn, ...
+            if next == 'a' || next == 'c' {
+                if let Some(&second) = chars.peek() {
+                    if "nedrt".contains(second) {
+                        key.push(second);
+                        chars.next();
+                    }
+                }
+            }
+        }
+        
+        let body = commit.message.split_once('\n').map(|(_, b)| b.trim_start_matches('\n')).unwrap_or_default();
+        match key.as_str() {
+            "H" => out.push_str(hash),
+            "h" => out.push_str(&hash[..7]),
+            "T" => out.push_str(&commit.tree),
+            "t" => out.push_str(&commit.tree[..7]),
+            "P" => out.push_str(&commit.parents.join(" ")),
+            "p" => out.push_str(&commit.parents.iter().map(|p| &p[..7]).collect::<Vec<_>>().join(" ")),
+            "s" => out.push_str(subject(&commit.message)),
+            "b" => out.push_str(body),
+            "d" => out.push_str(&decoration.map(|d| format!(" ({})", d)).unwrap_or_default()),
+            "D" => out.push_str(decoration.unwrap_or_default()),
+            "n" => out.push('\n'),
+            "%" => out.push('%'),
+            _ if key.len() == 2 => {
+                let who = if key.starts_with('a') { &commit.author } else { &commit.committer };
+                out.push_str(&signature_field(who, &key[1..]));
+            }
+            _ => {
+                out.push('%');
+                out.push_str(&key);
+            }
+        }
+    }
+    
+    out
+}
+
+fn signature_field(signature: &Signature, field: &str) -> String {
+    match field {
+        "n" => signature.name.clone(),
+        "e" => signature.email.clone(),
+        "d" => signature.datetime().format(DATE_FORMAT).to_string(),
+        "r" => relative_date(signature.time),
+        "t" => signature.time.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn relative_date(time: i64) -> String {
+    let secs = (Local::now().timestamp() - time).max(0);
+    let (n, unit) = match secs {
+        s if s < 90 => (s, "second"),
+        s if s < 90 * 60 => (s / 60, "minute"),
+        s if s < 36 * 3600 => (s / 3600, "hour"),
+        s if s < 14 * 86400 => (s / 86400, "day"),
+        s if s < 10 * 7 * 86400 => (s / (7 * 86400), "week"),
+        s if s < 365 * 86400 => (s / (30 * 86400), "month"),
+        s => (s / (365 * 86400), "year"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+/// Parse `--since`/`--until`: a Unix timestamp, `YYYY-MM-DD[ HH:MM[:SS]]`
+/// in local time, `<n> <unit>s ago` or `now`
+pub fn parse_date(value: &str) -> anyhow::Result<i64> {
+    let value = value.trim();
+    
+    if value == "now" {
+        return Ok(Local::now().timestamp());
+    }
+    
+    if let Ok(secs) = value.parse::<i64>() {
+        return Ok(secs);
+    }
+    
+    if let Some(ago) = value.strip_suffix("ago") {
+        let mut parts = ago.split_whitespace();
+        let n: i64 = parts.next().and_then(|n| n.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("Invalid date: {}", value))?;
+        let unit = parts.next().unwrap_or_default().trim_end_matches('s');
+        let seconds = match unit {
+            "second" => 1,
+            "minute" => 60,
+            "hour" => 3600,
+            "day" => 86400,
+            "week" => 7 * 86400,
+            "month" => 30 * 86400,
+            "year" => 365 * 86400,
+            _ => anyhow::bail!("Invalid date: {}", value),
+        };
+        return Ok(Local::now().timestamp() - n * seconds);
+    }
+    
+    let naive = ["%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M:%S"]
+        .iter()
+        .find_map(|f| NaiveDateTime::parse_from_str(value, f).ok())
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)))
+        .ok_or_else(|| anyhow::anyhow!("Invalid date: {}", value))?;
+    
+    Local.from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.timestamp())
+        .ok_or_else(|| anyhow::anyhow!("Invalid date: {}", value))
+}
+
+/// ASCII history graph. Each lane holds the commit expected next on that
+/// line; lane `k` is drawn at column `2k`.
+struct Graph {
+    lanes: Vec<String>,
+}
+
+impl Graph {
+    /// The `*` row for a commit, opening a new lane for branch tips
+    fn commit_row(&mut self, hash: &str) -> String {
+        let idx = self.lane_of(hash);
+        (0..self.lanes.len())
+            .map(|k| if k == idx { "*" } else { "|" })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    
+    /// Prefix for the lines printed below a commit row
+    fn padding(&self, hash: &str, root: bool) -> String {
+        self.lanes.iter()
+            .map(|lane| if root && lane == hash { " " } else { "|" })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    
+    /// Hand the commit's lane to its parents and return the rows connecting
+    /// the old lanes to the new ones
+    fn advance(&mut self, hash: &str, parents: &[String]) -> Vec<String> {
+        let idx = self.lane_of(hash);
+        let mut rows = Vec::new();
+        
+        let (first, rest) = match parents.split_first() {
+            Some(split) => split,
+            None => {
+                self.lanes.remove(idx);
+                if idx < self.lanes.len() {
+                    rows.push(self.shift_row(idx, false));
+                }
+                return rows;
+            }
+        };
+        
+        self.lanes[idx] = first.clone();
+        
+        // Merged branches open new lanes to the right
+        let mut at = idx;
+        for parent in rest {
+            if self.lanes.contains(parent) {
+                continue;
+            }
+            at += 1;
+            self.lanes.insert(at, parent.clone());
+            rows.push(self.branch_row(at));
+        }
+        
+        // Lanes waiting for the same commit join up
+        while let Some(j) = (1..self.lanes.len()).find(|&j| self.lanes[..j].contains(&self.lanes[j])) {
+            let target = self.lanes.iter().position(|l| *l == self.lanes[j]).unwrap_or(0);
+            self.lanes.remove(j);
+            rows.extend(self.join_rows(j, target));
+        }
+        
+        rows
+    }
+    
+    fn lane_of(&mut self, hash: &str) -> usize {
+        match self.lanes.iter().position(|l| l == hash) {
+            Some(idx) => idx,
+            None => {
+                self.lanes.push(hash.to_string());
+                self.lanes.len() - 1
+            }
+        }
+    }
+    
+    /// `|\` after lane `at` was inserted; lanes to its right move over
+    fn branch_row(&self, at: usize) -> String {
+        let mut row = vec![' '; self.lanes.len() * 2];
+        for k in 0..self.lanes.len() {
+            if k < at {
+                row[2 * k] = '|';
+            } else {
+                row[2 * k - 1] = '\\';
+            }
+        }
+        row.into_iter().collect::<String>().trim_end().to_string()
+    }
+    
+    /// `|/` after lane `removed` closed; lanes to its right move back. When
+    /// `joined` the closed lane itself bends into its neighbour.
+    fn shift_row(&self, removed: usize, joined: bool) -> String {
+        let mut row = vec![' '; (self.lanes.len() + 1) * 2];
+        for k in 0..=self.lanes.len() {
+            if k < removed {
+                row[2 * k] = '|';
+            } else if k > removed || joined {
+                row[2 * k - 1] = '/';
+            }
+        }
+        row.into_iter().collect::<String>().trim_end().to_string()
+    }
+    
+    /// Rows bending closed lane `from` left into lane `target`, crossing the
+    /// lanes in between one row at a time
+    fn join_rows(&self, from: usize, target: usize) -> Vec<String> {
+        let mut rows = vec![self.shift_row(from, true)];
+        for step in 1..from - target {
+            let mut row = vec![' '; self.lanes.len() * 2];
+            for k in 0..self.lanes.len() {
+                row[2 * k] = '|';
+            }
+            row[2 * (from - step) - 1] = '/';
+            rows.push(row.into_iter().collect::<String>().trim_end().to_string());
+        }
+        rows
+    }
+}
@@ -1,8 +1,257 @@
-
+// TriForge/src/commands/merge.rs
 use colored::*;
+use std::collections::BTreeMap;
+use crate::commands;
+use crate::native_git::{Repository, Index, IndexEntry, TreeBuilder, CommitBuilder};
+use crate::native_git::diff;
+use crate::native_git::merge::{self, MergeLabels};
+use crate::native_git::tree::TreeEntry;
+
+pub fn execute(branch: Option<&str>, ff_only: bool, abort: bool, continue_merge: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+
+    if abort {
+        return abort_merge(&repo);
+    }
+    if continue_merge {
+        return finish_merge(&repo);
+    }
+
+    let branch = branch.ok_or_else(|| anyhow::anyhow!("Specify a branch to merge"))?;
+
+    if merge::merge_head(&repo).is_some() {
+        anyhow::bail!("A merge is already in progress. Resolve it with --continue or --abort.");
+    }
 
-pub fn execute(branch: &str, ff_only: bool) -> anyhow::Result<()> {
     println!("{}", "Merging...".cyan());
-    println!("{} Merge functionality coming soon", "!".yellow());
+
+    let head = repo.head_commit()?;
+    let theirs = repo.resolve_commit(branch)?;
+    let mut index = repo.index()?;
+
+    if theirs == head || merge::is_ancestor(&repo, &theirs, &head)? {
+        println!("{} Already up to date", "✓".green());
+        return Ok(());
+    }
+
+    ensure_clean(&repo, &index, &head)?;
+
+    let our_files = flatten_commit(&repo, &head)?;
+    let their_files = flatten_commit(&repo, &theirs)?;
+
+    // Fast-forward when our history is contained in theirs
+    if merge::is_ancestor(&repo, &head, &theirs)? {
+        ensure_no_untracked(&repo, &index, &our_files, their_files.keys())?;
+        repo.switch_tree(&mut index, &our_files, &their_files)?;
+        index.save()?;
+        repo.refs().update_head(&theirs, &format!("merge {}: Fast-forward", branch))?;
+
+        println!("{} Fast-forward {}..{}", "✓".green(),
+            head[..8].to_string().dimmed(),
+            theirs[..8].to_string().yellow()
+        );
+        return Ok(());
+    }
+
+    if ff_only {
+        anyhow::bail!("Not possible to fast-forward, aborting");
+    }
+
+    let base_files = match merge::merge_base(&repo, &head, &theirs)? {
+        Some(base) => {
+            println!("{} Merge base: {}", "→".blue(), base[..8].to_string().dimmed());
+            flatten_commit(&repo, &base)?
+        }
+        None => {
+            println!("{} No common history, merging unrelated trees", "!".yellow());
+            BTreeMap::new()
+        }
+    };
+
+    let labels = MergeLabels { ours: "HEAD", theirs: branch };
+    let result = merge::merge_trees(&repo, &base_files, &our_files, &their_files, &labels)?;
+
+    let incoming = result.files.keys().chain(result.conflicts.iter().map(|c| &c.path));
+    ensure_no_untracked(&repo, &index, &our_files, incoming)?;
+
+    // Apply the clean part of the merge, then lay conflicted files on top
+    repo.switch_tree(&mut index, &our_files, &result.files)?;
+
+    for conflict in &result.conflicts {
+        repo.write_work_file(&conflict.path, &conflict.mode, &conflict.content)?;
+
+        index.remove(&conflict.path);
+        for (stage, side) in [(1, &conflict.base), (2, &conflict.ours), (3, &conflict.theirs)] {
+            if let Some(entry) = side {
+                index.add(IndexEntry::unmerged(
+                    conflict.path.clone(),
+                    u32::from_str_radix(&entry.mode, 8)?,
+                    entry.hash.clone(),
+                    stage,
+                ));
+            }
+        }
+    }
+    index.save()?;
+
+    let mut message = format!("Merge branch '{}'\n", branch);
+
+    if !result.conflicts.is_empty() {
+        message.push_str("\nConflicts:\n");
+        for conflict in &result.conflicts {
+            message.push_str(&format!("\t{}\n", conflict.path));
+        }
+        merge::write_merge_state(&repo, &head, &theirs, &message)?;
+
+        println!();
+        for conflict in &result.conflicts {
+            println!("{} CONFLICT: {}", "✗".red(), conflict.path.yellow());
+        }
+        println!();
+        println!("{} Automatic merge failed", "!".yellow().bold());
+        println!("{} Fix conflicts, then run: {}", "→".blue(), "triforge add <files> && triforge merge --continue".cyan());
+        println!("{} Or give up with: {}", "→".blue(), "triforge merge --abort".cyan());
+        return Ok(());
+    }
+
+    let commit_hash = commit_merge(&repo, &index, &head, &theirs, &message)?;
+
+    println!("{} Merged {} into HEAD", "✓".green(), branch.yellow());
+    println!("{} Created merge commit: {}", "✓".green(), commit_hash[..8].to_string().yellow());
+
+    Ok(())
+}
+
+/// Conclude a conflicted merge once every path has been resolved
+fn finish_merge(repo: &Repository) -> anyhow::Result<()> {
+    let theirs = merge::merge_head(repo)
+        .ok_or_else(|| anyhow::anyhow!("No merge in progress"))?;
+    let index = repo.index()?;
+
+    let conflicts = index.conflicts();
+    if !conflicts.is_empty() {
+        for path in &conflicts {
+            println!("{} Unresolved: {}", "✗".red(), path.yellow());
+        }
+        anyhow::bail!("Resolve conflicts and stage them with 'triforge add' first");
+    }
+
+    let head = repo.head_commit()?;
+    let message = merge::merge_message(repo)
+        .unwrap_or_else(|| format!("Merge commit '{}'\n", theirs));
+
+    let commit_hash = commit_merge(repo, &index, &head, &theirs, &message)?;
+
+    println!("{} Created merge commit: {}", "✓".green(), commit_hash[..8].to_string().yellow());
+    Ok(())
+}
+
+/// Throw away an in-progress merge and restore HEAD
+fn abort_merge(repo: &Repository) -> anyhow::Result<()> {
+    if merge::merge_head(repo).is_none() {
+        anyhow::bail!("No merge in progress");
+    }
+
+    let head = repo.head_commit()?;
+    let head_files = flatten_commit(repo, &head)?;
+    let mut index = repo.index()?;
+
+    // Describe the current index as a tree; conflicted paths get no hash so
+    // they are always rewritten
+    let mut current: BTreeMap<String, TreeEntry> = index.entries()
+        .map(|e| (e.path.clone(), TreeEntry {
+            mode: format!("{:o}", e.mode),
+            name: e.path.clone(),
+            hash: e.hash.clone(),
+        }))
+        .collect();
+    for path in index.conflicts() {
+        current.insert(path.clone(), TreeEntry {
+            mode: String::new(),
+            name: path.clone(),
+            hash: String::new(),
+        });
+        index.remove(&path);
+    }
+
+    repo.switch_tree(&mut index, &current, &head_files)?;
+    index.save()?;
+    merge::clear_merge_state(repo)?;
+
+    println!("{} Merge aborted, restored {}", "✓".green(), head[..8].to_string().yellow());
+    Ok(())
+}
+
+/// Record a two-parent merge commit from the index and advance HEAD
+fn commit_merge(repo: &Repository, index: &Index, head: &str, theirs: &str, message: &str) -> anyhow::Result<String> {
+    let tree_hash = TreeBuilder::write_paths(
+        repo.git_dir(),
+        index.entries().map(|e| (e.path.clone(), format!("{:o}", e.mode), e.hash.clone())),
+    )?;
+
+    let (author, committer) = commands::commit::signatures(repo, None)?;
+    let commit_obj = CommitBuilder::new(tree_hash, message.to_string())
+        .author(author)
+        .committer(committer)
+        .parent(head.to_string())
+        .parent(theirs.to_string())
+        .build()?;
+    let commit_hash = repo.store_object(&commit_obj)?;
+
+    let subject = message.lines().next().unwrap_or_default();
+    repo.refs().update_head(&commit_hash, &format!("commit (merge): {}", subject))?;
+    merge::clear_merge_state(repo)?;
+
+    Ok(commit_hash)
+}
+
+fn flatten_commit(repo: &Repository, commit: &str) -> anyhow::Result<BTreeMap<String, TreeEntry>> {
+    let tree = repo.tree_of(commit)?;
+    TreeBuilder::flatten(repo.git_dir(), &tree)
+}
+
+/// Refuse to merge over staged or unstaged changes to tracked files
+fn ensure_clean(repo: &Repository, index: &Index, head: &str) -> anyhow::Result<()> {
+    let head_snapshot = diff::tree_snapshot(repo, &repo.tree_of(head)?)?;
+    let index_snapshot = diff::index_snapshot(index);
+    let work_snapshot = diff::worktree_snapshot(repo, index, index_snapshot.keys())?;
+
+    let dirty: Vec<String> = diff::compare(&head_snapshot, &index_snapshot)
+        .into_iter()
+        .chain(diff::compare(&index_snapshot, &work_snapshot))
+        .map(|c| c.path().to_string())
+        .collect();
+
+    if !dirty.is_empty() {
+        for path in &dirty {
+            println!("{} {}", "M".red(), path.yellow());
+        }
+        anyhow::bail!("Commit or stash your changes before merging");
+    }
+
+    Ok(())
+}
+
+/// Refuse to write over untracked files at paths the merge adds
+fn ensure_no_untracked<'a>(
+    repo: &Repository,
+    index: &Index,
+    ours: &BTreeMap<String, TreeEntry>,
+    incoming: impl Iterator<Item = &'a String>,
+) -> anyhow::Result<()> {
+    let mut blocked: Vec<&String> = incoming
+        .filter(|path| !ours.contains_key(*path) && index.get(path).is_none())
+        .filter(|path| repo.work_dir().join(path).symlink_metadata().is_ok())
+        .collect();
+    blocked.sort();
+    blocked.dedup();
+
+    if !blocked.is_empty() {
+        println!("{} Untracked files would be overwritten by merge:", "✗".red());
+        for path in &blocked {
+            println!("    {}", path.yellow());
+        }
+        anyhow::bail!("Move or remove them before merging");
+    }
     Ok(())
 }
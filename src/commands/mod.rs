@@ -5,20 +5,26 @@ pub mod commit;
 pub mod status;
 pub mod log;
 pub mod diff;
+pub mod show;
 pub mod branch;
 pub mod checkout;
+pub mod check_ignore;
 pub mod merge;
 pub mod push;
 pub mod clone;
 pub mod pull;
+pub mod fetch;
 pub mod info;
 pub mod list;
+pub mod gc;
 pub mod verify;
 pub mod remote;
 pub mod config;
 pub mod auth;
 pub mod hash;
 pub mod tag;
+pub mod stash;
+pub mod reflog;
 pub mod star;
 pub mod pin;
 pub mod fork;
@@ -1,10 +1,55 @@
 // TriForge/src/commands/push.rs - Fixed with better error handling
 use colored::*;
 use indicatif::{ProgressBar, ProgressStyle};
-use crate::{api, config::AppConfig, git};
+use crate::{api, commands, config::AppConfig, git};
+use crate::native_git::{ObjectType, Repository};
+use crate::native_git::config::GitConfig;
+use crate::native_git::merge;
+use crate::native_git::reflog::NULL_HASH;
+use crate::native_git::refs::Expected;
+use crate::native_git::refspec::{self, Refspec};
+use crate::native_git::{revparse, revwalk};
 use base64::{Engine as _, engine::general_purpose};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Which local refs to push, and which remote values they may replace
+pub struct RefSelection {
+    /// `[+]<src>[:<dst>]` refspecs; empty means the current branch
+    pub refspecs: Vec<String>,
+    /// Every branch under `refs/heads/`
+    pub all: bool,
+    /// Every tag under `refs/tags/`
+    pub tags: bool,
+    /// Overwrite remote refs even when that loses commits
+    pub force: bool,
+    /// `--force-with-lease` values: empty, `<ref>` or `<ref>:<expected>`
+    pub force_with_lease: Vec<String>,
+}
+
+/// One ref to set on the remote
+struct PushRef {
+    /// Short local name or revision it comes from, for display
+    src: String,
+    dst: String,
+    hash: String,
+    /// Pushed with a `+` refspec
+    force: bool,
+    /// A tag that came along because it points into the pushed history
+    followed: bool,
+    /// What the remote ref must still hold when it is updated; `None`
+    /// overwrites whatever is there
+    expected: Option<String>,
+}
+
+/// What happens to a ref the remote already has a different value for
+enum Verdict {
+    Update(Option<String>),
+    Reject(&'static str),
+}
 
 pub async fn execute(
+    remote: Option<String>,
+    selection: RefSelection,
     name: Option<String>,
     description: Option<String>,
     private: bool,
@@ -24,52 +69,161 @@ pub async fn execute(
     
     // Open repository
     let repo = git::open_repo()?;
+    let local = Repository::open(".")?;
+    let client = api::ApiClient::new(config);
     
-    // Get repository name
-    let repo_name = if let Some(n) = name {
-        n
-    } else {
-        git::get_repo_name(&repo)?
-    };
+    // A remote we pushed to before is updated in place
+    let remote_name = remote.unwrap_or_else(|| "origin".to_string());
+    let existing = commands::remote::hash_for(&local, &remote_name).ok();
     
-    println!("{} Repository: {}", "→".blue(), repo_name.yellow());
-    if let Some(desc) = &description {
-        println!("{} Description: {}", "→".blue(), desc);
-    }
-    println!("{} Privacy: {}", "→".blue(), if private { "Private".red() } else { "Public".green() });
-    println!();
-    
-    // Get HEAD commit
     println!("{}", "Analyzing repository...".cyan());
-    let head_commit = git::get_head_commit(&repo)?;
-    let head_id = head_commit.id().to_string();
-    println!("{} HEAD: {}", "✓".green(), head_id[..8].to_string().yellow());
+    let mut updates = select_refs(&local, &selection)?;
     
-    // Get all objects
+    // Only what the pushed refs reach goes out; dangling blobs, stashes and
+    // other local leftovers never leave the machine
     println!("{}", "Collecting Git objects...".cyan());
-    let objects = git::get_all_objects(&repo)?;
+    let tips: Vec<String> = updates.iter().map(|u| u.hash.clone()).collect();
+    let mut reachable = revwalk::reachable_objects(&local, &tips)?;
+    
+    // Tags pointing into the pushed history travel with it
+    follow_tags(&local, &mut updates, &mut reachable)?;
+    println!("{} {} refs to push", "✓".green(), updates.len().to_string().yellow());
+    
+    let objects = sorted_oids(reachable)?;
     println!("{} Found {} objects", "✓".green(), objects.len().to_string().yellow());
     
     if objects.is_empty() {
         anyhow::bail!("No objects to push. Make sure you have committed changes.");
     }
+    println!();
     
-    // Create repository on Hyrule
-    println!("{}", "Creating repository on Hyrule...".cyan());
-    let client = api::ApiClient::new(config);
+    // Refs the remote already has at the right commit only need their
+    // tracking refs refreshed
+    let mut up_to_date = Vec::new();
+    let mut rejected = Vec::new();
     
-    let req = api::CreateRepoRequest {
-        name: repo_name.clone(),
-        description: description.clone(),
-        storage_tier: "free".to_string(),
-        is_private: private,
+    let (repo_hash, objects) = match existing {
+        Some(repo_hash) => {
+            let metadata = client.get_repo(&repo_hash).await?;
+            println!("{} Pushing to: {} ({})", "→".blue(), metadata.name.yellow(), remote_name.cyan());
+            println!("{} Repository hash: {}", "→".blue(), repo_hash.green().bold());
+            if name.is_some() || description.is_some() || private {
+                println!("{} --name, --description and --private only apply when creating a repository", "!".yellow());
+            }
+            
+            // Without the remote's refs there is no telling what a push would
+            // overwrite
+            let remote_refs: HashMap<String, String> = client.list_refs(&repo_hash).await
+                .map_err(|e| anyhow::anyhow!("Could not read the remote's refs: {}", e))?
+                .into_iter()
+                .map(|r| (r.ref_name, r.commit_id.trim().to_string()))
+                .collect();
+            
+            let selected = updates.len();
+            let mut pending = Vec::new();
+            for mut update in updates {
+                let old = remote_refs.get(&update.dst);
+                // Tags that only came along never replace the remote's
+                if update.followed && old.is_some() {
+                    continue;
+                }
+                if old == Some(&update.hash) {
+                    up_to_date.push(update);
+                    continue;
+                }
+                match check_update(&local, &remote_name, &selection, &update, old.map(|s| s.as_str()))? {
+                    Verdict::Update(expected) => {
+                        update.expected = expected;
+                        pending.push(update);
+                    }
+                    Verdict::Reject(reason) => rejected.push((update, reason.to_string())),
+                }
+            }
+            updates = pending;
+            
+            if updates.is_empty() {
+                update_tracking_refs(&local, &remote_name, &up_to_date)?;
+                report_rejected(&rejected)?;
+                println!("{} Everything up to date", "✓".green());
+                return Ok(());
+            }
+            
+            // Refs left behind take their objects with them
+            let objects = if updates.len() < selected {
+                let tips: Vec<String> = updates.iter().map(|u| u.hash.clone()).collect();
+                sorted_oids(revwalk::reachable_objects(&local, &tips)?)?
+            } else {
+                objects
+            };
+            
+            // Negotiate: only send what the remote does not have yet
+            println!("{}", "Comparing with remote...".cyan());
+            let objects = match client.list_objects(&repo_hash).await {
+                Ok(response) => {
+                    let remote_oids: HashSet<String> = response.objects.into_iter().collect();
+                    let missing: Vec<_> = objects.into_iter()
+                        .filter(|oid| !remote_oids.contains(&oid.to_string()))
+                        .collect();
+                    println!("{} Remote has {} objects, {} to upload", 
+                        "→".blue(), 
+                        remote_oids.len().to_string().yellow(),
+                        missing.len().to_string().yellow()
+                    );
+                    missing
+                }
+                Err(e) => {
+                    println!("{} Could not list remote objects ({}), uploading everything", "!".yellow(), e);
+                    objects
+                }
+            };
+            (repo_hash, objects)
+        }
+        None => {
+            // Get repository name
+            let repo_name = if let Some(n) = name {
+                n
+            } else {
+                git::get_repo_name(&repo)?
+            };
+            
+            println!("{} Repository: {}", "→".blue(), repo_name.yellow());
+            if let Some(desc) = &description {
+                println!("{} Description: {}", "→".blue(), desc);
+            }
+            println!("{} Privacy: {}", "→".blue(), if private { "Private".red() } else { "Public".green() });
+            println!();
+            
+            // Create repository on Hyrule
+            println!("{}", "Creating repository on Hyrule...".cyan());
+            let req = api::CreateRepoRequest {
+                name: repo_name.clone(),
+                description: description.clone(),
+                storage_tier: "free".to_string(),
+                is_private: private,
+            };
+            
+            let response = client.create_repo(req).await?;
+            
+            println!("{} {}", "✓".green(), response.message);
+            println!();
+            println!("{} Repository hash: {}", "→".blue(), response.repo_hash.green().bold());
+            
+            // Remember it so the next push only sends what changed
+            let hash_key = format!("remote.{}.hyrule-hash", remote_name);
+            GitConfig::set(local.git_dir(), &hash_key, &response.repo_hash)?;
+            println!("{} Saved as remote '{}'", "✓".green(), remote_name.cyan());
+            
+            // A new repository has no refs: every update is a creation
+            for update in &mut updates {
+                update.expected = Some(NULL_HASH.to_string());
+            }
+            (response.repo_hash, objects)
+        }
     };
     
-    let response = client.create_repo(req).await?;
-    
-    println!("{} {}", "✓".green(), response.message);
-    println!();
-    println!("{} Repository hash: {}", "→".blue(), response.repo_hash.green().bold());
+    if objects.is_empty() {
+        println!("{} Remote already has every object", "✓".green());
+    }
     
     // Upload objects in batches
     println!();
@@ -83,77 +237,63 @@ pub async fn execute(
             .progress_chars("█▓░")
     );
     
-    // Batch upload for efficiency
-    let batch_size = 1;
+    // Batch upload for efficiency, bounded by object count and encoded size
+    let batch_objects = client.config().batch_objects.max(1);
+    let batch_bytes = client.config().batch_bytes;
     let mut uploaded_count = 0;
     let mut failed_count = 0;
     let total_objects = objects.len();
     
-    for chunk in objects.chunks(batch_size) {
-        let mut batch = Vec::new();
+    let mut batch = Vec::new();
+    let mut pending_bytes = 0;
+    for oid in &objects {
+        if verbose {
+            pb.set_message(format!("Processing {}", oid));
+        }
         
-        for oid in chunk {
-            if verbose {
-                pb.set_message(format!("Processing {}", oid));
-            }
-            
-            match git::read_object(&repo, *oid) {
-                Ok(data) => {
-                    match git::get_object_type(&repo, *oid) {
-                        Ok(object_type) => {
-                            // Encode data as base64
-                            let encoded_data = general_purpose::STANDARD.encode(&data);
-                            
-                            batch.push(api::UploadObjectRequest {
-                                object_id: oid.to_string(),
-                                object_type,
-                                data: encoded_data,
-                            });
-                        }
-                        Err(e) => {
-                            if verbose {
-                                eprintln!("{} Failed to get type for {}: {}", "!".yellow(), oid, e);
-                            }
-                            failed_count += 1;
-                        }
-                    }
-                }
-                Err(e) => {
-                    if verbose {
-                        eprintln!("{} Failed to read object {}: {}", "!".yellow(), oid, e);
-                    }
-                    failed_count += 1;
+        let object = git::read_object(&repo, *oid)
+            .and_then(|data| Ok((data, git::get_object_type(&repo, *oid)?)));
+        let (data, object_type) = match object {
+            Ok(object) => object,
+            Err(e) => {
+                if verbose {
+                    eprintln!("{} Failed to read object {}: {}", "!".yellow(), oid, e);
                 }
+                failed_count += 1;
+                continue;
             }
+        };
+        
+        // Encode data as base64
+        let encoded_data = general_purpose::STANDARD.encode(&data);
+        
+        // A single object larger than the byte limit still goes, on its own
+        if !batch.is_empty() && pending_bytes + encoded_data.len() > batch_bytes {
+            let (uploaded, failed) = upload_batch(&client, &repo_hash, std::mem::take(&mut batch), &pb, verbose).await;
+            uploaded_count += uploaded;
+            failed_count += failed;
+            pending_bytes = 0;
         }
         
-        if !batch.is_empty() {
-            let batch_len = batch.len();
-            
-            // Upload batch
-            match client.batch_upload_objects(&response.repo_hash, batch).await {
-                Ok(result) => {
-                    uploaded_count += result.uploaded;
-                    pb.inc(result.uploaded as u64);
-                    
-                    if !result.failed.is_empty() {
-                        failed_count += result.failed.len();
-                        if verbose {
-                            for failed_id in &result.failed {
-                                eprintln!("{} Failed to upload: {}", "✗".red(), failed_id);
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    eprintln!("{} Batch upload failed: {}", "✗".red(), e);
-                    eprintln!("{} This might be a network or server issue", "→".blue());
-                    failed_count += batch_len;
-                    pb.inc(batch_len as u64);
-                }
-            }
+        pending_bytes += encoded_data.len();
+        batch.push(api::UploadObjectRequest {
+            object_id: oid.to_string(),
+            object_type,
+            data: encoded_data,
+        });
+        
+        if batch.len() >= batch_objects {
+            let (uploaded, failed) = upload_batch(&client, &repo_hash, std::mem::take(&mut batch), &pb, verbose).await;
+            uploaded_count += uploaded;
+            failed_count += failed;
+            pending_bytes = 0;
         }
     }
+    if !batch.is_empty() {
+        let (uploaded, failed) = upload_batch(&client, &repo_hash, batch, &pb, verbose).await;
+        uploaded_count += uploaded;
+        failed_count += failed;
+    }
     
     pb.finish_with_message("Complete!");
     
@@ -167,7 +307,7 @@ pub async fn execute(
         );
     }
     
-    if uploaded_count == 0 {
+    if uploaded_count == 0 && total_objects > 0 {
         println!("{}", "═".repeat(60).red());
         println!("{}", "✗ Upload failed - no objects were uploaded!".red().bold());
         println!("{}", "═".repeat(60).red());
@@ -180,15 +320,30 @@ pub async fn execute(
         anyhow::bail!("Push failed - no objects uploaded");
     }
     
-    // Update HEAD ref
+    // Refs must never point at objects the remote does not have
+    if failed_count > 0 {
+        anyhow::bail!("Push failed - remote refs were left unchanged. Run the push again to retry");
+    }
+    
     println!("{}", "Updating references...".cyan());
-    match client.update_ref(&response.repo_hash, "refs/heads/main", &head_id).await {
-        Ok(_) => println!("{} Updated refs/heads/main", "✓".green()),
-        Err(e) => {
-            eprintln!("{} Failed to update ref: {}", "!".yellow(), e);
-            eprintln!("{} Objects were uploaded but HEAD may not be set", "→".blue());
+    let mut pushed = Vec::new();
+    for update in updates {
+        match client.update_ref(&repo_hash, &update.dst, &update.hash, update.expected.as_deref()).await {
+            Ok(_) => {
+                println!("{} {} {} {}", "✓".green(), update.src.yellow(), "→".blue(), update.dst);
+                pushed.push(update);
+            }
+            // The remote refused the compare-and-swap or the request failed
+            Err(e) => rejected.push((update, e.to_string())),
         }
     }
+    for update in &up_to_date {
+        println!("{} {} {}", "=".dimmed(), update.dst, "(up to date)".dimmed());
+    }
+    
+    pushed.extend(up_to_date);
+    update_tracking_refs(&local, &remote_name, &pushed)?;
+    report_rejected(&rejected)?;
     
     println!();
     println!("{}", "═".repeat(60).green());
@@ -196,16 +351,242 @@ pub async fn execute(
     println!("{}", "═".repeat(60).green());
     println!();
     println!("{} Uploaded {} objects", "→".blue(), uploaded_count.to_string().cyan());
-    if failed_count > 0 {
-        println!("{} Failed {} objects", "→".blue(), failed_count.to_string().red());
-    }
     println!();
     println!("{} Clone with:", "→".blue());
-    println!("  {}", format!("triforge clone {}", response.repo_hash).cyan());
+    println!("  {}", format!("triforge clone {}", repo_hash).cyan());
     println!();
     println!("{} View on web:", "→".blue());
-    println!("  {}", format!("http://hyrule4e3tu7pfdkvvca43senvgvgisi6einpe3d3kpidlk3uyjf7lqd.onion/r/{}", response.repo_hash).cyan());
+    println!("  {}", format!("http://hyrule4e3tu7pfdkvvca43senvgvgisi6einpe3d3kpidlk3uyjf7lqd.onion/r/{}", repo_hash).cyan());
     println!();
     
     Ok(())
 }
+
+/// Send one batch of objects, returning how many were uploaded and how many
+/// failed
+async fn upload_batch(
+    client: &api::ApiClient,
+    repo_hash: &str,
+    batch: Vec<api::UploadObjectRequest>,
+    pb: &ProgressBar,
+    verbose: bool,
+) -> (usize, usize) {
+    let batch_len = batch.len();
+    match client.batch_upload_objects(repo_hash, batch).await {
+        Ok(result) => {
+            pb.inc(batch_len as u64);
+            if verbose {
+                for failed_id in &result.failed {
+                    eprintln!("{} Failed to upload: {}", "✗".red(), failed_id);
+                }
+            }
+            // Objects the server does not account for may be missing, so
+            // they count as failed too
+            let failed = result.failed.len().max(batch_len.saturating_sub(result.uploaded)).min(batch_len);
+            if failed > result.failed.len() {
+                eprintln!("{} Server did not confirm {} of {} objects", "✗".red(), failed - result.failed.len(), batch_len);
+            }
+            (batch_len - failed, failed)
+        }
+        Err(e) => {
+            eprintln!("{} Batch upload failed: {}", "✗".red(), e);
+            eprintln!("{} This might be a network or server issue", "→".blue());
+            pb.inc(batch_len as u64);
+            (0, batch_len)
+        }
+    }
+}
+
+/// Expand the selection into remote refs and the local objects they get.
+/// With nothing named, the current branch goes to the branch of the same
+/// name, like Git's `push.default = current`.
+fn select_refs(repo: &Repository, selection: &RefSelection) -> anyhow::Result<Vec<PushRef>> {
+    let mut specs = selection.refspecs.iter()
+        .map(|spec| Refspec::parse(spec))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    if selection.all {
+        specs.push(Refspec::parse("refs/heads/*")?);
+    }
+    if selection.tags {
+        specs.push(Refspec::parse("refs/tags/*")?);
+    }
+    if specs.is_empty() {
+        let branch = repo.refs().current_branch()?
+            .ok_or_else(|| anyhow::anyhow!("You are not on a branch. Name what to push, e.g. 'triforge push origin HEAD:<branch>'"))?;
+        specs.push(Refspec::parse(&format!("refs/heads/{}", branch))?);
+    }
+    
+    let mut selected: BTreeMap<String, PushRef> = BTreeMap::new();
+    for spec in &specs {
+        let mut matches = Vec::new();
+        if spec.is_glob() {
+            for name in repo.refs().list("refs")? {
+                if let Some(dst) = spec.map(&name) {
+                    matches.push((refspec::shorten(&name).to_string(), dst, repo.refs().read(&name)?));
+                }
+            }
+        } else {
+            // HEAD stands for the current branch; other revisions need an
+            // explicit destination
+            let full = if spec.src == "HEAD" {
+                repo.refs().current_branch()?.map(|b| format!("refs/heads/{}", b))
+            } else {
+                revparse::full_ref_name(repo, &spec.src)
+            };
+            let dst = match (&spec.dst, &full) {
+                (Some(dst), full) => refspec::qualify_dst(dst, full.as_deref().unwrap_or_default()),
+                (None, Some(full)) => full.clone(),
+                (None, None) => anyhow::bail!("'{}' is not a branch or tag; name a destination, as in {}:<branch>", spec.src, spec.src),
+            };
+            matches.push((refspec::shorten(&spec.src).to_string(), dst, repo.resolve(&spec.src)?));
+        }
+        
+        for (src, dst, hash) in matches {
+            if let Some(other) = selected.get(&dst) {
+                if other.hash != hash {
+                    anyhow::bail!("Both {} and {} would be pushed to {}", other.src, src, dst);
+                }
+            }
+            selected.insert(dst.clone(), PushRef { src, dst, hash, force: spec.force, followed: false, expected: None });
+        }
+    }
+    
+    if selected.is_empty() {
+        anyhow::bail!("Nothing matches the refs to push");
+    }
+    Ok(selected.into_values().collect())
+}
+
+/// Add the tags not yet selected whose target the push already includes,
+/// along with their tag objects
+fn follow_tags(repo: &Repository, updates: &mut Vec<PushRef>, reachable: &mut HashSet<String>) -> anyhow::Result<()> {
+    for name in repo.refs().list("refs/tags")? {
+        if updates.iter().any(|u| u.dst == name) {
+            continue;
+        }
+        let hash = repo.refs().read(&name)?;
+        
+        let mut chain = Vec::new();
+        let mut target = hash.clone();
+        loop {
+            let obj = repo.load_object(&target)?;
+            if obj.obj_type != ObjectType::Tag {
+                break;
+            }
+            chain.push(target);
+            target = revparse::tag_target(&obj.content)?;
+        }
+        
+        if reachable.contains(&target) {
+            reachable.extend(chain);
+            updates.push(PushRef {
+                src: refspec::shorten(&name).to_string(),
+                dst: name,
+                hash,
+                force: false,
+                followed: true,
+                expected: None,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Decide whether `update` may replace `old`, the remote's current value,
+/// and what the server must find there for the update to apply. Plain
+/// pushes only fast-forward branches and only create tags.
+fn check_update(
+    repo: &Repository,
+    remote: &str,
+    selection: &RefSelection,
+    update: &PushRef,
+    old: Option<&str>,
+) -> anyhow::Result<Verdict> {
+    let old_or_null = old.unwrap_or(NULL_HASH);
+    
+    // A lease allows any update as long as the remote still has what we
+    // last saw there
+    if let Some(expected) = lease(repo, remote, selection, &update.dst)? {
+        if expected != old_or_null {
+            return Ok(Verdict::Reject("stale info"));
+        }
+        return Ok(Verdict::Update(Some(expected)));
+    }
+    
+    if selection.force || update.force {
+        return Ok(Verdict::Update(None));
+    }
+    let Some(old) = old else {
+        return Ok(Verdict::Update(Some(NULL_HASH.to_string())));
+    };
+    if update.dst.starts_with("refs/tags/") {
+        return Ok(Verdict::Reject("already exists"));
+    }
+    
+    // Commits we have never seen cannot be in our history
+    if repo.load_object(old).is_err() {
+        return Ok(Verdict::Reject("fetch first"));
+    }
+    if !merge::is_ancestor(repo, old, &update.hash)? {
+        return Ok(Verdict::Reject("non-fast-forward"));
+    }
+    Ok(Verdict::Update(Some(old.to_string())))
+}
+
+/// The value `--force-with-lease` expects `dst` to have on the remote, if a
+/// lease covers it. Without an explicit value that is what our
+/// remote-tracking ref says; a ref we have no record of must not exist.
+fn lease(repo: &Repository, remote: &str, selection: &RefSelection, dst: &str) -> anyhow::Result<Option<String>> {
+    for lease in &selection.force_with_lease {
+        let (name, expected) = match lease.split_once(':') {
+            Some((name, expected)) => (name, Some(expected)),
+            None => (lease.as_str(), None),
+        };
+        if !name.is_empty() && name != dst && name != refspec::shorten(dst) {
+            continue;
+        }
+        
+        let value = match expected {
+            Some("") => NULL_HASH.to_string(),
+            Some(expected) if expected.len() == 40 && expected.chars().all(|c| c.is_ascii_hexdigit()) => expected.to_string(),
+            Some(expected) => repo.resolve(expected)?,
+            None => refspec::tracking_ref(remote, dst)
+                .and_then(|tracking| repo.refs().read(&tracking).ok())
+                .unwrap_or_else(|| NULL_HASH.to_string()),
+        };
+        return Ok(Some(value));
+    }
+    Ok(None)
+}
+
+/// List refs the remote refused and fail the push if there were any
+fn report_rejected(rejected: &[(PushRef, String)]) -> anyhow::Result<()> {
+    if rejected.is_empty() {
+        return Ok(());
+    }
+    for (update, reason) in rejected {
+        println!("{} {} {} {} ({})", "✗".red(), update.src.yellow(), "→".blue(), update.dst, reason);
+    }
+    println!("{} Fetch and merge the remote changes first, or use --force-with-lease to overwrite them", "→".blue());
+    anyhow::bail!("Some refs were rejected");
+}
+
+/// Object ids in a stable order for uploading
+fn sorted_oids(objects: HashSet<String>) -> anyhow::Result<Vec<git2::Oid>> {
+    let mut objects: Vec<String> = objects.into_iter().collect();
+    objects.sort();
+    Ok(objects.iter()
+        .map(|hash| git2::Oid::from_str(hash))
+        .collect::<Result<Vec<_>, _>>()?)
+}
+
+/// Point `refs/remotes/<remote>/*` at the branches the remote now has
+fn update_tracking_refs(repo: &Repository, remote: &str, pushed: &[PushRef]) -> anyhow::Result<()> {
+    let mut transaction = repo.refs().transaction();
+    for update in pushed {
+        if let Some(tracking) = refspec::tracking_ref(remote, &update.dst) {
+            transaction = transaction.update(&tracking, &update.hash, Expected::Any);
+        }
+    }
+    transaction.commit("update by push")
+}
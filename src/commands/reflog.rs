@@ -0,0 +1,30 @@
+// TriForge/src/commands/reflog.rs
+use colored::*;
+use crate::native_git::{reflog, revparse, Repository};
+
+/// List the updates recorded for a ref (HEAD by default), newest first
+pub fn execute(name: Option<&str>, limit: Option<usize>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let name = name.unwrap_or("HEAD");
+    let ref_name = revparse::full_ref_name(&repo, name)
+        .ok_or_else(|| anyhow::anyhow!("Unknown ref: {}", name))?;
+    
+    let entries = reflog::read(repo.git_dir(), &ref_name)?;
+    if entries.is_empty() {
+        println!("{} No reflog for {}", "→".blue(), ref_name.yellow());
+        return Ok(());
+    }
+    
+    // Show the name the way it was asked for, like `main@{2}`
+    let short = ref_name.strip_prefix("refs/heads/").unwrap_or(name);
+    
+    for (n, entry) in entries.iter().rev().take(limit.unwrap_or(usize::MAX)).enumerate() {
+        println!("{} {}: {}",
+            entry.new[..7].to_string().yellow(),
+            format!("{}@{{{}}}", short, n).cyan(),
+            entry.message
+        );
+    }
+    
+    Ok(())
+}
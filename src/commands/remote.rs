@@ -47,3 +47,25 @@ pub fn list() -> anyhow::Result<()> {
     
     Ok(())
 }
+
+/// Hyrule repository hash of a remote: `remote.<name>.hyrule-hash` in
+/// `.git/config`, or the latest entry added with `triforge remote add`
+pub fn hash_for(repo: &Repository, name: &str) -> anyhow::Result<String> {
+    if let Some(hash) = repo.config()?.get(&format!("remote.{}.hyrule-hash", name)) {
+        return Ok(hash.to_string());
+    }
+    
+    let remotes_file = repo.git_dir().join("remotes");
+    if remotes_file.exists() {
+        let remotes = fs::read_to_string(&remotes_file)?;
+        for line in remotes.lines().rev() {
+            if let Some((remote, hash)) = line.split_once('=') {
+                if remote == name {
+                    return Ok(hash.to_string());
+                }
+            }
+        }
+    }
+    
+    anyhow::bail!("Remote '{}' not configured. Add it with: triforge remote add {} <hash>", name, name)
+}
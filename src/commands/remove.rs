@@ -1,14 +1,76 @@
 use colored::*;
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::native_git::{Index, IndexEntry, Repository, TreeBuilder};
+use crate::native_git::tree::TreeEntry;
 
 pub fn execute(paths: Vec<String>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let mut index = repo.index()?;
+    
+    // Unstaging puts back what HEAD has; paths HEAD lacks leave the index
+    let head_files = match repo.head_commit() {
+        Ok(head) => TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(&head)?)?,
+        Err(_) => BTreeMap::new(),
+    };
+    
     println!("{}", "Removing files from staging...".cyan());
     
+    let mut removed = Vec::new();
     for path in &paths {
+        let rel_path = match repo.relative_path(Path::new(path))? {
+            Some(p) => p,
+            None => continue,
+        };
+        
+        let prefix = format!("{}/", rel_path);
+        let mut candidates: Vec<String> = index.entries()
+            .map(|e| e.path.clone())
+            .chain(head_files.keys().cloned())
+            .filter(|p| *p == rel_path || p.starts_with(&prefix))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        
+        let unstaged: Vec<String> = candidates.into_iter()
+            .filter(|p| unstage(&mut index, &head_files, p))
+            .collect();
+        if unstaged.is_empty() {
+            println!("{} {} (not staged)", "!".yellow(), path);
+        }
+        removed.extend(unstaged);
+    }
+    
+    for path in &removed {
         println!("{} {}", "-".red(), path.yellow());
     }
     
+    index.save()?;
+    
     println!();
-    println!("{} Removed {} files from staging", "✓".green(), paths.len());
+    println!("{} Removed {} files from staging", "✓".green(), removed.len());
     
     Ok(())
 }
+
+/// Reset one index entry to HEAD, dropping it when HEAD does not have the
+/// path. Returns whether anything was staged there.
+fn unstage(index: &mut Index, head_files: &BTreeMap<String, TreeEntry>, path: &str) -> bool {
+    match head_files.get(path) {
+        Some(original) => {
+            let staged_as_head = index.get(path)
+                .is_some_and(|e| e.hash == original.hash && format!("{:o}", e.mode) == original.mode);
+            if staged_as_head {
+                return false;
+            }
+            match u32::from_str_radix(&original.mode, 8) {
+                Ok(mode) => {
+                    index.add(IndexEntry::without_stat(path.to_string(), mode, original.hash.clone()));
+                    true
+                }
+                Err(_) => false,
+            }
+        }
+        None => index.remove(path).is_some(),
+    }
+}
@@ -0,0 +1,82 @@
+// TriForge/src/commands/show.rs
+use colored::*;
+use std::io::Write;
+use crate::commands;
+use crate::native_git::{Repository, CommitBuilder, ObjectType, TreeBuilder, TagBuilder};
+use crate::native_git::diff::{self, Snapshot};
+
+pub fn execute(revision: &str) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let hash = repo.resolve(revision)?;
+    show_object(&repo, &hash, revision)
+}
+
+fn show_object(repo: &Repository, hash: &str, revision: &str) -> anyhow::Result<()> {
+    let obj = repo.load_object(hash)?;
+    
+    match obj.obj_type {
+        ObjectType::Commit => {
+            let commit = CommitBuilder::parse(&obj)?;
+            
+            println!("{} {}", "commit".yellow().bold(), hash.yellow());
+            if commit.parents.len() > 1 {
+                let parents: Vec<&str> = commit.parents.iter().map(|p| &p[..8]).collect();
+                println!("{}  {}", "Merge:".bold(), parents.join(" "));
+            }
+            println!("{} {}", "Author:".bold(), commit.author);
+            println!("{}   {}", "Date:".bold(), commit.author.datetime().format("%a %b %-d %H:%M:%S %Y %z"));
+            println!();
+            for line in commit.message.lines() {
+                println!("    {}", line);
+            }
+            println!();
+            
+            // Merges would need a combined diff; show only their header
+            if commit.parents.len() > 1 {
+                return Ok(());
+            }
+            
+            let old = match commit.parents.first() {
+                Some(parent) => diff::tree_snapshot(repo, &repo.tree_of(parent)?)?,
+                None => Snapshot::new(),
+            };
+            let new = diff::tree_snapshot(repo, &commit.tree)?;
+            let changes = diff::detect_renames(diff::compare(&old, &new), |path, entry| {
+                diff::load_content(repo, path, entry)
+            })?;
+            commands::diff::print_patch(repo, &changes)?;
+        }
+        ObjectType::Tag => {
+            let tag = TagBuilder::parse(&obj)?;
+            
+            println!("{} {}", "tag".yellow().bold(), tag.name.yellow());
+            if let Some(tagger) = &tag.tagger {
+                println!("{} {}", "Tagger:".bold(), tagger);
+                println!("{}   {}", "Date:".bold(), tagger.datetime().format("%a %b %-d %H:%M:%S %Y %z"));
+            }
+            println!();
+            if !tag.message.is_empty() {
+                println!("{}", tag.message);
+                println!();
+            }
+            
+            show_object(repo, &tag.target, revision)?;
+        }
+        ObjectType::Tree => {
+            println!("{} {}", "tree".yellow().bold(), revision);
+            println!();
+            for entry in TreeBuilder::parse(&obj)? {
+                if entry.is_tree() {
+                    println!("{}/", entry.name.blue());
+                } else {
+                    println!("{}", entry.name);
+                }
+            }
+        }
+        ObjectType::Blob => {
+            std::io::stdout().write_all(&obj.content)?;
+        }
+    }
+    
+    Ok(())
+}
@@ -0,0 +1,317 @@
+// TriForge/src/commands/stash.rs
+use colored::*;
+use std::collections::BTreeMap;
+use std::fs;
+use crate::commands;
+use crate::native_git::{CommitBuilder, Index, IndexEntry, Repository, TreeBuilder};
+use crate::native_git::diff;
+use crate::native_git::index::file_mode;
+use crate::native_git::merge::{self, MergeLabels};
+use crate::native_git::reflog::{self, ReflogEntry};
+use crate::native_git::tree::TreeEntry;
+
+const STASH_REF: &str = "refs/stash";
+
+/// Save staged and unstaged changes to tracked files as a stash commit and
+/// reset the working tree and index to HEAD.
+///
+/// Like Git, the stash is a commit of the working tree whose parents are
+/// HEAD and a commit of the index, and `refs/stash` keeps one reflog entry
+/// per stash.
+pub fn push(message: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let head = repo.head_commit()
+        .map_err(|_| anyhow::anyhow!("You do not have the initial commit yet"))?;
+    let mut index = repo.index()?;
+    
+    if !index.conflicts().is_empty() {
+        anyhow::bail!("Cannot stash while there are unresolved merge conflicts");
+    }
+    
+    let head_files = TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(&head)?)?;
+    let dirty = commands::checkout::dirty_paths(&repo, &index, &head_files)?;
+    if dirty.is_empty() {
+        println!("{} No local changes to save", "→".blue());
+        return Ok(());
+    }
+    
+    let head_commit = CommitBuilder::parse(&repo.load_object(&head)?)?;
+    let branch = repo.refs().current_branch()?
+        .unwrap_or_else(|| "(no branch)".to_string());
+    let subject = head_commit.message.lines().next().unwrap_or_default();
+    let base = format!("{}: {} {}", branch, &head[..7], subject);
+    let (author, committer) = commands::commit::signatures(&repo, None)?;
+    
+    // The staging area as it is
+    let index_tree = TreeBuilder::write_paths(
+        repo.git_dir(),
+        index.entries().map(|e| (e.path.clone(), format!("{:o}", e.mode), e.hash.clone())),
+    )?;
+    let index_commit = repo.store_object(
+        &CommitBuilder::new(index_tree, format!("index on {}\n", base))
+            .author(author.clone())
+            .committer(committer.clone())
+            .parent(head.clone())
+            .build()?,
+    )?;
+    
+    // Tracked files as they are on disk
+    let mut work_files = Vec::new();
+    for entry in index.entries() {
+        let meta = match fs::symlink_metadata(repo.work_dir().join(&entry.path)) {
+            Ok(m) if !m.is_dir() => m,
+            _ => continue,
+        };
+        let hash = if entry.matches_metadata(&meta) {
+            entry.hash.clone()
+        } else {
+            repo.store_object(&repo.create_blob(&repo.read_work_file(&entry.path)?)?)?
+        };
+        work_files.push((entry.path.clone(), format!("{:o}", file_mode(&meta)), hash));
+    }
+    let work_tree = TreeBuilder::write_paths(repo.git_dir(), work_files)?;
+    
+    let description = match message {
+        Some(message) => format!("On {}: {}", branch, message),
+        None => format!("WIP on {}", base),
+    };
+    let stash_commit = repo.store_object(
+        &CommitBuilder::new(work_tree, format!("{}\n", description))
+            .author(author)
+            .committer(committer)
+            .parent(head.clone())
+            .parent(index_commit)
+            .build()?,
+    )?;
+    
+    // The ref's reflog is the stash list
+    repo.refs().update(STASH_REF, &stash_commit, &description)?;
+    
+    // Throw the saved changes away: every dirty path is rewritten or removed
+    let mut from = head_files.clone();
+    for path in dirty.keys() {
+        from.insert(path.clone(), TreeEntry {
+            mode: String::new(),
+            name: path.clone(),
+            hash: String::new(),
+        });
+    }
+    repo.switch_tree(&mut index, &from, &head_files)?;
+    index.save()?;
+    
+    println!("{} Saved working directory and index state: {}", "✓".green(), description.cyan());
+    Ok(())
+}
+
+pub fn list() -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let entries = stash_entries(&repo)?;
+    
+    if entries.is_empty() {
+        println!("{} No stashes", "→".blue());
+        return Ok(());
+    }
+    
+    for (n, entry) in entries.iter().enumerate() {
+        println!("{}: {}", format!("stash@{{{}}}", n).yellow(), entry.message);
+    }
+    Ok(())
+}
+
+/// Show the changes a stash records relative to the commit it was made on
+pub fn show(stash: Option<&str>, patch: bool) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let (_, entry) = find(&repo, stash)?;
+    let stash_commit = CommitBuilder::parse(&repo.load_object(&entry.new)?)?;
+    let base = stash_commit.parents.first()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a stash commit", &entry.new[..8]))?;
+    
+    let old = diff::tree_snapshot(&repo, &repo.tree_of(base)?)?;
+    let new = diff::tree_snapshot(&repo, &stash_commit.tree)?;
+    let changes = diff::detect_renames(diff::compare(&old, &new), |path, entry| {
+        diff::load_content(&repo, path, entry)
+    })?;
+    
+    if patch {
+        commands::diff::print_patch(&repo, &changes)
+    } else {
+        commands::diff::print_stat(&repo, &changes)
+    }
+}
+
+/// Re-apply a stash on top of the current HEAD, leaving it in the stash list
+pub fn apply(stash: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let (n, entry) = find(&repo, stash)?;
+    
+    if apply_entry(&repo, &entry)? {
+        println!("{} Applied stash@{{{}}}", "✓".green(), n);
+    }
+    Ok(())
+}
+
+/// Apply a stash and drop it, unless applying it left conflicts
+pub fn pop(stash: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let (n, entry) = find(&repo, stash)?;
+    
+    if !apply_entry(&repo, &entry)? {
+        println!("{} The stash entry is kept in case you need it again", "→".blue());
+        return Ok(());
+    }
+    drop_entry(&repo, n)?;
+    
+    println!("{} Dropped stash@{{{}}} ({})", "✓".green(), n, entry.new[..8].to_string().dimmed());
+    Ok(())
+}
+
+pub fn drop(stash: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    let (n, entry) = find(&repo, stash)?;
+    drop_entry(&repo, n)?;
+    
+    println!("{} Dropped stash@{{{}}} ({})", "✓".green(), n, entry.new[..8].to_string().dimmed());
+    Ok(())
+}
+
+/// Stashes newest first, so position `n` is `stash@{n}`
+fn stash_entries(repo: &Repository) -> anyhow::Result<Vec<ReflogEntry>> {
+    let mut entries = reflog::read(repo.git_dir(), STASH_REF)?;
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Look up `stash@{n}` (or plain `n`), defaulting to the latest stash
+fn find(repo: &Repository, stash: Option<&str>) -> anyhow::Result<(usize, ReflogEntry)> {
+    let n = match stash {
+        None => 0,
+        Some(name) => {
+            let position = name.strip_prefix("stash@{")
+                .and_then(|rest| rest.strip_suffix('}'))
+                .unwrap_or(name);
+            position.parse()
+                .map_err(|_| anyhow::anyhow!("'{}' is not a stash reference (expected stash@{{n}})", name))?
+        }
+    };
+    
+    let entries = stash_entries(repo)?;
+    if entries.is_empty() {
+        anyhow::bail!("No stash entries found");
+    }
+    let entry = entries.get(n).cloned()
+        .ok_or_else(|| anyhow::anyhow!("stash@{{{}}} does not exist; there are {} stashes", n, entries.len()))?;
+    Ok((n, entry))
+}
+
+/// Remove `stash@{n}` and point `refs/stash` at whatever is now newest
+fn drop_entry(repo: &Repository, n: usize) -> anyhow::Result<()> {
+    let mut entries = stash_entries(repo)?;
+    entries.remove(n);
+    
+    match entries.first() {
+        Some(latest) => repo.refs().update(STASH_REF, &latest.new, "stash: drop")?,
+        None => repo.refs().delete(STASH_REF)?,
+    }
+    
+    // Rewriting the log replaces the entry the update just added
+    entries.reverse();
+    reflog::write(repo.git_dir(), STASH_REF, &entries)
+}
+
+/// Three-way merge a stash into the working tree, with the commit it was
+/// made on as the base. Changes come back unstaged, except new files.
+/// Returns false when conflicts were left for the user.
+fn apply_entry(repo: &Repository, entry: &ReflogEntry) -> anyhow::Result<bool> {
+    let head = repo.head_commit()?;
+    let mut index = repo.index()?;
+    if !index.conflicts().is_empty() {
+        anyhow::bail!("Cannot apply a stash while there are unresolved merge conflicts");
+    }
+    
+    let stash_commit = CommitBuilder::parse(&repo.load_object(&entry.new)?)?;
+    let base = stash_commit.parents.first()
+        .ok_or_else(|| anyhow::anyhow!("{} is not a stash commit", &entry.new[..8]))?;
+    
+    let base_files = TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(base)?)?;
+    let head_files = TreeBuilder::flatten(repo.git_dir(), &repo.tree_of(&head)?)?;
+    let stash_files = TreeBuilder::flatten(repo.git_dir(), &stash_commit.tree)?;
+    
+    let labels = MergeLabels { ours: "Updated upstream", theirs: "Stashed changes" };
+    let result = merge::merge_trees(repo, &base_files, &head_files, &stash_files, &labels)?;
+    
+    let same = |a: Option<&TreeEntry>, b: Option<&TreeEntry>| match (a, b) {
+        (Some(a), Some(b)) => a.hash == b.hash && a.mode == b.mode,
+        (None, None) => true,
+        _ => false,
+    };
+    let touched: Vec<&String> = head_files.keys()
+        .chain(result.files.keys())
+        .filter(|path| !same(head_files.get(*path), result.files.get(*path)))
+        .chain(result.conflicts.iter().map(|c| &c.path))
+        .collect();
+    
+    // Local changes and untracked files the stash would overwrite must be
+    // dealt with first
+    let dirty = commands::checkout::dirty_paths(repo, &index, &head_files)?;
+    let untracked = |path: &String| {
+        !head_files.contains_key(path)
+            && index.get(path).is_none()
+            && repo.work_dir().join(path).symlink_metadata().is_ok()
+    };
+    let mut blocked: Vec<&String> = touched.iter()
+        .copied()
+        .filter(|path| dirty.contains_key(*path) || untracked(path))
+        .collect();
+    blocked.sort();
+    blocked.dedup();
+    if !blocked.is_empty() {
+        println!("{} Your local changes or untracked files would be overwritten by the stash:", "✗".red());
+        for path in &blocked {
+            println!("    {}", path.yellow());
+        }
+        anyhow::bail!("Commit, stash or move them before applying");
+    }
+    
+    repo.switch_tree(&mut index, &head_files, &result.files)?;
+    unstage(&mut index, &head_files, &result.files);
+    
+    for conflict in &result.conflicts {
+        repo.write_work_file(&conflict.path, &conflict.mode, &conflict.content)?;
+        
+        index.remove(&conflict.path);
+        for (stage, side) in [(1, &conflict.base), (2, &conflict.ours), (3, &conflict.theirs)] {
+            if let Some(side) = side {
+                index.add(IndexEntry::unmerged(
+                    conflict.path.clone(),
+                    u32::from_str_radix(&side.mode, 8)?,
+                    side.hash.clone(),
+                    stage,
+                ));
+            }
+        }
+    }
+    index.save()?;
+    
+    if !result.conflicts.is_empty() {
+        for conflict in &result.conflicts {
+            println!("{} CONFLICT: {}", "✗".red(), conflict.path.yellow());
+        }
+        println!("{} Fix conflicts and stage them with {}", "→".blue(), "triforge add".cyan());
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Point the index back at HEAD's version of files the stash modified, so
+/// restored changes show up as unstaged
+fn unstage(index: &mut Index, head_files: &BTreeMap<String, TreeEntry>, applied: &BTreeMap<String, TreeEntry>) {
+    for (path, entry) in applied {
+        if let Some(original) = head_files.get(path) {
+            if original.hash != entry.hash || original.mode != entry.mode {
+                if let Ok(mode) = u32::from_str_radix(&original.mode, 8) {
+                    index.add(IndexEntry::without_stat(path.clone(), mode, original.hash.clone()));
+                }
+            }
+        }
+    }
+}
@@ -1,21 +1,59 @@
 use colored::*;
-use crate::native_git::Repository;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use crate::native_git::{Repository, Index};
+use crate::native_git::diff::{self, ChangeStatus, FileChange, Snapshot};
+use crate::native_git::merge;
 
 pub fn execute(short: bool) -> anyhow::Result<()> {
     let repo = Repository::open(".")?;
+    let index = repo.index()?;
     
-    if !short {
-        println!("{}", "Repository Status".cyan().bold());
-        println!("{}", "═".repeat(60).cyan());
-        println!();
+    let head = repo.head_commit().ok();
+    let head_snapshot = match &head {
+        Some(commit) => diff::tree_snapshot(&repo, &repo.tree_of(commit)?)?,
+        None => Snapshot::new(),
+    };
+    
+    // Conflicted paths have no stage-0 entry and are reported on their own
+    let conflicts = index.conflicts();
+    let head_snapshot: Snapshot = head_snapshot.into_iter()
+        .filter(|(path, _)| !conflicts.contains(path))
+        .collect();
+    
+    let index_snapshot = diff::index_snapshot(&index);
+    let work_snapshot = diff::worktree_snapshot(&repo, &index, index_snapshot.keys())?;
+    
+    let staged = diff::detect_renames(diff::compare(&head_snapshot, &index_snapshot), |path, entry| {
+        diff::load_content(&repo, path, entry)
+    })?;
+    let unstaged = diff::compare(&index_snapshot, &work_snapshot);
+    let untracked = untracked_paths(&repo, &index)?;
+    
+    if short {
+        print_short(&index, &staged, &unstaged, &conflicts, &untracked);
+        return Ok(());
     }
     
+    println!("{}", "Repository Status".cyan().bold());
+    println!("{}", "═".repeat(60).cyan());
+    println!();
+    
     // Get current branch/commit
-    match repo.head_commit() {
-        Ok(commit) => {
+    match repo.refs().current_branch()? {
+        Some(branch) => {
+            println!("{} On branch {}", "→".blue(), branch.yellow());
+        }
+        None => {
+            let commit = head.as_deref().unwrap_or_default();
+            println!("{} HEAD detached at {}", "→".blue(), commit[..8.min(commit.len())].to_string().yellow());
+        }
+    }
+    match &head {
+        Some(commit) => {
             println!("{} HEAD: {}", "→".blue(), commit[..8].to_string().yellow());
         }
-        Err(_) => {
+        None => {
             println!("{} HEAD: {}", "→".blue(), "no commits yet".dimmed());
         }
     }
@@ -28,5 +66,176 @@ pub fn execute(short: bool) -> anyhow::Result<()> {
     let size = repo.size()?;
     println!("{} Size: {} KB", "→".blue(), (size / 1024).to_string().yellow());
     
+    if merge::merge_head(&repo).is_some() {
+        println!("{} Merge in progress", "!".yellow());
+        if conflicts.is_empty() {
+            println!("  {}", "All conflicts fixed, run 'triforge merge --continue'".dimmed());
+        } else {
+            println!("  {}", "Fix conflicts and run 'triforge merge --continue'".dimmed());
+        }
+    }
+    
+    if !staged.is_empty() {
+        println!();
+        println!("{}", "Changes to be committed:".green().bold());
+        for change in &staged {
+            let line = format!("{:<12}{}", format!("{}:", describe(change)), display_path(change));
+            println!("    {}", line.green());
+        }
+    }
+    
+    if !conflicts.is_empty() {
+        println!();
+        println!("{}", "Unmerged paths:".red().bold());
+        for path in &conflicts {
+            let line = format!("{:<16}{}", format!("{}:", conflict_description(&index.stages(path))), path);
+            println!("    {}", line.red());
+        }
+    }
+    
+    if !unstaged.is_empty() {
+        println!();
+        println!("{}", "Changes not staged for commit:".yellow().bold());
+        for change in &unstaged {
+            let line = format!("{:<12}{}", format!("{}:", describe(change)), change.path());
+            println!("    {}", line.red());
+        }
+    }
+    
+    if !untracked.is_empty() {
+        println!();
+        println!("{}", "Untracked files:".bold());
+        for path in &untracked {
+            println!("    {}", path.red());
+        }
+    }
+    
+    println!();
+    if staged.is_empty() && unstaged.is_empty() && conflicts.is_empty() {
+        if untracked.is_empty() {
+            println!("{} Nothing to commit, working tree clean", "✓".green());
+        } else {
+            println!("{} Nothing staged, use 'triforge add' to track files", "→".blue());
+        }
+    }
+    
     Ok(())
 }
+
+/// Porcelain `XY path` lines: X is the index against HEAD, Y the working
+/// tree against the index
+fn print_short(index: &Index, staged: &[FileChange], unstaged: &[FileChange], conflicts: &[String], untracked: &[String]) {
+    let mut lines: BTreeMap<String, (char, char, Option<String>)> = BTreeMap::new();
+    
+    for change in staged {
+        let entry = lines.entry(change.path().to_string()).or_insert((' ', ' ', None));
+        entry.0 = status_letter(change.status);
+        if let ChangeStatus::Renamed(_) = change.status {
+            entry.2 = Some(change.old_path.clone());
+        }
+    }
+    for change in unstaged {
+        let entry = lines.entry(change.path().to_string()).or_insert((' ', ' ', None));
+        entry.1 = status_letter(change.status);
+    }
+    for path in conflicts {
+        let code = conflict_code(&index.stages(path));
+        lines.insert(path.clone(), (code.0, code.1, None));
+    }
+    
+    for (path, (x, y, from)) in &lines {
+        match from {
+            Some(old) => println!("{}{} {} -> {}", x, y, old, path),
+            None => println!("{}{} {}", x, y, path),
+        }
+    }
+    for path in untracked {
+        println!("?? {}", path);
+    }
+}
+
+fn status_letter(status: ChangeStatus) -> char {
+    match status {
+        ChangeStatus::Added => 'A',
+        ChangeStatus::Deleted => 'D',
+        ChangeStatus::Modified => 'M',
+        ChangeStatus::Renamed(_) => 'R',
+    }
+}
+
+fn describe(change: &FileChange) -> &'static str {
+    match change.status {
+        ChangeStatus::Added => "new file",
+        ChangeStatus::Deleted => "deleted",
+        ChangeStatus::Modified => "modified",
+        ChangeStatus::Renamed(_) => "renamed",
+    }
+}
+
+fn display_path(change: &FileChange) -> String {
+    match change.status {
+        ChangeStatus::Renamed(_) => format!("{} -> {}", change.old_path, change.new_path),
+        _ => change.path().to_string(),
+    }
+}
+
+/// Porcelain code for an unmerged path, from the stages present in the index
+fn conflict_code(stages: &[u16]) -> (char, char) {
+    match stages {
+        [1, 2, 3] => ('U', 'U'),
+        [2, 3] => ('A', 'A'),
+        [1, 2] => ('U', 'D'),
+        [1, 3] => ('D', 'U'),
+        [2] => ('A', 'U'),
+        [3] => ('U', 'A'),
+        _ => ('D', 'D'),
+    }
+}
+
+fn conflict_description(stages: &[u16]) -> &'static str {
+    match conflict_code(stages) {
+        ('U', 'U') => "both modified",
+        ('A', 'A') => "both added",
+        ('U', 'D') => "deleted by them",
+        ('D', 'U') => "deleted by us",
+        ('A', 'U') => "added by us",
+        ('U', 'A') => "added by them",
+        _ => "both deleted",
+    }
+}
+
+/// Non-ignored files the index does not know about. Directories
+/// holding no tracked files are collapsed to `dir/`.
+fn untracked_paths(repo: &Repository, index: &Index) -> anyhow::Result<Vec<String>> {
+    let mut tracked_dirs = BTreeSet::new();
+    let mut tracked = BTreeSet::new();
+    for path in index.entries().map(|e| e.path.clone()).chain(index.conflicts()) {
+        let mut dir = Path::new(&path).parent();
+        while let Some(d) = dir.filter(|d| !d.as_os_str().is_empty()) {
+            tracked_dirs.insert(d.to_string_lossy().replace('\\', "/"));
+            dir = d.parent();
+        }
+        tracked.insert(path);
+    }
+    
+    let mut untracked = BTreeSet::new();
+    for rel_path in repo.ignore_rules()?.walk(repo.work_dir())? {
+        if tracked.contains(&rel_path) {
+            continue;
+        }
+        
+        // Report the outermost directory that contains nothing tracked
+        let mut shown = rel_path.clone();
+        let parts: Vec<&str> = rel_path.split('/').collect();
+        for depth in 1..parts.len() {
+            let dir = parts[..depth].join("/");
+            if !tracked_dirs.contains(&dir) {
+                shown = format!("{}/", dir);
+                break;
+            }
+        }
+        untracked.insert(shown);
+    }
+    
+    Ok(untracked.into_iter().collect())
+}
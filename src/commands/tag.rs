@@ -1,75 +1,126 @@
-// TriForge/src/commands/tags.rs - Complete implementation
+// TriForge/src/commands/tag.rs
 use colored::*;
-use crate::{api, config::AppConfig};
+use crate::commands;
+use crate::native_git::{ObjectType, Repository, TagBuilder};
+use crate::native_git::{ignore, refs};
+use crate::native_git::refs::Expected;
 
-pub async fn add(hash: &str, tags: Vec<String>) -> anyhow::Result<()> {
-    let config = AppConfig::load()?;
+pub fn list(pattern: Option<&str>) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
     
-    if config.auth_token.is_none() {
-        anyhow::bail!("Not authenticated. Run 'triforge login' first.");
-    }
+    println!("{}", "Tags:".cyan().bold());
+    println!();
     
-    let client = api::ApiClient::new(config);
+    let mut tags = repo.refs().list("refs/tags")?;
+    tags.sort();
     
-    println!("{} Adding tags to repository...", "→".blue());
-    client.add_tags(hash, tags.clone()).await?;
+    let tags: Vec<String> = tags.into_iter()
+        .map(|t| t.strip_prefix("refs/tags/").unwrap_or(&t).to_string())
+        .filter(|t| pattern.map(|p| ignore::wildmatch(p.as_bytes(), t.as_bytes())).unwrap_or(true))
+        .collect();
     
-    println!("{} Added tags: {}", "✓".green(), tags.join(", ").yellow());
-    Ok(())
-}
-
-pub async fn list(hash: Option<String>) -> anyhow::Result<()> {
-    let config = AppConfig::load()?;
-    let client = api::ApiClient::new(config);
+    if tags.is_empty() {
+        println!("{} No tags yet", "→".blue());
+        return Ok(());
+    }
     
-    if let Some(h) = hash {
-        // List tags for specific repository
-        let display_hash = if h.len() > 16 { &h[..16] } else { &h };
-        println!("{} Tags for {}:", "→".blue(), display_hash.yellow());
-        let tags = client.get_repo_tags(&h).await?;
-        
-        if tags.is_empty() {
-            println!("   No tags");
-        } else {
-            for tag in tags {
-                println!("   • {}", tag.cyan());
+    for tag in tags {
+        let hash = repo.refs().read(&format!("refs/tags/{}", tag))?;
+        match repo.load_object(&hash) {
+            Ok(obj) if obj.obj_type == ObjectType::Tag => {
+                let parsed = TagBuilder::parse(&obj)?;
+                println!("  {} ({} {}) {}",
+                    tag.yellow(),
+                    parsed.target_type.as_str().dimmed(),
+                    parsed.target[..8].to_string().dimmed(),
+                    parsed.message.lines().next().unwrap_or_default()
+                );
+            }
+            Ok(obj) => {
+                println!("  {} ({} {})", tag.yellow(), obj.obj_type.as_str().dimmed(), hash[..8].to_string().dimmed());
+            }
+            Err(_) => {
+                println!("  {} (invalid)", tag.dimmed());
             }
-        }
-    } else {
-        // List all available tags
-        println!("{}", "All Tags".cyan().bold());
-        println!("{}", "═".repeat(60).cyan());
-        println!();
-        
-        let tags = client.get_all_tags().await?;
-        
-        for (tag, count) in tags {
-            println!("  {} ({})", tag.yellow(), count.to_string().dimmed());
         }
     }
     
     Ok(())
 }
 
-pub async fn search(tag: &str) -> anyhow::Result<()> {
-    let config = AppConfig::load()?;
-    let client = api::ApiClient::new(config);
+/// Tag `target` (HEAD by default). With a message the tag is an annotated
+/// tag object carrying the tagger; without one it is a plain ref.
+pub fn create(
+    name: &str,
+    target: Option<&str>,
+    message: Option<&str>,
+    annotate: bool,
+    force: bool,
+) -> anyhow::Result<()> {
+    let repo = Repository::open(".")?;
+    refs::validate_name(name)?;
     
-    println!("{} Repositories tagged with '{}':", "→".blue(), tag.yellow());
-    println!();
+    let tag_ref = format!("refs/tags/{}", name);
+    let previous = repo.refs().read(&tag_ref).ok();
+    if previous.is_some() && !force {
+        anyhow::bail!("Tag '{}' already exists (use --force to replace it)", name);
+    }
     
-    let repos = client.get_repos_by_tag(tag).await?;
+    let target_hash = repo.resolve(target.unwrap_or("HEAD"))?;
+    let target_type = repo.load_object(&target_hash)?.obj_type;
     
-    if repos.is_empty() {
-        println!("   No repositories found");
-        return Ok(());
-    }
+    let value = match message {
+        Some(message) => {
+            let (_, tagger) = commands::commit::signatures(&repo, None)?;
+            let tag = TagBuilder::new(target_hash.clone(), target_type, name.to_string(), message.to_string())
+                .tagger(tagger)
+                .build()?;
+            repo.store_object(&tag)?
+        }
+        None if annotate => anyhow::bail!("Annotated tags need a message: use -m <message>"),
+        None => target_hash.clone(),
+    };
     
-    for repo in repos {
-        println!("  • {}", repo.name.cyan());
-        println!("    {}", repo.repo_hash[..16].to_string().dimmed());
-        println!();
+    // Fail rather than clobber a tag created or moved since we looked
+    let expected = match &previous {
+        Some(previous) => Expected::Value(previous.clone()),
+        None => Expected::Missing,
+    };
+    repo.refs().transaction()
+        .update(&tag_ref, &value, expected)
+        .commit(&format!("tag: tagging {} ({})", &target_hash[..7], target_type.as_str()))?;
+    
+    let kind = if message.is_some() { "annotated tag" } else { "tag" };
+    println!("{} Created {}: {} at {} ({})", "✓".green(), kind, name.yellow(),
+        target_hash[..8].to_string().dimmed(),
+        target_type.as_str()
+    );
+    if let Some(previous) = previous.filter(|p| *p != value) {
+        println!("{} Replaced previous tag ({})", "!".yellow(), previous[..8].to_string().dimmed());
     }
+    Ok(())
+}
+
+pub fn delete(name: &str) -> anyhow::Result<()> {
+    refs::validate_name(name)?;
+    let repo = Repository::open(".")?;
+    let tag_ref = format!("refs/tags/{}", name);
+    
+    let hash = repo.refs().read(&tag_ref)
+        .map_err(|_| anyhow::anyhow!("Tag '{}' not found", name))?;
+    repo.refs().delete(&tag_ref)?;
     
+    println!("{} Deleted tag: {} (was {})", "✓".green(), name.yellow(), hash[..8].to_string().dimmed());
     Ok(())
 }
+
+pub fn show(name: &str) -> anyhow::Result<()> {
+    refs::validate_name(name)?;
+    let repo = Repository::open(".")?;
+    let tag_ref = format!("refs/tags/{}", name);
+    
+    if repo.refs().read(&tag_ref).is_err() {
+        anyhow::bail!("Tag '{}' not found", name);
+    }
+    commands::show::execute(&tag_ref)
+}
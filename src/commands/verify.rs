@@ -1,7 +1,16 @@
+// TriForge/src/commands/verify.rs
 use colored::*;
-use crate::native_git::Repository;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs;
+use base64::{Engine as _, engine::general_purpose};
+use crate::{api, commands, config::AppConfig};
+use crate::native_git::{CommitBuilder, GitObject, ObjectType, Repository, TreeBuilder};
+use crate::native_git::{hash, revparse};
 
-pub fn execute(fix: bool) -> anyhow::Result<()> {
+/// Where `--fix` moves corrupt loose objects, relative to `.git`
+const QUARANTINE_DIR: &str = "quarantine";
+
+pub async fn execute(fix: bool, remote: &str) -> anyhow::Result<()> {
     println!("{}", "Verifying repository...".cyan());
     println!();
     
@@ -10,24 +19,237 @@ pub fn execute(fix: bool) -> anyhow::Result<()> {
     
     println!("{} Checking {} objects...", "→".blue(), objects.len());
     
-    let mut valid = 0;
-    let mut invalid = 0;
+    // Re-hash every object against its name
+    let mut valid: BTreeMap<String, GitObject> = BTreeMap::new();
+    let mut corrupt: BTreeSet<String> = BTreeSet::new();
     
     for obj_hash in &objects {
         match repo.load_object(obj_hash) {
-            Ok(_) => valid += 1,
-            Err(_) => {
-                invalid += 1;
-                println!("{} Invalid object: {}", "✗".red(), obj_hash[..8].to_string());
+            Ok(obj) if hash::verify_hash(&obj.data(), obj_hash) => {
+                valid.insert(obj_hash.clone(), obj);
+            }
+            Ok(_) => {
+                println!("{} Corrupt object: {} (content does not match its hash)", "✗".red(), &obj_hash[..8]);
+                corrupt.insert(obj_hash.clone());
+            }
+            Err(e) => {
+                println!("{} Corrupt object: {} ({})", "✗".red(), &obj_hash[..8], e);
+                corrupt.insert(obj_hash.clone());
             }
         }
     }
     
+    // Every object referenced by a commit, tree or tag must exist
+    let mut referenced: HashSet<String> = HashSet::new();
+    let mut missing: BTreeSet<String> = BTreeSet::new();
+    
+    for (obj_hash, obj) in &valid {
+        let links = match references(obj) {
+            Ok(links) => links,
+            Err(e) => {
+                println!("{} Unreadable {}: {} ({})", "✗".red(), obj.obj_type.as_str(), &obj_hash[..8], e);
+                corrupt.insert(obj_hash.clone());
+                continue;
+            }
+        };
+        for (target, role) in links {
+            if !valid.contains_key(&target) && !corrupt.contains(&target) && missing.insert(target.clone()) {
+                println!("{} Missing object: {} ({} of {})", "✗".red(), &target[..8], role, &obj_hash[..8]);
+            }
+            referenced.insert(target);
+        }
+    }
+    
+    for obj_hash in &corrupt {
+        valid.remove(obj_hash);
+    }
+    
+    // Refs must hold a valid object, and branches a commit
+    let broken_refs = check_refs(&repo, &valid, &corrupt, &mut missing)?;
+    
+    // Objects nothing points at
+    let tips: HashSet<String> = repo.refs().tips()?.into_iter().collect();
+    let staged: HashSet<String> = repo.index()?.entries().map(|e| e.hash.clone()).collect();
+    let dangling: Vec<&GitObject> = valid.values()
+        .filter(|o| !referenced.contains(&o.hash) && !tips.contains(&o.hash) && !staged.contains(&o.hash))
+        .collect();
+    for obj in &dangling {
+        println!("{} Dangling {}: {}", "!".yellow(), obj.obj_type.as_str(), &obj.hash[..8]);
+    }
+    
     println!();
-    println!("{} Valid: {}", "✓".green(), valid.to_string().cyan());
-    if invalid > 0 {
-        println!("{} Invalid: {}", "✗".red(), invalid.to_string().red());
+    println!("{} Valid: {}", "✓".green(), valid.len().to_string().cyan());
+    if !corrupt.is_empty() {
+        println!("{} Corrupt: {}", "✗".red(), corrupt.len().to_string().red());
+    }
+    if !missing.is_empty() {
+        println!("{} Missing: {}", "✗".red(), missing.len().to_string().red());
+    }
+    if broken_refs > 0 {
+        println!("{} Broken refs: {}", "✗".red(), broken_refs.to_string().red());
+    }
+    if !dangling.is_empty() {
+        println!("{} Dangling: {}", "!".yellow(), dangling.len().to_string().yellow());
+    }
+    
+    if corrupt.is_empty() && missing.is_empty() {
+        if broken_refs > 0 {
+            anyhow::bail!("Repository has {} broken refs", broken_refs);
+        }
+        return Ok(());
+    }
+    
+    if !fix {
+        println!();
+        println!("{} Run {} to restore them from the remote", "→".blue(), "triforge verify --fix".cyan());
+        anyhow::bail!("Repository is damaged");
     }
     
+    println!();
+    let unrepaired = repair(&repo, remote, &corrupt, &missing).await?;
+    
+    if unrepaired > 0 {
+        anyhow::bail!("{} objects could not be restored", unrepaired);
+    }
+    if broken_refs > 0 {
+        anyhow::bail!("Objects restored, but {} refs are still broken", broken_refs);
+    }
+    
+    println!();
+    println!("{} Repository repaired", "✓".green().bold());
     Ok(())
 }
+
+/// Objects a commit, tree or tag points at, with a description of each link
+fn references(obj: &GitObject) -> anyhow::Result<Vec<(String, &'static str)>> {
+    Ok(match obj.obj_type {
+        ObjectType::Commit => {
+            let commit = CommitBuilder::parse(obj)?;
+            let mut links = vec![(commit.tree, "tree")];
+            links.extend(commit.parents.into_iter().map(|p| (p, "parent")));
+            links
+        }
+        ObjectType::Tree => TreeBuilder::parse(obj)?
+            .into_iter()
+            .filter(|e| e.mode != "160000")
+            .map(|e| (e.hash, "entry"))
+            .collect(),
+        ObjectType::Tag => vec![(revparse::tag_target(&obj.content)?, "target")],
+        ObjectType::Blob => Vec::new(),
+    })
+}
+
+/// Report refs that do not resolve, returning how many are broken. Objects
+/// they name that do not exist are added to `missing`.
+fn check_refs(
+    repo: &Repository,
+    valid: &BTreeMap<String, GitObject>,
+    corrupt: &BTreeSet<String>,
+    missing: &mut BTreeSet<String>,
+) -> anyhow::Result<usize> {
+    let mut names = repo.refs().list("refs")?;
+    names.insert(0, "HEAD".to_string());
+    
+    let mut broken = 0;
+    for name in names {
+        let Ok(value) = repo.refs().read(&name) else {
+            continue;
+        };
+        
+        if let Some(target) = value.strip_prefix("ref: ") {
+            // An unborn HEAD is fine; any other dangling symref is not
+            if repo.refs().read(target).is_err() && name != "HEAD" {
+                println!("{} Broken ref: {} -> {} (target does not exist)", "✗".red(), name.yellow(), target);
+                broken += 1;
+            }
+            continue;
+        }
+        
+        if value.len() != 40 || !value.chars().all(|c| c.is_ascii_hexdigit()) {
+            println!("{} Broken ref: {} (not an object name: {:?})", "✗".red(), name.yellow(), value);
+            broken += 1;
+            continue;
+        }
+        
+        match valid.get(&value) {
+            Some(obj) if name.starts_with("refs/heads/") && obj.obj_type != ObjectType::Commit => {
+                println!("{} Broken ref: {} -> {} (a {}, not a commit)", "✗".red(), name.yellow(), &value[..8], obj.obj_type.as_str());
+                broken += 1;
+            }
+            Some(_) => {}
+            None if corrupt.contains(&value) => {}
+            None => {
+                if missing.insert(value.clone()) {
+                    println!("{} Missing object: {} (target of {})", "✗".red(), &value[..8], name);
+                }
+            }
+        }
+    }
+    
+    Ok(broken)
+}
+
+/// Quarantine corrupt loose objects and download replacements for them and
+/// for missing objects, following references from whatever arrives.
+/// Returns how many objects are still damaged.
+async fn repair(
+    repo: &Repository,
+    remote: &str,
+    corrupt: &BTreeSet<String>,
+    missing: &BTreeSet<String>,
+) -> anyhow::Result<usize> {
+    let repo_hash = commands::remote::hash_for(repo, remote)?;
+    println!("{} Repairing from {} ({})", "→".blue(), remote.yellow(), repo_hash.dimmed());
+    
+    let quarantine = repo.git_dir().join(QUARANTINE_DIR);
+    for obj_hash in corrupt {
+        let path = GitObject::loose_path(repo.git_dir(), obj_hash);
+        if path.exists() {
+            fs::create_dir_all(&quarantine)?;
+            fs::rename(&path, quarantine.join(obj_hash))?;
+            println!("{} Quarantined {}", "✓".green(), obj_hash[..8].to_string().yellow());
+        }
+    }
+    
+    let client = api::ApiClient::new(AppConfig::load()?);
+    let mut queue: Vec<String> = corrupt.iter().chain(missing).cloned().collect();
+    let mut attempted: HashSet<String> = queue.iter().cloned().collect();
+    let mut unrepaired = 0;
+    
+    while let Some(obj_hash) = queue.pop() {
+        let obj = match download(&client, &repo_hash, &obj_hash).await {
+            Ok(obj) => obj,
+            Err(e) => {
+                println!("{} Could not restore {}: {}", "✗".red(), &obj_hash[..8], e);
+                unrepaired += 1;
+                continue;
+            }
+        };
+        
+        obj.store(repo.git_dir())?;
+        println!("{} Restored {} {}", "✓".green(), obj.obj_type.as_str(), obj_hash[..8].to_string().yellow());
+        
+        // A restored tree or commit may lead to more objects we never had
+        for (target, _) in references(&obj)? {
+            let present = repo.load_object(&target)
+                .map(|o| hash::verify_hash(&o.data(), &target))
+                .unwrap_or(false);
+            if !present && attempted.insert(target.clone()) {
+                queue.push(target);
+            }
+        }
+    }
+    
+    Ok(unrepaired)
+}
+
+/// Fetch one object from a Hyrule repository, checking it against its name
+async fn download(client: &api::ApiClient, repo_hash: &str, obj_hash: &str) -> anyhow::Result<GitObject> {
+    let data = client.download_object(repo_hash, obj_hash).await?;
+    let data = general_purpose::STANDARD.decode(&data).unwrap_or(data);
+    
+    if !hash::verify_hash(&data, obj_hash) {
+        anyhow::bail!("remote copy does not match its hash");
+    }
+    GitObject::parse(obj_hash, &data)
+}
@@ -13,6 +13,32 @@ pub struct AppConfig {
     pub use_tor: bool,
     pub tor_proxy: String,
     pub verify_ssl: bool,
+    /// Commit identity used when the repository does not set one
+    #[serde(default)]
+    pub user_name: Option<String>,
+    #[serde(default)]
+    pub user_email: Option<String>,
+    /// Most objects sent or requested in one batch
+    #[serde(default = "default_batch_objects")]
+    pub batch_objects: usize,
+    /// Most base64-encoded bytes sent in one upload batch
+    #[serde(default = "default_batch_bytes")]
+    pub batch_bytes: usize,
+    /// Download requests in flight at once
+    #[serde(default = "default_transfer_concurrency")]
+    pub transfer_concurrency: usize,
+}
+
+fn default_batch_objects() -> usize {
+    100
+}
+
+fn default_batch_bytes() -> usize {
+    4 * 1024 * 1024
+}
+
+fn default_transfer_concurrency() -> usize {
+    4
 }
 
 impl Default for AppConfig {
@@ -25,6 +51,11 @@ impl Default for AppConfig {
             use_tor: false,
             tor_proxy: "socks5h://127.0.0.1:9050".to_string(),
             verify_ssl: true,
+            user_name: None,
+            user_email: None,
+            batch_objects: default_batch_objects(),
+            batch_bytes: default_batch_bytes(),
+            transfer_concurrency: default_transfer_concurrency(),
         }
     }
 }
@@ -71,6 +102,12 @@ impl AppConfig {
             "username" => {
                 self.username = Some(value.to_string());
             }
+            "user.name" | "user_name" => {
+                self.user_name = Some(value.to_string());
+            }
+            "user.email" | "user_email" => {
+                self.user_email = Some(value.to_string());
+            }
             "token" | "auth_token" => {
                 self.auth_token = Some(value.to_string());
             }
@@ -89,6 +126,15 @@ impl AppConfig {
                 self.verify_ssl = value.parse()
                     .map_err(|_| anyhow::anyhow!("Invalid boolean value"))?;
             }
+            "batch.objects" | "batch_objects" => {
+                self.batch_objects = parse_count(value)?;
+            }
+            "batch.bytes" | "batch_bytes" => {
+                self.batch_bytes = parse_count(value)?;
+            }
+            "concurrency" | "transfer_concurrency" => {
+                self.transfer_concurrency = parse_count(value)?;
+            }
             _ => {
                 anyhow::bail!("Unknown configuration key: {}", key);
             }
@@ -100,15 +146,29 @@ impl AppConfig {
         match key {
             "server" | "hyrule_server" => Some(self.hyrule_server.clone()),
             "username" => self.username.clone(),
+            "user.name" | "user_name" => self.user_name.clone(),
+            "user.email" | "user_email" => self.user_email.clone(),
             "token" | "auth_token" => self.auth_token.clone(),
             "private" | "default_private" => Some(self.default_private.to_string()),
             "tor" | "use_tor" => Some(self.use_tor.to_string()),
             "proxy" | "tor_proxy" => Some(self.tor_proxy.clone()),
             "ssl" | "verify_ssl" => Some(self.verify_ssl.to_string()),
+            "batch.objects" | "batch_objects" => Some(self.batch_objects.to_string()),
+            "batch.bytes" | "batch_bytes" => Some(self.batch_bytes.to_string()),
+            "concurrency" | "transfer_concurrency" => Some(self.transfer_concurrency.to_string()),
             _ => None,
         }
     }
     
+    /// Name and email for commits and reflog entries when the repository
+    /// sets none
+    pub fn identity(&self) -> (Option<String>, Option<String>) {
+        (
+            self.user_name.clone().or_else(|| self.username.clone()),
+            self.user_email.clone(),
+        )
+    }
+    
     pub fn check_tor_available(&self) -> bool {
         use std::net::TcpStream;
         use std::time::Duration;
@@ -125,3 +185,11 @@ impl AppConfig {
         ).is_ok()
     }
 }
+
+/// A positive count for the transfer settings
+fn parse_count(value: &str) -> Result<usize> {
+    match value.parse() {
+        Ok(0) | Err(_) => anyhow::bail!("Invalid value: expected a positive number"),
+        Ok(count) => Ok(count),
+    }
+}
@@ -43,13 +43,6 @@ pub fn get_repo_name(repo: &Repository) -> Result<String> {
     Ok(name.to_string())
 }
 
-/// Get HEAD commit
-pub fn get_head_commit(repo: &Repository) -> Result<git2::Commit> {
-    let head = repo.head()?;
-    let commit = head.peel_to_commit()?;
-    Ok(commit)
-}
-
 /// Get all objects in repository
 pub fn get_all_objects(repo: &Repository) -> Result<Vec<Oid>> {
     let mut objects = Vec::new();
@@ -119,10 +112,3 @@ pub fn checkout_head(repo: &Repository) -> anyhow::Result<()> {
     repo.checkout_head(Some(&mut checkout_opts))?;
     Ok(())
 }
-
-/// Set a reference
-pub fn set_ref(repo: &Repository, ref_name: &str, target: &str) -> Result<()> {
-    let oid = git2::Oid::from_str(target)?;
-    repo.reference(ref_name, oid, true, "triforge update")?;
-    Ok(())
-}
@@ -44,6 +44,8 @@ enum Commands {
         message: String,
         #[arg(short, long)]
         all: bool,
+        #[arg(long)]
+        author: Option<String>,
     },
 
     Status {
@@ -52,10 +54,27 @@ enum Commands {
     },
 
     Log {
+        revisions: Vec<String>,
         #[arg(short = 'n', long, default_value = "10")]
         limit: usize,
         #[arg(long)]
         oneline: bool,
+        #[arg(long)]
+        graph: bool,
+        #[arg(long)]
+        topo_order: bool,
+        #[arg(long)]
+        author: Option<String>,
+        #[arg(long)]
+        grep: Option<String>,
+        #[arg(long)]
+        since: Option<String>,
+        #[arg(long)]
+        until: Option<String>,
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(last = true)]
+        paths: Vec<String>,
     },
 
     Diff {
@@ -63,6 +82,8 @@ enum Commands {
         to: Option<String>,
         #[arg(long)]
         name_only: bool,
+        #[arg(long)]
+        stat: bool,
     },
 
     Branch {
@@ -70,19 +91,52 @@ enum Commands {
         action: Option<BranchAction>,
     },
 
+    Tag {
+        #[command(subcommand)]
+        action: Option<TagAction>,
+    },
+
+    Stash {
+        #[command(subcommand)]
+        action: Option<StashAction>,
+    },
+
+    Reflog {
+        name: Option<String>,
+        #[arg(short = 'n', long)]
+        limit: Option<usize>,
+    },
+
     Checkout {
         target: String,
         #[arg(short = 'b', long)]
         create: bool,
+        #[arg(short, long)]
+        force: bool,
     },
 
     Merge {
-        branch: String,
+        #[arg(required_unless_present_any = ["abort", "continue_merge"])]
+        branch: Option<String>,
         #[arg(long)]
         ff_only: bool,
+        #[arg(long, conflicts_with = "continue_merge")]
+        abort: bool,
+        #[arg(long = "continue")]
+        continue_merge: bool,
     },
 
     Push {
+        remote: Option<String>,
+        refspecs: Vec<String>,
+        #[arg(long)]
+        all: bool,
+        #[arg(long)]
+        tags: bool,
+        #[arg(short, long)]
+        force: bool,
+        #[arg(long, value_name = "REF:EXPECTED", num_args = 0..=1, require_equals = true, default_missing_value = "")]
+        force_with_lease: Vec<String>,
         #[arg(short, long)]
         name: Option<String>,
         #[arg(short, long)]
@@ -102,6 +156,11 @@ enum Commands {
         remote: Option<String>,
     },
 
+    Fetch {
+        remote: Option<String>,
+        refspecs: Vec<String>,
+    },
+
     Info { 
         hash: String 
     },
@@ -113,9 +172,26 @@ enum Commands {
         pinned: bool,
     },
 
+    Show {
+        #[arg(default_value = "HEAD")]
+        revision: String,
+    },
+
+    CheckIgnore {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+
+    Gc {
+        #[arg(long, default_value = "2 weeks ago")]
+        prune: String,
+    },
+
     Verify {
         #[arg(short, long)]
         fix: bool,
+        #[arg(long, default_value = "origin")]
+        remote: String,
     },
 
     Remote {
@@ -193,13 +269,66 @@ enum Commands {
 #[derive(Subcommand)]
 enum BranchAction {
     List,
-    Create { name: String },
+    Create {
+        name: String,
+        start: Option<String>,
+    },
     Delete {
         name: String,
         #[arg(short, long)]
         force: bool,
     },
-    Rename { new_name: String },
+    /// Rename a branch, or the current branch when given one name
+    Rename {
+        name: String,
+        new_name: Option<String>,
+        #[arg(short, long)]
+        force: bool,
+    },
+    /// Track <remote>/<branch> or a local branch
+    SetUpstream {
+        upstream: String,
+        branch: Option<String>,
+    },
+    UnsetUpstream {
+        branch: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    List {
+        pattern: Option<String>,
+    },
+    Create {
+        name: String,
+        target: Option<String>,
+        #[arg(short, long)]
+        message: Option<String>,
+        #[arg(short, long)]
+        annotate: bool,
+        #[arg(short, long)]
+        force: bool,
+    },
+    Delete { name: String },
+    Show { name: String },
+}
+
+#[derive(Subcommand)]
+enum StashAction {
+    Push {
+        #[arg(short, long)]
+        message: Option<String>,
+    },
+    List,
+    Show {
+        stash: Option<String>,
+        #[arg(short, long)]
+        patch: bool,
+    },
+    Apply { stash: Option<String> },
+    Pop { stash: Option<String> },
+    Drop { stash: Option<String> },
 }
 
 #[derive(Subcommand)]
@@ -244,40 +373,97 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
         Commands::Remove { paths } => {
             commands::remove::execute(paths)?;
         }
-        Commands::Commit { message, all } => {
-            commands::commit::execute(&message, all)?;
+        Commands::Commit { message, all, author } => {
+            commands::commit::execute(&message, all, author.as_deref())?;
         }
         Commands::Status { short } => {
             commands::status::execute(short)?;
         }
-        Commands::Log { limit, oneline } => {
-            commands::log::execute(limit, oneline)?;
+        Commands::Log { revisions, limit, oneline, graph, topo_order, author, grep, since, until, format, paths } => {
+            commands::log::execute(commands::log::LogOptions {
+                revisions,
+                paths,
+                limit,
+                oneline,
+                graph,
+                topo_order,
+                author,
+                grep,
+                since,
+                until,
+                format,
+            })?;
         }
         Commands::Diff {
             from,
             to,
             name_only,
+            stat,
         } => {
-            commands::diff::execute(from, to, name_only)?;
+            commands::diff::execute(from, to, name_only, stat)?;
         }
         Commands::Branch { action } => match action {
             Some(BranchAction::List) | None => commands::branch::list()?,
-            Some(BranchAction::Create { name }) => commands::branch::create(&name)?,
+            Some(BranchAction::Create { name, start }) => commands::branch::create(&name, start.as_deref())?,
             Some(BranchAction::Delete { name, force }) => commands::branch::delete(&name, force)?,
-            Some(BranchAction::Rename { new_name }) => commands::branch::rename(&new_name)?,
+            Some(BranchAction::Rename { name, new_name: Some(new_name), force }) => {
+                commands::branch::rename(Some(&name), &new_name, force)?
+            }
+            Some(BranchAction::Rename { name, new_name: None, force }) => {
+                commands::branch::rename(None, &name, force)?
+            }
+            Some(BranchAction::SetUpstream { upstream, branch }) => {
+                commands::branch::set_upstream(&upstream, branch.as_deref())?
+            }
+            Some(BranchAction::UnsetUpstream { branch }) => {
+                commands::branch::unset_upstream(branch.as_deref())?
+            }
+        },
+        Commands::Tag { action } => match action {
+            Some(TagAction::List { pattern }) => commands::tag::list(pattern.as_deref())?,
+            None => commands::tag::list(None)?,
+            Some(TagAction::Create { name, target, message, annotate, force }) => {
+                commands::tag::create(&name, target.as_deref(), message.as_deref(), annotate, force)?
+            }
+            Some(TagAction::Delete { name }) => commands::tag::delete(&name)?,
+            Some(TagAction::Show { name }) => commands::tag::show(&name)?,
+        },
+        Commands::Stash { action } => match action {
+            Some(StashAction::Push { message }) => commands::stash::push(message.as_deref())?,
+            None => commands::stash::push(None)?,
+            Some(StashAction::List) => commands::stash::list()?,
+            Some(StashAction::Show { stash, patch }) => commands::stash::show(stash.as_deref(), patch)?,
+            Some(StashAction::Apply { stash }) => commands::stash::apply(stash.as_deref())?,
+            Some(StashAction::Pop { stash }) => commands::stash::pop(stash.as_deref())?,
+            Some(StashAction::Drop { stash }) => commands::stash::drop(stash.as_deref())?,
         },
-        Commands::Checkout { target, create } => {
-            commands::checkout::execute(&target, create)?;
+        Commands::Reflog { name, limit } => {
+            commands::reflog::execute(name.as_deref(), limit)?;
         }
-        Commands::Merge { branch, ff_only } => {
-            commands::merge::execute(&branch, ff_only)?;
+        Commands::Checkout { target, create, force } => {
+            commands::checkout::execute(&target, create, force)?;
+        }
+        Commands::Merge {
+            branch,
+            ff_only,
+            abort,
+            continue_merge,
+        } => {
+            commands::merge::execute(branch.as_deref(), ff_only, abort, continue_merge)?;
         }
         Commands::Push {
+            remote,
+            refspecs,
+            all,
+            tags,
+            force,
+            force_with_lease,
             name,
             description,
             private,
         } => {
-            commands::push::execute(name, description, private, cli.verbose).await?;
+            let selection = commands::push::RefSelection { refspecs, all, tags, force, force_with_lease };
+            commands::push::execute(remote, selection, name, description, private, cli.verbose).await?;
         }
         Commands::Clone {
             hash,
@@ -289,14 +475,26 @@ async fn run(cli: Cli) -> anyhow::Result<()> {
         Commands::Pull { remote } => {
             commands::pull::execute(remote, cli.verbose).await?;
         }
+        Commands::Fetch { remote, refspecs } => {
+            commands::fetch::execute(remote, refspecs, cli.verbose).await?;
+        }
         Commands::Info { hash } => {
             commands::info::execute(&hash).await?;
         }
         Commands::List { starred, pinned } => {
             commands::list::execute(starred, pinned).await?;
         }
-        Commands::Verify { fix } => {
-            commands::verify::execute(fix)?;
+        Commands::Show { revision } => {
+            commands::show::execute(&revision)?;
+        }
+        Commands::CheckIgnore { paths } => {
+            commands::check_ignore::execute(paths, cli.verbose)?;
+        }
+        Commands::Gc { prune } => {
+            commands::gc::execute(&prune, cli.verbose)?;
+        }
+        Commands::Verify { fix, remote } => {
+            commands::verify::execute(fix, &remote).await?;
         }
         Commands::Remote { action } => match action {
             RemoteAction::Add { name, hash } => commands::remote::add(&name, &hash)?,
@@ -1,25 +1,159 @@
 use super::{GitObject, ObjectType};
+use super::config::GitConfig;
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Local, TimeZone};
+use std::env;
+use std::fmt;
+
+/// A name, email and timestamp as recorded on `author`/`committer` lines
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub name: String,
+    pub email: String,
+    /// Seconds since the Unix epoch
+    pub time: i64,
+    /// Offset from UTC in minutes
+    pub offset: i32,
+}
+
+impl Signature {
+    /// Signature stamped with the current time in the local timezone
+    pub fn now(name: &str, email: &str) -> Self {
+        let now = Local::now();
+        Self {
+            name: name.to_string(),
+            email: email.to_string(),
+            time: now.timestamp(),
+            offset: now.offset().local_minus_utc() / 60,
+        }
+    }
+
+    /// Identity for `role` (`AUTHOR` or `COMMITTER`) the way Git resolves
+    /// it: `GIT_<role>_NAME`/`EMAIL`, then the repository's `user.name`/
+    /// `user.email`, then `defaults`. `ident` (`Name <email>`) replaces all
+    /// of them, and `GIT_<role>_DATE` replaces the time. `None` when no name
+    /// or no email is known.
+    pub fn for_role(
+        role: &str,
+        ident: Option<&str>,
+        config: Option<&GitConfig>,
+        defaults: (Option<String>, Option<String>),
+    ) -> Result<Option<Self>> {
+        let from_config = |key: &str| config.and_then(|c| c.get(key)).map(|v| v.to_string());
+        let name = env::var(format!("GIT_{}_NAME", role)).ok()
+            .or_else(|| from_config("user.name"))
+            .or(defaults.0);
+        let email = env::var(format!("GIT_{}_EMAIL", role)).ok()
+            .or_else(|| from_config("user.email"))
+            .or(defaults.1);
+
+        let signature = match (ident, name, email) {
+            (Some(ident), _, _) => Self::parse_ident(ident)?,
+            (None, Some(name), Some(email)) => Self::now(&name, &email),
+            _ => return Ok(None),
+        };
+        match env::var(format!("GIT_{}_DATE", role)) {
+            Ok(date) => signature.with_raw_date(&date).map(Some),
+            Err(_) => Ok(Some(signature)),
+        }
+    }
+
+    /// Parse `Name <email>` into a signature stamped now
+    pub fn parse_ident(ident: &str) -> Result<Self> {
+        let (name, rest) = ident.split_once('<')
+            .ok_or_else(|| anyhow::anyhow!("Expected 'Name <email>', got '{}'", ident))?;
+        let email = rest.strip_suffix('>')
+            .ok_or_else(|| anyhow::anyhow!("Expected 'Name <email>', got '{}'", ident))?;
+        Ok(Self::now(name.trim(), email.trim()))
+    }
+
+    /// Parse the value of an `author`/`committer` line:
+    /// `Name <email> 1700000000 +0100`
+    pub fn parse(line: &str) -> Result<Self> {
+        let mut parts = line.rsplitn(3, ' ');
+        let offset = parts.next().unwrap_or_default();
+        let time = parts.next().unwrap_or_default();
+        let ident = parts.next().unwrap_or_default();
+
+        let mut signature = Self::parse_ident(ident)?;
+        signature.time = time.parse()
+            .map_err(|_| anyhow::anyhow!("Invalid timestamp in '{}'", line))?;
+        signature.offset = parse_offset(offset)
+            .ok_or_else(|| anyhow::anyhow!("Invalid timezone in '{}'", line))?;
+        Ok(signature)
+    }
+
+    /// Replace the timestamp with a Git raw date (`1700000000 +0100`,
+    /// optionally prefixed with `@`)
+    pub fn with_raw_date(mut self, date: &str) -> Result<Self> {
+        let (time, offset) = date.trim().trim_start_matches('@').split_once(' ')
+            .ok_or_else(|| anyhow::anyhow!("Expected '<seconds> <+hhmm>', got '{}'", date))?;
+        self.time = time.parse()
+            .map_err(|_| anyhow::anyhow!("Invalid timestamp in '{}'", date))?;
+        self.offset = parse_offset(offset)
+            .ok_or_else(|| anyhow::anyhow!("Invalid timezone in '{}'", date))?;
+        Ok(self)
+    }
+
+    /// Timezone in Git's `+hhmm` form
+    pub fn timezone(&self) -> String {
+        let sign = if self.offset < 0 { '-' } else { '+' };
+        let minutes = self.offset.abs();
+        format!("{}{:02}{:02}", sign, minutes / 60, minutes % 60)
+    }
+
+    /// The timestamp in the signer's own timezone
+    pub fn datetime(&self) -> DateTime<FixedOffset> {
+        let zone = FixedOffset::east_opt(self.offset * 60)
+            .unwrap_or_else(|| FixedOffset::east_opt(0).unwrap());
+        zone.timestamp_opt(self.time, 0)
+            .single()
+            .unwrap_or_else(|| zone.timestamp_opt(0, 0).unwrap())
+    }
+
+    /// The full `Name <email> seconds +hhmm` form used in commit objects
+    pub fn to_raw(&self) -> String {
+        format!("{} {} {}", self, self.time, self.timezone())
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} <{}>", self.name, self.email)
+    }
+}
+
+fn parse_offset(offset: &str) -> Option<i32> {
+    let (sign, digits) = match offset.as_bytes().first()? {
+        b'+' => (1, &offset[1..]),
+        b'-' => (-1, &offset[1..]),
+        _ => return None,
+    };
+    if digits.len() != 4 {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = digits[2..].parse().ok()?;
+    Some(sign * (hours * 60 + minutes))
+}
 
 pub struct CommitBuilder {
     tree: String,
     parents: Vec<String>,
-    author: String,
-    committer: String,
+    author: Signature,
+    committer: Signature,
     message: String,
-    timestamp: DateTime<Utc>,
 }
 
 impl CommitBuilder {
     pub fn new(tree: String, message: String) -> Self {
+        let signature = Signature::now("TriForge User", "user@triforge.local");
         Self {
             tree,
             parents: Vec::new(),
-            author: "TriForge User <user@triforge.local>".to_string(),
-            committer: "TriForge User <user@triforge.local>".to_string(),
+            author: signature.clone(),
+            committer: signature,
             message,
-            timestamp: Utc::now(),
         }
     }
 
@@ -28,12 +162,12 @@ impl CommitBuilder {
         self
     }
 
-    pub fn author(mut self, author: String) -> Self {
+    pub fn author(mut self, author: Signature) -> Self {
         self.author = author;
         self
     }
 
-    pub fn committer(mut self, committer: String) -> Self {
+    pub fn committer(mut self, committer: Signature) -> Self {
         self.committer = committer;
         self
     }
@@ -47,14 +181,9 @@ impl CommitBuilder {
             content.push_str(&format!("parent {}\n", parent));
         }
         
-        let timestamp = self.timestamp.timestamp();
-        let timezone = "+0000"; // UTC
-        
-        content.push_str(&format!("author {} {} {}\n", 
-            self.author, timestamp, timezone));
-        content.push_str(&format!("committer {} {} {}\n", 
-            self.committer, timestamp, timezone));
-        content.push_str("\n");
+        content.push_str(&format!("author {}\n", self.author.to_raw()));
+        content.push_str(&format!("committer {}\n", self.committer.to_raw()));
+        content.push('\n');
         content.push_str(&self.message);
         
         if !self.message.ends_with('\n') {
@@ -71,15 +200,15 @@ impl CommitBuilder {
         }
 
         let content = std::str::from_utf8(&obj.content)?;
-        let mut lines = content.lines();
         
         let mut tree = None;
         let mut parents = Vec::new();
         let mut author = None;
+        let mut committer = None;
         let mut message = String::new();
         let mut in_message = false;
 
-        for line in lines {
+        for line in content.lines() {
             if in_message {
                 if !message.is_empty() {
                     message.push('\n');
@@ -87,19 +216,23 @@ impl CommitBuilder {
                 message.push_str(line);
             } else if line.is_empty() {
                 in_message = true;
-            } else if line.starts_with("tree ") {
-                tree = Some(line[5..].to_string());
-            } else if line.starts_with("parent ") {
-                parents.push(line[7..].to_string());
-            } else if line.starts_with("author ") {
-                author = Some(line[7..].to_string());
+            } else if let Some(hash) = line.strip_prefix("tree ") {
+                tree = Some(hash.to_string());
+            } else if let Some(hash) = line.strip_prefix("parent ") {
+                parents.push(hash.to_string());
+            } else if let Some(sig) = line.strip_prefix("author ") {
+                author = Some(Signature::parse(sig)?);
+            } else if let Some(sig) = line.strip_prefix("committer ") {
+                committer = Some(Signature::parse(sig)?);
             }
         }
 
+        let author = author.ok_or_else(|| anyhow::anyhow!("No author in commit"))?;
         Ok(ParsedCommit {
             tree: tree.ok_or_else(|| anyhow::anyhow!("No tree in commit"))?,
             parents,
-            author: author.ok_or_else(|| anyhow::anyhow!("No author in commit"))?,
+            committer: committer.unwrap_or_else(|| author.clone()),
+            author,
             message: message.trim().to_string(),
         })
     }
@@ -109,6 +242,7 @@ impl CommitBuilder {
 pub struct ParsedCommit {
     pub tree: String,
     pub parents: Vec<String>,
-    pub author: String,
+    pub author: Signature,
+    pub committer: Signature,
     pub message: String,
 }
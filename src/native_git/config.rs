@@ -0,0 +1,260 @@
+// TriForge/src/native_git/config.rs
+use std::fs;
+use std::path::Path;
+use anyhow::Result;
+
+/// Reader for Git's INI-style `.git/config`, with helpers that edit the
+/// file in place and leave unrelated lines untouched
+pub struct GitConfig {
+    /// `section.subsection.key` to value, in file order
+    entries: Vec<(String, String)>,
+}
+
+impl GitConfig {
+    /// Load `config` from a git directory; a missing file is empty
+    pub fn load(git_dir: &Path) -> Result<Self> {
+        let path = git_dir.join("config");
+        let content = if path.exists() {
+            fs::read_to_string(&path)?
+        } else {
+            String::new()
+        };
+
+        let mut entries = Vec::new();
+        let mut section = String::new();
+
+        for (i, raw) in content.lines().enumerate() {
+            let line = raw.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(header) = line.strip_prefix('[') {
+                let header = header.split(']').next().unwrap_or_default();
+                section = parse_section(header);
+                continue;
+            }
+
+            if section.is_empty() {
+                anyhow::bail!("Invalid config line {} in {}: key outside a section", i + 1, path.display());
+            }
+
+            // A key with no `=` is a boolean set to true
+            let (key, value) = match line.split_once('=') {
+                Some((k, v)) => (k.trim(), parse_value(v)),
+                None => (line, "true".to_string()),
+            };
+            entries.push((format!("{}.{}", section, key.to_lowercase()), value));
+        }
+
+        Ok(Self { entries })
+    }
+
+    /// Last value set for `section.key` or `section.subsection.key`
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let key = normalize_key(key);
+        self.entries.iter()
+            .rev()
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl GitConfig {
+    /// Set `section[.subsection].key`, replacing its last value or adding it
+    /// to the end of the section, which is created if needed
+    pub fn set(git_dir: &Path, key: &str, value: &str) -> Result<()> {
+        let key = normalize_key(key);
+        let (section, name) = key.rsplit_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Config key '{}' has no section", key))?;
+        let line = format!("\t{} = {}", name, quote_value(value));
+
+        let mut lines = read_lines(git_dir)?;
+        let sections = line_sections(&lines);
+
+        let existing = (0..lines.len()).rev().find(|&i| {
+            sections[i].as_deref() == Some(section) && line_key(&lines[i]).as_deref() == Some(name)
+        });
+        let section_end = (0..lines.len()).rev().find(|&i| sections[i].as_deref() == Some(section));
+
+        match (existing, section_end) {
+            (Some(i), _) => lines[i] = line,
+            (None, Some(i)) => lines.insert(i + 1, line),
+            (None, None) => {
+                lines.push(format_section(section));
+                lines.push(line);
+            }
+        }
+
+        write_lines(git_dir, &lines)
+    }
+
+    /// Remove every value of `section[.subsection].key`
+    pub fn unset(git_dir: &Path, key: &str) -> Result<()> {
+        let key = normalize_key(key);
+        let (section, name) = key.rsplit_once('.')
+            .ok_or_else(|| anyhow::anyhow!("Config key '{}' has no section", key))?;
+
+        let lines = read_lines(git_dir)?;
+        let sections = line_sections(&lines);
+        let kept: Vec<String> = lines.iter()
+            .zip(&sections)
+            .filter(|(l, s)| s.as_deref() != Some(section) || line_key(l).as_deref() != Some(name))
+            .map(|(l, _)| l.clone())
+            .collect();
+
+        if kept.len() != lines.len() {
+            write_lines(git_dir, &kept)?;
+        }
+        Ok(())
+    }
+
+    /// Remove every `[section]` block for `section[.subsection]`
+    pub fn remove_section(git_dir: &Path, section: &str) -> Result<()> {
+        let section = normalize_section(section);
+
+        let lines = read_lines(git_dir)?;
+        let sections = line_sections(&lines);
+        let kept: Vec<String> = lines.iter()
+            .zip(&sections)
+            .filter(|(_, s)| s.as_deref() != Some(section.as_str()))
+            .map(|(l, _)| l.clone())
+            .collect();
+
+        if kept.len() != lines.len() {
+            write_lines(git_dir, &kept)?;
+        }
+        Ok(())
+    }
+
+    /// Rename the `[section]` blocks for `old` to `new`, keeping their keys
+    pub fn rename_section(git_dir: &Path, old: &str, new: &str) -> Result<()> {
+        let old = normalize_section(old);
+
+        let mut lines = read_lines(git_dir)?;
+        let sections = line_sections(&lines);
+        let mut changed = false;
+        for i in 0..lines.len() {
+            if sections[i].as_deref() == Some(old.as_str()) && lines[i].trim_start().starts_with('[') {
+                lines[i] = format_section(new);
+                changed = true;
+            }
+        }
+
+        if changed {
+            write_lines(git_dir, &lines)?;
+        }
+        Ok(())
+    }
+}
+
+fn read_lines(git_dir: &Path) -> Result<Vec<String>> {
+    let path = git_dir.join("config");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)?.lines().map(|l| l.to_string()).collect())
+}
+
+fn write_lines(git_dir: &Path, lines: &[String]) -> Result<()> {
+    let mut content = lines.join("\n");
+    content.push('\n');
+    fs::write(git_dir.join("config"), content)?;
+    Ok(())
+}
+
+/// The section each line belongs to; headers belong to their own section
+fn line_sections(lines: &[String]) -> Vec<Option<String>> {
+    let mut current = None;
+    lines.iter()
+        .map(|line| {
+            if let Some(header) = line.trim().strip_prefix('[') {
+                current = Some(parse_section(header.split(']').next().unwrap_or_default()));
+            }
+            current.clone()
+        })
+        .collect()
+}
+
+/// Lowercased key name of a `key = value` line
+fn line_key(line: &str) -> Option<String> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with(['#', ';', '[']) {
+        return None;
+    }
+    let key = line.split_once('=').map(|(k, _)| k).unwrap_or(line);
+    Some(key.trim().to_lowercase())
+}
+
+/// `branch.main` -> `[branch "main"]`
+fn format_section(section: &str) -> String {
+    match section.split_once('.') {
+        Some((name, sub)) => format!("[{} \"{}\"]", name, sub.replace('\\', "\\\\").replace('"', "\\\"")),
+        None => format!("[{}]", section),
+    }
+}
+
+/// Quote values that would not survive being read back verbatim
+fn quote_value(value: &str) -> String {
+    let needs_quotes = value.starts_with(' ') || value.ends_with(' ')
+        || value.contains(['#', ';', '"', '\\']);
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n").replace('\t', "\\t");
+    if needs_quotes {
+        format!("\"{}\"", escaped)
+    } else {
+        escaped
+    }
+}
+
+/// `core` -> `core`, `branch "main"` -> `branch.main`
+fn parse_section(header: &str) -> String {
+    match header.split_once('"') {
+        Some((name, sub)) => format!("{}.{}", name.trim().to_lowercase(), sub.trim_end_matches('"')),
+        None => header.trim().to_lowercase(),
+    }
+}
+
+/// Section and key names are case-insensitive; subsections are not
+fn normalize_key(key: &str) -> String {
+    let first = key.find('.').unwrap_or(key.len());
+    let last = key.rfind('.').unwrap_or(0);
+    if first >= last {
+        return key.to_lowercase();
+    }
+    format!("{}{}{}", key[..first].to_lowercase(), &key[first..last], key[last..].to_lowercase())
+}
+
+/// Lowercase the section name, keeping the subsection's case
+fn normalize_section(section: &str) -> String {
+    match section.split_once('.') {
+        Some((name, sub)) => format!("{}.{}", name.to_lowercase(), sub),
+        None => section.to_lowercase(),
+    }
+}
+
+/// Strip comments and quotes and resolve escapes in a value
+fn parse_value(raw: &str) -> String {
+    let mut value = String::new();
+    let mut quoted = false;
+    let mut chars = raw.trim().chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => quoted = !quoted,
+            '\\' => match chars.next() {
+                Some('n') => value.push('\n'),
+                Some('t') => value.push('\t'),
+                Some(other) => value.push(other),
+                None => {}
+            },
+            '#' | ';' if !quoted => break,
+            _ => value.push(c),
+        }
+    }
+
+    if quoted {
+        value
+    } else {
+        value.trim_end().to_string()
+    }
+}
@@ -0,0 +1,628 @@
+// TriForge/src/native_git/diff.rs
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use anyhow::Result;
+
+use super::hash;
+use super::index::{self, Index};
+use super::repository::Repository;
+use super::tree::TreeBuilder;
+
+/// Number of bytes inspected when sniffing for binary content
+const BINARY_SNIFF_LEN: usize = 8000;
+/// Minimum similarity (percent) for a delete/add pair to count as a rename
+const RENAME_THRESHOLD: u8 = 50;
+/// Upper bound on delete x add pairs scored for inexact renames
+const RENAME_LIMIT: usize = 10_000;
+
+/// A file as seen on one side of a diff
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiffEntry {
+    pub mode: String,
+    pub hash: String,
+    /// Content lives in the working tree rather than the object store
+    pub worktree: bool,
+}
+
+/// Files keyed by `/`-separated path
+pub type Snapshot = BTreeMap<String, DiffEntry>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChangeStatus {
+    Added,
+    Deleted,
+    Modified,
+    /// Renamed with the given similarity percentage
+    Renamed(u8),
+}
+
+#[derive(Debug, Clone)]
+pub struct FileChange {
+    pub status: ChangeStatus,
+    pub old_path: String,
+    pub new_path: String,
+    pub old: Option<DiffEntry>,
+    pub new: Option<DiffEntry>,
+}
+
+impl FileChange {
+    /// Path the change is reported under
+    pub fn path(&self) -> &str {
+        if self.new.is_some() {
+            &self.new_path
+        } else {
+            &self.old_path
+        }
+    }
+}
+
+/// One step of a line-level edit script
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Edit {
+    /// Line `a` of the old side equals line `b` of the new side
+    Equal(usize, usize),
+    /// Line of the old side removed
+    Delete(usize),
+    /// Line of the new side inserted
+    Insert(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineKind {
+    Context,
+    Removed,
+    Added,
+}
+
+#[derive(Debug, Clone)]
+pub struct HunkLine {
+    pub kind: LineKind,
+    pub text: String,
+    /// The line is the last in its file and lacks a trailing newline
+    pub missing_newline: bool,
+}
+
+/// A unified diff hunk; starts are 1-based like `@@ -a,b +c,d @@`
+#[derive(Debug, Clone)]
+pub struct Hunk {
+    pub old_start: usize,
+    pub old_len: usize,
+    pub new_start: usize,
+    pub new_len: usize,
+    pub lines: Vec<HunkLine>,
+}
+
+/// Snapshot of every blob in a tree
+pub fn tree_snapshot(repo: &Repository, tree_hash: &str) -> Result<Snapshot> {
+    Ok(TreeBuilder::flatten(repo.git_dir(), tree_hash)?
+        .into_iter()
+        .map(|(path, entry)| (path, DiffEntry {
+            mode: entry.mode,
+            hash: entry.hash,
+            worktree: false,
+        }))
+        .collect())
+}
+
+/// Snapshot of the staging area
+pub fn index_snapshot(index: &Index) -> Snapshot {
+    index.entries()
+        .map(|e| (e.path.clone(), DiffEntry {
+            mode: format!("{:o}", e.mode),
+            hash: e.hash.clone(),
+            worktree: false,
+        }))
+        .collect()
+}
+
+/// Snapshot of the working tree for the given paths. Files whose stat data
+/// matches the index reuse the staged hash instead of being re-read.
+pub fn worktree_snapshot<'a, I>(repo: &Repository, index: &Index, paths: I) -> Result<Snapshot>
+where
+    I: IntoIterator<Item = &'a String>,
+{
+    let mut snapshot = Snapshot::new();
+
+    for path in paths {
+        let meta = match fs::symlink_metadata(repo.work_dir().join(path)) {
+            Ok(m) if !m.is_dir() => m,
+            _ => continue,
+        };
+
+        let staged = index.get(path).filter(|e| e.matches_metadata(&meta));
+        let hash = match staged {
+            Some(entry) => entry.hash.clone(),
+            None => hash::compute_object_hash("blob", &repo.read_work_file(path)?),
+        };
+
+        snapshot.insert(path.clone(), DiffEntry {
+            mode: format!("{:o}", index::file_mode(&meta)),
+            hash,
+            worktree: true,
+        });
+    }
+
+    Ok(snapshot)
+}
+
+/// Load the content of one side of a change
+pub fn load_content(repo: &Repository, path: &str, entry: &DiffEntry) -> Result<Vec<u8>> {
+    if entry.worktree {
+        repo.read_work_file(path)
+    } else {
+        Ok(repo.load_object(&entry.hash)?.content)
+    }
+}
+
+/// Compare two snapshots path by path
+pub fn compare(old: &Snapshot, new: &Snapshot) -> Vec<FileChange> {
+    let mut changes = Vec::new();
+
+    for (path, old_entry) in old {
+        match new.get(path) {
+            Some(new_entry) => {
+                if old_entry.hash != new_entry.hash || old_entry.mode != new_entry.mode {
+                    changes.push(FileChange {
+                        status: ChangeStatus::Modified,
+                        old_path: path.clone(),
+                        new_path: path.clone(),
+                        old: Some(old_entry.clone()),
+                        new: Some(new_entry.clone()),
+                    });
+                }
+            }
+            None => changes.push(FileChange {
+                status: ChangeStatus::Deleted,
+                old_path: path.clone(),
+                new_path: path.clone(),
+                old: Some(old_entry.clone()),
+                new: None,
+            }),
+        }
+    }
+
+    for (path, new_entry) in new {
+        if !old.contains_key(path) {
+            changes.push(FileChange {
+                status: ChangeStatus::Added,
+                old_path: path.clone(),
+                new_path: path.clone(),
+                old: None,
+                new: Some(new_entry.clone()),
+            });
+        }
+    }
+
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
+
+/// Pair deleted and added files into renames, first by identical content,
+/// then by line similarity above `RENAME_THRESHOLD`.
+pub fn detect_renames<F>(changes: Vec<FileChange>, mut load: F) -> Result<Vec<FileChange>>
+where
+    F: FnMut(&str, &DiffEntry) -> Result<Vec<u8>>,
+{
+    let (mut deleted, rest): (Vec<FileChange>, Vec<FileChange>) = changes.into_iter()
+        .partition(|c| c.status == ChangeStatus::Deleted);
+    let (mut added, mut result): (Vec<FileChange>, Vec<FileChange>) = rest.into_iter()
+        .partition(|c| c.status == ChangeStatus::Added);
+
+    // Exact renames
+    let mut i = 0;
+    while i < added.len() {
+        let hash = added[i].new.as_ref().map(|e| e.hash.as_str());
+        match deleted.iter().position(|d| d.old.as_ref().map(|e| e.hash.as_str()) == hash) {
+            Some(pos) => {
+                let from = deleted.remove(pos);
+                let to = added.remove(i);
+                result.push(renamed(from, to, 100));
+            }
+            None => i += 1,
+        }
+    }
+
+    // Inexact renames, best pairs first
+    if !deleted.is_empty() && !added.is_empty() && deleted.len() * added.len() <= RENAME_LIMIT {
+        let mut old_texts = Vec::new();
+        for d in &deleted {
+            old_texts.push(load(&d.old_path, d.old.as_ref().unwrap())?);
+        }
+        let mut new_texts = Vec::new();
+        for a in &added {
+            new_texts.push(load(&a.new_path, a.new.as_ref().unwrap())?);
+        }
+
+        let mut scores = Vec::new();
+        for (di, old) in old_texts.iter().enumerate() {
+            for (ai, new) in new_texts.iter().enumerate() {
+                let score = similarity(old, new);
+                if score >= RENAME_THRESHOLD {
+                    scores.push((score, di, ai));
+                }
+            }
+        }
+        scores.sort_by_key(|s| std::cmp::Reverse(s.0));
+
+        let mut used_old = vec![false; deleted.len()];
+        let mut used_new = vec![false; added.len()];
+        let mut pairs = Vec::new();
+        for (score, di, ai) in scores {
+            if !used_old[di] && !used_new[ai] {
+                used_old[di] = true;
+                used_new[ai] = true;
+                pairs.push((di, ai, score));
+            }
+        }
+
+        let mut deleted: Vec<Option<FileChange>> = deleted.into_iter().map(Some).collect();
+        let mut added: Vec<Option<FileChange>> = added.into_iter().map(Some).collect();
+        for (di, ai, score) in pairs {
+            let from = deleted[di].take().unwrap();
+            let to = added[ai].take().unwrap();
+            result.push(renamed(from, to, score));
+        }
+        result.extend(deleted.into_iter().flatten());
+        result.extend(added.into_iter().flatten());
+    } else {
+        result.extend(deleted);
+        result.extend(added);
+    }
+
+    result.sort_by(|a, b| a.path().cmp(b.path()));
+    Ok(result)
+}
+
+fn renamed(from: FileChange, to: FileChange, score: u8) -> FileChange {
+    FileChange {
+        status: ChangeStatus::Renamed(score),
+        old_path: from.old_path,
+        new_path: to.new_path,
+        old: from.old,
+        new: to.new,
+    }
+}
+
+/// Percentage of lines shared between two texts
+pub fn similarity(old: &[u8], new: &[u8]) -> u8 {
+    if old == new {
+        return 100;
+    }
+    if is_binary(old) || is_binary(new) {
+        return 0;
+    }
+
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let total = old_lines.len() + new_lines.len();
+    if total == 0 {
+        return 100;
+    }
+
+    let mut counts: HashMap<&[u8], usize> = HashMap::new();
+    for line in &old_lines {
+        *counts.entry(line).or_default() += 1;
+    }
+
+    let mut common = 0;
+    for line in &new_lines {
+        if let Some(count) = counts.get_mut(line) {
+            if *count > 0 {
+                *count -= 1;
+                common += 1;
+            }
+        }
+    }
+
+    (common * 2 * 100 / total) as u8
+}
+
+/// Git's heuristic: content with a NUL byte near the start is binary
+pub fn is_binary(data: &[u8]) -> bool {
+    data.iter().take(BINARY_SNIFF_LEN).any(|&b| b == 0)
+}
+
+/// Split content into lines, each keeping its trailing newline if present
+pub fn split_lines(data: &[u8]) -> Vec<&[u8]> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+    for (i, &b) in data.iter().enumerate() {
+        if b == b'\n' {
+            lines.push(&data[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < data.len() {
+        lines.push(&data[start..]);
+    }
+    lines
+}
+
+/// Shortest edit script between two sequences (Myers' O(ND) algorithm)
+pub fn diff_lines<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    // Trim the common prefix and suffix so the search only covers the change
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Equal(i, i)).collect();
+    for edit in myers(a_mid, b_mid) {
+        edits.push(match edit {
+            Edit::Equal(x, y) => Edit::Equal(x + prefix, y + prefix),
+            Edit::Delete(x) => Edit::Delete(x + prefix),
+            Edit::Insert(y) => Edit::Insert(y + prefix),
+        });
+    }
+    for i in 0..suffix {
+        edits.push(Edit::Equal(a.len() - suffix + i, b.len() - suffix + i));
+    }
+    edits
+}
+
+fn myers<T: PartialEq>(a: &[T], b: &[T]) -> Vec<Edit> {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let max = (n + m) as usize;
+    let offset = max as isize + 1;
+    let mut v = vec![0isize; 2 * max + 3];
+    let mut trace = Vec::new();
+
+    // Round d only reaches diagonals -d..=d, so each snapshot keeps just
+    // that frontier (plus one on either side) rather than all of `v`
+    'search: for d in 0..=max as isize {
+        trace.push(v[(offset - d - 1) as usize..=(offset + d + 1) as usize].to_vec());
+        let mut k = -d;
+        while k <= d {
+            let idx = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && v[idx - 1] < v[idx + 1]) {
+                v[idx + 1]
+            } else {
+                v[idx - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[idx] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+            k += 2;
+        }
+    }
+
+    // Walk the trace backwards to recover the edit script
+    let mut edits = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, frontier) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| frontier[(k + d + 1) as usize];
+        let k = x - y;
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        while x > prev_x && y > prev_y {
+            edits.push(Edit::Equal((x - 1) as usize, (y - 1) as usize));
+            x -= 1;
+            y -= 1;
+        }
+        if d > 0 {
+            if x == prev_x {
+                edits.push(Edit::Insert((y - 1) as usize));
+            } else {
+                edits.push(Edit::Delete((x - 1) as usize));
+            }
+        }
+        x = prev_x;
+        y = prev_y;
+    }
+
+    edits.reverse();
+    edits
+}
+
+/// Build unified diff hunks with `context` lines around each change
+pub fn hunks(old: &[u8], new: &[u8], context: usize) -> Vec<Hunk> {
+    let old_lines = split_lines(old);
+    let new_lines = split_lines(new);
+    let edits = diff_lines(&old_lines, &new_lines);
+
+    // Indices of edits that are actual changes
+    let changed: Vec<usize> = edits.iter()
+        .enumerate()
+        .filter(|(_, e)| !matches!(e, Edit::Equal(..)))
+        .map(|(i, _)| i)
+        .collect();
+
+    // Old and new lines consumed before each edit
+    let mut positions = Vec::with_capacity(edits.len());
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for edit in &edits {
+        positions.push((old_pos, new_pos));
+        match edit {
+            Edit::Equal(..) => {
+                old_pos += 1;
+                new_pos += 1;
+            }
+            Edit::Delete(_) => old_pos += 1,
+            Edit::Insert(_) => new_pos += 1,
+        }
+    }
+
+    let mut hunks = Vec::new();
+    let mut i = 0;
+    while i < changed.len() {
+        let start = changed[i].saturating_sub(context);
+        let mut end = changed[i];
+        // Merge changes whose context windows touch
+        while i + 1 < changed.len() && changed[i + 1] - end <= 2 * context + 1 {
+            i += 1;
+            end = changed[i];
+        }
+        let end = (end + context + 1).min(edits.len());
+        hunks.push(build_hunk(&edits[start..end], positions[start], &old_lines, &new_lines));
+        i += 1;
+    }
+
+    hunks
+}
+
+/// `before` holds how many old and new lines precede the hunk
+fn build_hunk(edits: &[Edit], before: (usize, usize), old_lines: &[&[u8]], new_lines: &[&[u8]]) -> Hunk {
+    let (old_before, new_before) = before;
+
+    let mut old_len = 0;
+    let mut new_len = 0;
+    let mut lines = Vec::new();
+
+    let line = |kind: LineKind, raw: &[u8]| HunkLine {
+        kind,
+        text: String::from_utf8_lossy(raw.strip_suffix(b"\n").unwrap_or(raw)).to_string(),
+        missing_newline: !raw.ends_with(b"\n"),
+    };
+
+    for edit in edits {
+        match *edit {
+            Edit::Equal(x, _) => {
+                old_len += 1;
+                new_len += 1;
+                lines.push(line(LineKind::Context, old_lines[x]));
+            }
+            Edit::Delete(x) => {
+                old_len += 1;
+                lines.push(line(LineKind::Removed, old_lines[x]));
+            }
+            Edit::Insert(y) => {
+                new_len += 1;
+                lines.push(line(LineKind::Added, new_lines[y]));
+            }
+        }
+    }
+
+    // An empty side is reported at the line before the change, as Git does
+    Hunk {
+        old_start: if old_len == 0 { old_before } else { old_before + 1 },
+        old_len,
+        new_start: if new_len == 0 { new_before } else { new_before + 1 },
+        new_len,
+        lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(lines: impl IntoIterator<Item = usize>) -> Vec<u8> {
+        lines.into_iter().map(|i| format!("{}\n", i)).collect::<String>().into_bytes()
+    }
+
+    fn headers(hunks: &[Hunk]) -> Vec<(usize, usize, usize, usize)> {
+        hunks.iter().map(|h| (h.old_start, h.old_len, h.new_start, h.new_len)).collect()
+    }
+
+    /// Replay an edit script, checking it consumes both sides in order
+    fn replay<'a>(a: &[&'a str], b: &[&'a str], edits: &[Edit]) -> Vec<&'a str> {
+        let (mut x, mut y) = (0, 0);
+        let mut out = Vec::new();
+        for edit in edits {
+            match *edit {
+                Edit::Equal(i, j) => {
+                    assert_eq!((i, j), (x, y));
+                    assert_eq!(a[i], b[j]);
+                    out.push(a[i]);
+                    x += 1;
+                    y += 1;
+                }
+                Edit::Delete(i) => {
+                    assert_eq!(i, x);
+                    x += 1;
+                }
+                Edit::Insert(j) => {
+                    assert_eq!(j, y);
+                    out.push(b[j]);
+                    y += 1;
+                }
+            }
+        }
+        assert_eq!((x, y), (a.len(), b.len()));
+        out
+    }
+
+    #[test]
+    fn edit_script_is_shortest() {
+        let cases: [(&[&str], &[&str], usize); 5] = [
+            (&["a", "b", "c", "a", "b", "b", "a"], &["c", "b", "a", "b", "a", "c"], 5),
+            (&[], &["x", "y"], 2),
+            (&["x", "y"], &[], 2),
+            (&["same"], &["same"], 0),
+            (&["a", "b", "c"], &["d", "e"], 5),
+        ];
+        for (a, b, changes) in cases {
+            let edits = diff_lines(a, b);
+            assert_eq!(replay(a, b, &edits), b);
+            assert_eq!(edits.iter().filter(|e| !matches!(e, Edit::Equal(..))).count(), changes);
+        }
+    }
+
+    #[test]
+    fn zero_context_hunks_start_where_git_puts_them() {
+        // Deletion: the new side is empty and reported at the line before
+        let deleted = hunks(b"a\nb\nc\n", b"a\nc\n", 0);
+        assert_eq!(headers(&deleted), [(2, 1, 1, 0)]);
+
+        let inserted = hunks(b"a\nc\n", b"a\nb\nc\n", 0);
+        assert_eq!(headers(&inserted), [(1, 0, 2, 1)]);
+
+        let at_top = hunks(b"b\n", b"a\nb\n", 0);
+        assert_eq!(headers(&at_top), [(0, 0, 1, 1)]);
+
+        let replaced = hunks(b"x\ny", b"x\nz\n", 0);
+        assert_eq!(headers(&replaced), [(2, 1, 2, 1)]);
+        assert!(replaced[0].lines[0].missing_newline);
+        assert!(!replaced[0].lines[1].missing_newline);
+    }
+
+    #[test]
+    fn nearby_changes_share_a_hunk() {
+        let old = numbered(1..=10);
+
+        // Six unchanged lines between the changes fit in both contexts
+        let close = hunks(&old, &numbered((1..=10).filter(|&i| i != 2 && i != 9)), 3);
+        assert_eq!(headers(&close), [(1, 10, 1, 8)]);
+
+        let apart = hunks(&old, &numbered((1..=10).filter(|&i| i != 2 && i != 10)), 3);
+        assert_eq!(headers(&apart), [(1, 5, 1, 4), (7, 4, 6, 3)]);
+    }
+
+    #[test]
+    fn identical_content_has_no_hunks() {
+        assert!(hunks(b"one\ntwo\n", b"one\ntwo\n", 3).is_empty());
+        assert!(hunks(b"", b"", 3).is_empty());
+    }
+
+    #[test]
+    fn split_lines_keeps_newlines() {
+        assert_eq!(split_lines(b"a\nb"), [&b"a\n"[..], &b"b"[..]]);
+        assert_eq!(split_lines(b"a\n\n"), [&b"a\n"[..], &b"\n"[..]]);
+        assert!(split_lines(b"").is_empty());
+    }
+
+    #[test]
+    fn similarity_counts_shared_lines() {
+        assert_eq!(similarity(b"a\nb\n", b"a\nb\n"), 100);
+        assert_eq!(similarity(b"a\nb\nc\nd\n", b"a\nb\nc\ne\n"), 75);
+        assert_eq!(similarity(b"a\0b", b"a\0c"), 0);
+    }
+}
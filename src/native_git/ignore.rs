@@ -0,0 +1,370 @@
+// TriForge/src/native_git/ignore.rs
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use walkdir::WalkDir;
+
+/// Per-directory ignore files, read in this order so the Triforge file wins
+pub const IGNORE_FILES: [&str; 2] = [".gitignore", ".triforgeignore"];
+/// Directories that are never part of the working tree
+const ALWAYS_IGNORED: [&str; 2] = [".git", ".tri"];
+
+/// One pattern line from an ignore file
+#[derive(Debug, Clone)]
+pub struct IgnoreRule {
+    /// File the rule came from
+    pub source: PathBuf,
+    /// 1-based line number in `source`
+    pub line: usize,
+    /// The pattern as written
+    pub pattern: String,
+    /// `!pattern`: re-include a previously ignored path
+    pub negated: bool,
+    /// Directory the ignore file lives in, relative to the work tree
+    base: String,
+    /// Glob with `!`, the leading `/` and the trailing `/` removed
+    glob: String,
+    /// Trailing `/`: only matches directories
+    dir_only: bool,
+    /// Pattern contains a `/` and is matched against the path from `base`
+    anchored: bool,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str, base: &str, source: &Path, line_no: usize) -> Option<Self> {
+        let line = trim_trailing_spaces(line);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        // A leading `\` escapes a literal `!` or `#`
+        let negated = line.starts_with('!');
+        let escaped = line.starts_with("\\!") || line.starts_with("\\#");
+        let glob = if negated || escaped { &line[1..] } else { line };
+
+        let dir_only = glob.ends_with('/');
+        let glob = glob.trim_end_matches('/');
+        let anchored = glob.contains('/');
+        let glob = glob.trim_start_matches('/');
+        if glob.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            source: source.to_path_buf(),
+            line: line_no,
+            pattern: line.to_string(),
+            negated,
+            base: base.to_string(),
+            glob: glob.to_string(),
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// Whether the rule applies to a work-tree path
+    fn matches(&self, path: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+
+        let relative = if self.base.is_empty() {
+            path
+        } else {
+            match path.strip_prefix(&self.base).and_then(|p| p.strip_prefix('/')) {
+                Some(p) => p,
+                None => return false,
+            }
+        };
+
+        if self.anchored {
+            wildmatch(self.glob.as_bytes(), relative.as_bytes())
+        } else {
+            let name = relative.rsplit('/').next().unwrap_or(relative);
+            wildmatch(self.glob.as_bytes(), name.as_bytes())
+        }
+    }
+}
+
+/// Ignore rules for a working tree, following gitignore semantics: later
+/// rules and deeper files take precedence, and nothing inside an ignored
+/// directory can be re-included.
+pub struct IgnoreRules {
+    work_dir: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+impl IgnoreRules {
+    /// Read `.git/info/exclude` and every ignore file in the work tree
+    pub fn load(work_dir: &Path, git_dir: &Path) -> Result<Self> {
+        let mut ignore = Self {
+            work_dir: work_dir.to_path_buf(),
+            rules: Vec::new(),
+        };
+
+        ignore.read_file(&git_dir.join("info").join("exclude"), "")?;
+        ignore.load_dir(work_dir, "")?;
+
+        Ok(ignore)
+    }
+
+    /// Pick up ignore files from `dir`, then descend into directories that
+    /// are not themselves ignored
+    fn load_dir(&mut self, dir: &Path, rel: &str) -> Result<()> {
+        for name in IGNORE_FILES {
+            self.read_file(&dir.join(name), rel)?;
+        }
+
+        let mut subdirs = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            let name = entry.file_name().to_string_lossy().to_string();
+            let child = join(rel, &name);
+            let ignored = ALWAYS_IGNORED.contains(&name.as_str())
+                || self.matched(&child, true).map(|r| !r.negated).unwrap_or(false);
+            if !ignored {
+                subdirs.push((entry.path(), child));
+            }
+        }
+        subdirs.sort();
+
+        for (path, child) in subdirs {
+            self.load_dir(&path, &child)?;
+        }
+        Ok(())
+    }
+
+    fn read_file(&mut self, path: &Path, base: &str) -> Result<()> {
+        if !path.is_file() {
+            return Ok(());
+        }
+
+        let content = fs::read_to_string(path)?;
+        for (i, line) in content.lines().enumerate() {
+            if let Some(rule) = IgnoreRule::parse(line, base, path, i + 1) {
+                self.rules.push(rule);
+            }
+        }
+        Ok(())
+    }
+
+    /// Last rule matching the path itself, ignoring its parents
+    fn matched(&self, path: &str, is_dir: bool) -> Option<&IgnoreRule> {
+        self.rules.iter().rev().find(|r| r.matches(path, is_dir))
+    }
+
+    /// The rule that decides whether `path` (relative to the work tree) is
+    /// ignored, including rules that exclude one of its parent directories.
+    /// A negated rule means the path was explicitly re-included.
+    pub fn rule_for(&self, path: &str, is_dir: bool) -> Option<&IgnoreRule> {
+        let parts: Vec<&str> = path.split('/').collect();
+        for depth in 1..parts.len() {
+            let dir = parts[..depth].join("/");
+            if let Some(rule) = self.matched(&dir, true).filter(|r| !r.negated) {
+                return Some(rule);
+            }
+        }
+        self.matched(path, is_dir)
+    }
+
+    /// Whether a path relative to the work tree is ignored
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        if path.split('/').any(|c| ALWAYS_IGNORED.contains(&c)) {
+            return true;
+        }
+        self.rule_for(path, is_dir).map(|r| !r.negated).unwrap_or(false)
+    }
+
+    /// Files and symlinks under `start` that are not ignored, as `/`-separated
+    /// paths relative to the work tree
+    pub fn walk(&self, start: &Path) -> Result<Vec<String>> {
+        let mut files = Vec::new();
+
+        let walker = WalkDir::new(start)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| match self.relative(e.path()) {
+                Some(rel) => !self.is_ignored(&rel, e.file_type().is_dir()),
+                None => true,
+            });
+
+        for entry in walker {
+            let entry = entry?;
+            if entry.file_type().is_dir() {
+                continue;
+            }
+            if let Some(rel) = self.relative(entry.path()) {
+                files.push(rel);
+            }
+        }
+
+        Ok(files)
+    }
+
+    fn relative(&self, path: &Path) -> Option<String> {
+        let rel = path.strip_prefix(&self.work_dir).unwrap_or(path);
+        let parts: Vec<String> = rel.components()
+            .filter_map(|c| match c {
+                std::path::Component::Normal(s) => Some(s.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("/"))
+        }
+    }
+}
+
+fn join(base: &str, name: &str) -> String {
+    if base.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}/{}", base, name)
+    }
+}
+
+/// Strip trailing spaces unless escaped with a backslash
+fn trim_trailing_spaces(line: &str) -> &str {
+    let mut end = line.len();
+    while end > 0 && line.as_bytes()[end - 1] == b' ' {
+        if end >= 2 && line.as_bytes()[end - 2] == b'\\' {
+            break;
+        }
+        end -= 1;
+    }
+    &line[..end]
+}
+
+/// Match a gitignore glob against a path. `*`, `?` and `[...]` never match
+/// `/`; `**` between slashes matches any number of directories.
+pub fn wildmatch(pattern: &[u8], text: &[u8]) -> bool {
+    match_from(pattern, 0, text, 0)
+}
+
+fn match_from(p: &[u8], mut pi: usize, t: &[u8], mut ti: usize) -> bool {
+    while pi < p.len() {
+        match p[pi] {
+            b'*' => {
+                let double = p.get(pi + 1) == Some(&b'*')
+                    && (pi == 0 || p[pi - 1] == b'/')
+                    && (pi + 2 == p.len() || p[pi + 2] == b'/');
+
+                if double {
+                    // Trailing `**` swallows everything that is left
+                    if pi + 2 == p.len() {
+                        return true;
+                    }
+                    // `**/` matches zero or more leading directories
+                    let rest = pi + 3;
+                    if match_from(p, rest, t, ti) {
+                        return true;
+                    }
+                    return (ti..t.len())
+                        .filter(|&i| t[i] == b'/')
+                        .any(|i| match_from(p, rest, t, i + 1));
+                }
+
+                while p.get(pi) == Some(&b'*') {
+                    pi += 1;
+                }
+                let mut k = ti;
+                loop {
+                    if match_from(p, pi, t, k) {
+                        return true;
+                    }
+                    if k == t.len() || t[k] == b'/' {
+                        return false;
+                    }
+                    k += 1;
+                }
+            }
+            b'?' => {
+                if ti == t.len() || t[ti] == b'/' {
+                    return false;
+                }
+                pi += 1;
+                ti += 1;
+            }
+            b'[' => {
+                if ti == t.len() || t[ti] == b'/' {
+                    return false;
+                }
+                match match_class(p, pi, t[ti]) {
+                    Some((true, next)) => {
+                        pi = next;
+                        ti += 1;
+                    }
+                    Some((false, _)) => return false,
+                    // Unterminated class: treat `[` literally
+                    None => {
+                        if t[ti] != b'[' {
+                            return false;
+                        }
+                        pi += 1;
+                        ti += 1;
+                    }
+                }
+            }
+            b'\\' if pi + 1 < p.len() => {
+                if ti == t.len() || t[ti] != p[pi + 1] {
+                    return false;
+                }
+                pi += 2;
+                ti += 1;
+            }
+            c => {
+                if ti == t.len() || t[ti] != c {
+                    return false;
+                }
+                pi += 1;
+                ti += 1;
+            }
+        }
+    }
+
+    ti == t.len()
+}
+
+/// Match one byte against the class starting at `p[start] == b'['`,
+/// returning whether it matched and the index after the closing `]`
+fn match_class(p: &[u8], start: usize, c: u8) -> Option<(bool, usize)> {
+    let mut i = start + 1;
+    let negated = matches!(p.get(i), Some(b'!') | Some(b'^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < p.len() {
+        if p[i] == b']' && !first {
+            return Some((matched != negated, i + 1));
+        }
+        first = false;
+
+        let mut lo = p[i];
+        if lo == b'\\' && i + 1 < p.len() {
+            i += 1;
+            lo = p[i];
+        }
+        if p.get(i + 1) == Some(&b'-') && p.get(i + 2).map(|&b| b != b']').unwrap_or(false) {
+            let hi = p[i + 2];
+            if lo <= c && c <= hi {
+                matched = true;
+            }
+            i += 3;
+        } else {
+            if lo == c {
+                matched = true;
+            }
+            i += 1;
+        }
+    }
+
+    None
+}
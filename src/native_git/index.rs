@@ -0,0 +1,542 @@
+// TriForge/src/native_git/index.rs
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Result, Context};
+
+use super::hash;
+use super::refs::LockFile;
+
+const INDEX_SIGNATURE: &[u8; 4] = b"DIRC";
+const INDEX_VERSION: u32 = 2;
+const ENTRY_HEADER_LEN: usize = 62;
+
+/// A single staged file, laid out like a Git index v2 entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    pub ctime_secs: u32,
+    pub ctime_nsecs: u32,
+    pub mtime_secs: u32,
+    pub mtime_nsecs: u32,
+    pub dev: u32,
+    pub ino: u32,
+    pub mode: u32,
+    pub uid: u32,
+    pub gid: u32,
+    pub size: u32,
+    pub hash: String,
+    pub flags: u16,
+    pub path: String,
+}
+
+impl IndexEntry {
+    /// Create an entry for a working tree file from its metadata
+    pub fn from_metadata(path: String, hash: String, meta: &fs::Metadata) -> Self {
+        let stat = Stat::from_metadata(meta);
+        Self {
+            ctime_secs: stat.ctime_secs,
+            ctime_nsecs: stat.ctime_nsecs,
+            mtime_secs: stat.mtime_secs,
+            mtime_nsecs: stat.mtime_nsecs,
+            dev: stat.dev,
+            ino: stat.ino,
+            mode: file_mode(meta),
+            uid: stat.uid,
+            gid: stat.gid,
+            size: stat.size,
+            hash,
+            flags: name_flags(&path),
+            path,
+        }
+    }
+
+    /// Create a conflict entry for one side of an unmerged path.
+    /// Stage 1 is the merge base, 2 is ours and 3 is theirs.
+    pub fn unmerged(path: String, mode: u32, hash: String, stage: u16) -> Self {
+        let mut entry = Self::without_stat(path, mode, hash);
+        entry.flags |= stage << 12;
+        entry
+    }
+
+    /// Create an entry for a blob that is not what the working tree holds.
+    /// The zeroed stat data makes the next status check re-hash the file.
+    pub fn without_stat(path: String, mode: u32, hash: String) -> Self {
+        Self {
+            ctime_secs: 0,
+            ctime_nsecs: 0,
+            mtime_secs: 0,
+            mtime_nsecs: 0,
+            dev: 0,
+            ino: 0,
+            mode,
+            uid: 0,
+            gid: 0,
+            size: 0,
+            hash,
+            flags: name_flags(&path),
+            path,
+        }
+    }
+
+    /// Merge stage; 0 for normal entries
+    pub fn stage(&self) -> u16 {
+        (self.flags >> 12) & 0x3
+    }
+
+    /// Check whether the cached stat data still matches a file on disk
+    pub fn matches_metadata(&self, meta: &fs::Metadata) -> bool {
+        let stat = Stat::from_metadata(meta);
+        self.mtime_secs == stat.mtime_secs
+            && self.mtime_nsecs == stat.mtime_nsecs
+            && self.size == stat.size
+            && self.ino == stat.ino
+            && self.mode == file_mode(meta)
+    }
+}
+
+/// The staging area, stored in `.git/index`
+pub struct Index {
+    path: PathBuf,
+    /// Keyed by path and merge stage, matching Git's on-disk order
+    entries: BTreeMap<(String, u16), IndexEntry>,
+}
+
+impl Index {
+    /// Load the index, or start an empty one if none exists yet
+    pub fn load(git_dir: &Path) -> Result<Self> {
+        let path = git_dir.join("index");
+        let mut index = Self {
+            path,
+            entries: BTreeMap::new(),
+        };
+
+        if !index.path.exists() {
+            return Ok(index);
+        }
+
+        let data = fs::read(&index.path)?;
+        index.parse(&data)?;
+        Ok(index)
+    }
+
+    fn parse(&mut self, data: &[u8]) -> Result<()> {
+        if data.len() < 12 + 20 {
+            anyhow::bail!("Index file is truncated");
+        }
+
+        let (body, checksum) = data.split_at(data.len() - 20);
+        if hex::encode(checksum) != hash::compute_hash(body) {
+            anyhow::bail!("Index checksum mismatch");
+        }
+
+        if &body[..4] != INDEX_SIGNATURE {
+            anyhow::bail!("Invalid index signature");
+        }
+
+        let version = read_u32(body, 4)?;
+        if version != 2 && version != 3 {
+            anyhow::bail!("Unsupported index version: {}", version);
+        }
+
+        let count = read_u32(body, 8)? as usize;
+        let mut pos = 12;
+
+        for _ in 0..count {
+            let start = pos;
+            let field = |i: usize| read_u32(body, start + i * 4);
+
+            let hash_start = start + 40;
+            let hash_bytes = body.get(hash_start..hash_start + 20)
+                .context("Index entry is truncated")?;
+            let flags = read_u16(body, start + 60)?;
+            pos = start + ENTRY_HEADER_LEN;
+
+            // Version 3 entries may carry a second flags word
+            if version == 3 && flags & 0x4000 != 0 {
+                pos += 2;
+            }
+
+            let rest = body.get(pos..).context("Index entry is truncated")?;
+            let name_len = rest.iter()
+                .position(|&b| b == 0)
+                .context("Index entry path is not terminated")?;
+            let path = std::str::from_utf8(&rest[..name_len])?.to_string();
+            pos += name_len;
+
+            // Entries are NUL-padded to a multiple of 8 bytes
+            let entry_len = pos - start;
+            pos = start + (entry_len + 8) / 8 * 8;
+
+            let entry = IndexEntry {
+                ctime_secs: field(0)?,
+                ctime_nsecs: field(1)?,
+                mtime_secs: field(2)?,
+                mtime_nsecs: field(3)?,
+                dev: field(4)?,
+                ino: field(5)?,
+                mode: field(6)?,
+                uid: field(7)?,
+                gid: field(8)?,
+                size: field(9)?,
+                hash: hex::encode(hash_bytes),
+                flags: flags & 0x3fff,
+                path: path.clone(),
+            };
+            self.entries.insert((path, entry.stage()), entry);
+        }
+
+        // Extensions (TREE, REUC, ...) are optional caches; they are dropped
+        // on the next write and Git rebuilds them as needed.
+        Ok(())
+    }
+
+    /// Write the index back to disk atomically via `index.lock`
+    pub fn save(&self) -> Result<()> {
+        let mut data = Vec::new();
+        data.extend_from_slice(INDEX_SIGNATURE);
+        data.extend_from_slice(&INDEX_VERSION.to_be_bytes());
+        data.extend_from_slice(&(self.entries.len() as u32).to_be_bytes());
+
+        for entry in self.entries.values() {
+            let start = data.len();
+            for value in [
+                entry.ctime_secs, entry.ctime_nsecs,
+                entry.mtime_secs, entry.mtime_nsecs,
+                entry.dev, entry.ino, entry.mode,
+                entry.uid, entry.gid, entry.size,
+            ] {
+                data.extend_from_slice(&value.to_be_bytes());
+            }
+            data.extend_from_slice(&hex::decode(&entry.hash)?);
+            data.extend_from_slice(&entry.flags.to_be_bytes());
+            data.extend_from_slice(entry.path.as_bytes());
+
+            let entry_len = data.len() - start;
+            let padded = (entry_len + 8) / 8 * 8;
+            data.resize(start + padded, 0);
+        }
+
+        let checksum = hex::decode(hash::compute_hash(&data))?;
+        data.extend_from_slice(&checksum);
+
+        // Fails while another process holds `index.lock`; the lock is
+        // removed again if writing it fails
+        let mut lock = LockFile::acquire(&self.path)?;
+        lock.write(&data)?;
+        lock.commit()
+    }
+
+    /// Stage an entry, replacing any existing entry for the same path.
+    /// Adding a stage-0 entry resolves any conflict recorded for the path.
+    pub fn add(&mut self, entry: IndexEntry) {
+        if entry.stage() == 0 {
+            for stage in 1..=3 {
+                self.entries.remove(&(entry.path.clone(), stage));
+            }
+        } else {
+            self.entries.remove(&(entry.path.clone(), 0));
+        }
+        self.entries.insert((entry.path.clone(), entry.stage()), entry);
+    }
+
+    /// Unstage a path, including any conflict stages
+    pub fn remove(&mut self, path: &str) -> Option<IndexEntry> {
+        let mut removed = None;
+        for stage in (0..=3).rev() {
+            if let Some(entry) = self.entries.remove(&(path.to_string(), stage)) {
+                removed = Some(entry);
+            }
+        }
+        removed
+    }
+
+    /// Unstage every path under a directory prefix, returning the removed paths
+    pub fn remove_dir(&mut self, dir: &str) -> Vec<String> {
+        let prefix = format!("{}/", dir.trim_end_matches('/'));
+        let mut removed: Vec<String> = self.entries.keys()
+            .filter(|(p, _)| p.starts_with(&prefix))
+            .map(|(p, _)| p.clone())
+            .collect();
+        removed.dedup();
+
+        for path in &removed {
+            self.remove(path);
+        }
+        removed
+    }
+
+    /// Get the stage-0 entry for a path
+    pub fn get(&self, path: &str) -> Option<&IndexEntry> {
+        self.entries.get(&(path.to_string(), 0))
+    }
+
+    /// Stage-0 entries in Git index order (byte-wise by path)
+    pub fn entries(&self) -> impl Iterator<Item = &IndexEntry> {
+        self.entries.values().filter(|e| e.stage() == 0)
+    }
+
+    /// Paths with unresolved merge conflicts
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.entries.values()
+            .filter(|e| e.stage() != 0)
+            .map(|e| e.path.clone())
+            .collect();
+        paths.dedup();
+        paths
+    }
+
+    /// Conflict stages (1 = base, 2 = ours, 3 = theirs) recorded for a path
+    pub fn stages(&self, path: &str) -> Vec<u16> {
+        self.entries.range((path.to_string(), 1)..=(path.to_string(), 3))
+            .map(|(_, e)| e.stage())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Stat fields cached in the index; truncated to 32 bits like Git does
+struct Stat {
+    ctime_secs: u32,
+    ctime_nsecs: u32,
+    mtime_secs: u32,
+    mtime_nsecs: u32,
+    dev: u32,
+    ino: u32,
+    uid: u32,
+    gid: u32,
+    size: u32,
+}
+
+impl Stat {
+    #[cfg(unix)]
+    fn from_metadata(meta: &fs::Metadata) -> Self {
+        use std::os::unix::fs::MetadataExt;
+        Self {
+            ctime_secs: meta.ctime() as u32,
+            ctime_nsecs: meta.ctime_nsec() as u32,
+            mtime_secs: meta.mtime() as u32,
+            mtime_nsecs: meta.mtime_nsec() as u32,
+            dev: meta.dev() as u32,
+            ino: meta.ino() as u32,
+            uid: meta.uid(),
+            gid: meta.gid(),
+            size: meta.len() as u32,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn from_metadata(meta: &fs::Metadata) -> Self {
+        let since_epoch = |t: std::io::Result<std::time::SystemTime>| {
+            t.ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .unwrap_or_default()
+        };
+        let mtime = since_epoch(meta.modified());
+        let ctime = since_epoch(meta.created());
+        Self {
+            ctime_secs: ctime.as_secs() as u32,
+            ctime_nsecs: ctime.subsec_nanos(),
+            mtime_secs: mtime.as_secs() as u32,
+            mtime_nsecs: mtime.subsec_nanos(),
+            dev: 0,
+            ino: 0,
+            uid: 0,
+            gid: 0,
+            size: meta.len() as u32,
+        }
+    }
+}
+
+/// Git mode for a working tree file: symlink, executable or regular.
+/// Expects metadata from `symlink_metadata` so links are not followed.
+#[cfg(unix)]
+pub fn file_mode(meta: &fs::Metadata) -> u32 {
+    use std::os::unix::fs::PermissionsExt;
+    if meta.file_type().is_symlink() {
+        0o120000
+    } else if meta.permissions().mode() & 0o111 != 0 {
+        0o100755
+    } else {
+        0o100644
+    }
+}
+
+#[cfg(not(unix))]
+pub fn file_mode(meta: &fs::Metadata) -> u32 {
+    if meta.file_type().is_symlink() {
+        0o120000
+    } else {
+        0o100644
+    }
+}
+
+/// Flags word for a stage-0 entry: the low 12 bits hold the name length
+fn name_flags(path: &str) -> u16 {
+    path.len().min(0xfff) as u16
+}
+
+fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    let bytes = data.get(pos..pos + 4).context("Index file is truncated")?;
+    Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    let bytes = data.get(pos..pos + 2).context("Index file is truncated")?;
+    Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native_git::testing::ScratchDir;
+
+    const BLOB: &str = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+    const OTHER: &str = "d00491fd7e5bb6fa28c517a0bb32b8b506539d4d";
+
+    fn stat_entry(path: &str, hash: &str) -> IndexEntry {
+        let mut entry = IndexEntry::without_stat(path.to_string(), 0o100644, hash.to_string());
+        entry.ctime_secs = 1_700_000_000;
+        entry.ctime_nsecs = 12;
+        entry.mtime_secs = 1_700_000_001;
+        entry.mtime_nsecs = 34;
+        entry.dev = 5;
+        entry.ino = 6;
+        entry.uid = 1000;
+        entry.gid = 100;
+        entry.size = 42;
+        entry
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = ScratchDir::new("index-round-trip");
+        let mut index = Index::load(dir.path()).unwrap();
+        assert!(index.is_empty());
+
+        // Path lengths around the 8-byte padding boundary
+        let paths = ["a", "ab", "abcdefg", "abcdefgh", "dir/nested/file.txt", "z"];
+        for path in paths {
+            index.add(stat_entry(path, BLOB));
+        }
+        let mut exec = IndexEntry::without_stat("run.sh".to_string(), 0o100755, OTHER.to_string());
+        exec.size = 7;
+        index.add(exec);
+        index.add(IndexEntry::without_stat("link".to_string(), 0o120000, OTHER.to_string()));
+        index.save().unwrap();
+
+        let loaded = Index::load(dir.path()).unwrap();
+        assert_eq!(loaded.len(), index.len());
+        let before: Vec<&IndexEntry> = index.entries().collect();
+        let after: Vec<&IndexEntry> = loaded.entries().collect();
+        assert_eq!(before, after);
+        assert_eq!(loaded.get("run.sh").unwrap().mode, 0o100755);
+        assert_eq!(loaded.get("dir/nested/file.txt").unwrap().size, 42);
+    }
+
+    #[test]
+    fn entries_are_sorted_by_path() {
+        let dir = ScratchDir::new("index-order");
+        let mut index = Index::load(dir.path()).unwrap();
+        for path in ["b", "a/z", "a.txt", "a"] {
+            index.add(stat_entry(path, BLOB));
+        }
+        index.save().unwrap();
+
+        let loaded = Index::load(dir.path()).unwrap();
+        let paths: Vec<&str> = loaded.entries().map(|e| e.path.as_str()).collect();
+        assert_eq!(paths, ["a", "a.txt", "a/z", "b"]);
+    }
+
+    #[test]
+    fn conflict_stages_survive_and_resolve() {
+        let dir = ScratchDir::new("index-conflicts");
+        let mut index = Index::load(dir.path()).unwrap();
+        index.add(stat_entry("clean", BLOB));
+        for stage in 1..=3 {
+            index.add(IndexEntry::unmerged("both".to_string(), 0o100644, OTHER.to_string(), stage));
+        }
+        index.save().unwrap();
+
+        let mut loaded = Index::load(dir.path()).unwrap();
+        assert_eq!(loaded.conflicts(), ["both"]);
+        assert_eq!(loaded.stages("both"), [1, 2, 3]);
+        assert!(loaded.get("both").is_none());
+
+        // Staging the path resolves the conflict
+        loaded.add(stat_entry("both", BLOB));
+        assert!(loaded.conflicts().is_empty());
+        assert_eq!(loaded.len(), 2);
+    }
+
+    #[test]
+    fn corrupt_checksum_is_rejected() {
+        let dir = ScratchDir::new("index-checksum");
+        let mut index = Index::load(dir.path()).unwrap();
+        index.add(stat_entry("file", BLOB));
+        index.save().unwrap();
+
+        let path = dir.path().join("index");
+        let mut data = fs::read(&path).unwrap();
+        data[20] ^= 0xff;
+        fs::write(&path, data).unwrap();
+        assert!(Index::load(dir.path()).is_err());
+    }
+
+    #[test]
+    fn remove_dir_only_matches_whole_components() {
+        let dir = ScratchDir::new("index-remove-dir");
+        let mut index = Index::load(dir.path()).unwrap();
+        for path in ["src/a", "src/b/c", "srcfile"] {
+            index.add(stat_entry(path, BLOB));
+        }
+        assert_eq!(index.remove_dir("src/"), ["src/a", "src/b/c"]);
+        assert!(index.get("srcfile").is_some());
+    }
+
+    #[test]
+    fn save_respects_a_held_lock() {
+        let dir = ScratchDir::new("index-lock");
+        let mut index = Index::load(dir.path()).unwrap();
+        index.add(stat_entry("first", BLOB));
+        index.save().unwrap();
+
+        // Another process is in the middle of writing the index
+        let lock_path = dir.path().join("index.lock");
+        fs::write(&lock_path, b"theirs").unwrap();
+        index.add(stat_entry("second", BLOB));
+        let err = index.save().unwrap_err().to_string();
+        assert!(err.contains("index.lock exists"), "{}", err);
+        assert_eq!(fs::read(&lock_path).unwrap(), b"theirs");
+        assert_eq!(Index::load(dir.path()).unwrap().len(), 1);
+
+        fs::remove_file(&lock_path).unwrap();
+        index.save().unwrap();
+        assert!(!lock_path.exists());
+        assert_eq!(Index::load(dir.path()).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn truncated_extended_entry_is_rejected() {
+        let dir = ScratchDir::new("index-truncated");
+
+        // A version 3 entry whose extended flags word is cut off
+        let mut data = Vec::new();
+        data.extend_from_slice(INDEX_SIGNATURE);
+        data.extend_from_slice(&3u32.to_be_bytes());
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.resize(12 + 60, 0);
+        data.extend_from_slice(&0x4000u16.to_be_bytes());
+        data.extend_from_slice(&hex::decode(hash::compute_hash(&data)).unwrap());
+        fs::write(dir.path().join("index"), data).unwrap();
+
+        let err = Index::load(dir.path()).err().expect("truncated index must not load");
+        assert_eq!(err.to_string(), "Index entry is truncated");
+    }
+}
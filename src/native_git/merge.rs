@@ -0,0 +1,379 @@
+// TriForge/src/native_git/merge.rs
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::fs;
+use anyhow::Result;
+
+use super::commit::CommitBuilder;
+use super::diff::{self, Edit};
+use super::objects::{GitObject, ObjectType};
+use super::repository::Repository;
+use super::tree::TreeEntry;
+
+/// Result of merging one file's lines
+pub struct LineMerge {
+    pub content: Vec<u8>,
+    pub conflicts: usize,
+}
+
+/// A path the tree merge could not resolve on its own
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub path: String,
+    pub base: Option<TreeEntry>,
+    pub ours: Option<TreeEntry>,
+    pub theirs: Option<TreeEntry>,
+    /// Content to leave in the working tree for the user to resolve
+    pub content: Vec<u8>,
+    /// Mode for the working tree file
+    pub mode: String,
+}
+
+/// Outcome of a three-way tree merge
+pub struct TreeMerge {
+    /// Cleanly merged files keyed by path
+    pub files: BTreeMap<String, TreeEntry>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Labels written into conflict markers
+pub struct MergeLabels<'a> {
+    pub ours: &'a str,
+    pub theirs: &'a str,
+}
+
+/// Commit being merged while a conflicted merge is in progress
+pub fn merge_head(repo: &Repository) -> Option<String> {
+    fs::read_to_string(repo.git_dir().join("MERGE_HEAD"))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Record an in-progress merge the way Git does
+pub fn write_merge_state(repo: &Repository, head: &str, theirs: &str, message: &str) -> Result<()> {
+    fs::write(repo.git_dir().join("ORIG_HEAD"), format!("{}\n", head))?;
+    fs::write(repo.git_dir().join("MERGE_HEAD"), format!("{}\n", theirs))?;
+    fs::write(repo.git_dir().join("MERGE_MSG"), message)?;
+    Ok(())
+}
+
+/// Saved message for the pending merge commit
+pub fn merge_message(repo: &Repository) -> Option<String> {
+    fs::read_to_string(repo.git_dir().join("MERGE_MSG")).ok()
+}
+
+/// Forget an in-progress merge
+pub fn clear_merge_state(repo: &Repository) -> Result<()> {
+    for name in ["MERGE_HEAD", "MERGE_MSG"] {
+        let path = repo.git_dir().join(name);
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Parent hashes of a commit
+fn parents(repo: &Repository, hash: &str) -> Result<Vec<String>> {
+    Ok(CommitBuilder::parse(&repo.load_object(hash)?)?.parents)
+}
+
+/// Every commit reachable from `start`, including itself
+pub fn ancestors(repo: &Repository, start: &str) -> Result<HashSet<String>> {
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([start.to_string()]);
+
+    while let Some(hash) = queue.pop_front() {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+        queue.extend(parents(repo, &hash)?);
+    }
+
+    Ok(seen)
+}
+
+/// Whether `ancestor` is reachable from `descendant`
+pub fn is_ancestor(repo: &Repository, ancestor: &str, descendant: &str) -> Result<bool> {
+    Ok(ancestors(repo, descendant)?.contains(ancestor))
+}
+
+/// Best common ancestor of two commits, if they share history
+pub fn merge_base(repo: &Repository, a: &str, b: &str) -> Result<Option<String>> {
+    let from_a = ancestors(repo, a)?;
+
+    // Walk back from `b`, stopping at the first common commit on each path
+    let mut candidates = Vec::new();
+    let mut seen = HashSet::new();
+    let mut queue = VecDeque::from([b.to_string()]);
+    while let Some(hash) = queue.pop_front() {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+        if from_a.contains(&hash) {
+            candidates.push(hash);
+        } else {
+            queue.extend(parents(repo, &hash)?);
+        }
+    }
+
+    // Drop candidates that are ancestors of other candidates
+    let mut best = Vec::new();
+    for candidate in &candidates {
+        let mut redundant = false;
+        for other in &candidates {
+            if other != candidate && is_ancestor(repo, candidate, other)? {
+                redundant = true;
+                break;
+            }
+        }
+        if !redundant {
+            best.push(candidate.clone());
+        }
+    }
+
+    Ok(best.into_iter().next())
+}
+
+/// Three-way merge of text, writing Git-style conflict markers where both
+/// sides changed the same region differently
+pub fn merge_lines(base: &[u8], ours: &[u8], theirs: &[u8], labels: &MergeLabels) -> LineMerge {
+    let base_lines = diff::split_lines(base);
+    let our_lines = diff::split_lines(ours);
+    let their_lines = diff::split_lines(theirs);
+
+    let our_match = matches(&base_lines, &our_lines);
+    let their_match = matches(&base_lines, &their_lines);
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut conflicts = 0;
+    let (mut b, mut o, mut t) = (0, 0, 0);
+
+    while b < base_lines.len() || o < our_lines.len() || t < their_lines.len() {
+        // Stable line present unchanged on all three sides
+        if b < base_lines.len() && our_match[b] == Some(o) && their_match[b] == Some(t) {
+            out.extend_from_slice(base_lines[b]);
+            b += 1;
+            o += 1;
+            t += 1;
+            continue;
+        }
+
+        // Find the next base line both sides kept; everything before it is one chunk
+        let mut next = (base_lines.len(), our_lines.len(), their_lines.len());
+        for k in b..base_lines.len() {
+            if let (Some(ok), Some(tk)) = (our_match[k], their_match[k]) {
+                if ok >= o && tk >= t {
+                    next = (k, ok, tk);
+                    break;
+                }
+            }
+        }
+
+        let base_chunk = &base_lines[b..next.0];
+        let our_chunk = &our_lines[o..next.1];
+        let their_chunk = &their_lines[t..next.2];
+
+        if our_chunk == base_chunk {
+            their_chunk.iter().for_each(|l| out.extend_from_slice(l));
+        } else if their_chunk == base_chunk || our_chunk == their_chunk {
+            our_chunk.iter().for_each(|l| out.extend_from_slice(l));
+        } else {
+            conflicts += 1;
+            out.extend_from_slice(format!("<<<<<<< {}\n", labels.ours).as_bytes());
+            push_chunk(&mut out, our_chunk);
+            out.extend_from_slice(b"=======\n");
+            push_chunk(&mut out, their_chunk);
+            out.extend_from_slice(format!(">>>>>>> {}\n", labels.theirs).as_bytes());
+        }
+
+        b = next.0;
+        o = next.1;
+        t = next.2;
+    }
+
+    LineMerge { content: out, conflicts }
+}
+
+/// For each base line, the index of the equal line on the other side
+fn matches(base: &[&[u8]], other: &[&[u8]]) -> Vec<Option<usize>> {
+    let mut map = vec![None; base.len()];
+    for edit in diff::diff_lines(base, other) {
+        if let Edit::Equal(x, y) = edit {
+            map[x] = Some(y);
+        }
+    }
+    map
+}
+
+/// Append lines inside a conflict block, making sure a marker follows on its own line
+fn push_chunk(out: &mut Vec<u8>, lines: &[&[u8]]) {
+    for line in lines {
+        out.extend_from_slice(line);
+    }
+    if lines.last().map(|l| !l.ends_with(b"\n")).unwrap_or(false) {
+        out.push(b'\n');
+    }
+}
+
+/// Merge three flattened trees. Clean results are stored as blobs; paths
+/// that need attention are returned as conflicts.
+pub fn merge_trees(
+    repo: &Repository,
+    base: &BTreeMap<String, TreeEntry>,
+    ours: &BTreeMap<String, TreeEntry>,
+    theirs: &BTreeMap<String, TreeEntry>,
+    labels: &MergeLabels,
+) -> Result<TreeMerge> {
+    let same = |a: Option<&TreeEntry>, b: Option<&TreeEntry>| match (a, b) {
+        (Some(a), Some(b)) => a.hash == b.hash && a.mode == b.mode,
+        (None, None) => true,
+        _ => false,
+    };
+
+    let paths: BTreeSet<&String> = base.keys().chain(ours.keys()).chain(theirs.keys()).collect();
+    let mut files = BTreeMap::new();
+    let mut conflicts = Vec::new();
+
+    for path in paths {
+        let b = base.get(path);
+        let o = ours.get(path);
+        let t = theirs.get(path);
+
+        let resolved = if same(o, t) || same(b, t) {
+            o
+        } else if same(b, o) {
+            t
+        } else {
+            match (o, t) {
+                (Some(o), Some(t)) => {
+                    let base_content = match b {
+                        Some(b) => repo.load_object(&b.hash)?.content,
+                        None => Vec::new(),
+                    };
+                    let our_content = repo.load_object(&o.hash)?.content;
+                    let their_content = repo.load_object(&t.hash)?.content;
+
+                    // Take the mode change from whichever side made it
+                    let mode = match b {
+                        Some(b) if b.mode == o.mode => t.mode.clone(),
+                        _ => o.mode.clone(),
+                    };
+
+                    let binary = diff::is_binary(&base_content)
+                        || diff::is_binary(&our_content)
+                        || diff::is_binary(&their_content);
+
+                    if binary {
+                        conflicts.push(Conflict {
+                            path: path.clone(),
+                            base: b.cloned(),
+                            ours: Some(o.clone()),
+                            theirs: Some(t.clone()),
+                            content: our_content,
+                            mode,
+                        });
+                        continue;
+                    }
+
+                    let merged = merge_lines(&base_content, &our_content, &their_content, labels);
+                    if merged.conflicts > 0 {
+                        conflicts.push(Conflict {
+                            path: path.clone(),
+                            base: b.cloned(),
+                            ours: Some(o.clone()),
+                            theirs: Some(t.clone()),
+                            content: merged.content,
+                            mode,
+                        });
+                        continue;
+                    }
+
+                    let blob = GitObject::new(ObjectType::Blob, merged.content);
+                    let hash = repo.store_object(&blob)?;
+                    files.insert(path.clone(), TreeEntry {
+                        mode,
+                        name: path.clone(),
+                        hash,
+                    });
+                    continue;
+                }
+                // Modified on one side, deleted on the other: keep the modified
+                // version in the working tree for the user to decide
+                (Some(kept), None) | (None, Some(kept)) => {
+                    conflicts.push(Conflict {
+                        path: path.clone(),
+                        base: b.cloned(),
+                        ours: o.cloned(),
+                        theirs: t.cloned(),
+                        content: repo.load_object(&kept.hash)?.content,
+                        mode: kept.mode.clone(),
+                    });
+                    continue;
+                }
+                (None, None) => None,
+            }
+        };
+
+        if let Some(entry) = resolved {
+            files.insert(path.clone(), entry.clone());
+        }
+    }
+
+    Ok(TreeMerge { files, conflicts })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LABELS: MergeLabels<'static> = MergeLabels { ours: "HEAD", theirs: "topic" };
+
+    fn merge(base: &str, ours: &str, theirs: &str) -> (String, usize) {
+        let merged = merge_lines(base.as_bytes(), ours.as_bytes(), theirs.as_bytes(), &LABELS);
+        (String::from_utf8(merged.content).unwrap(), merged.conflicts)
+    }
+
+    #[test]
+    fn separate_changes_merge_cleanly() {
+        let base = "a\nb\nc\nd\ne\n";
+        assert_eq!(merge(base, "A\nb\nc\nd\ne\n", "a\nb\nc\nd\nE\n"), ("A\nb\nc\nd\nE\n".to_string(), 0));
+        assert_eq!(merge(base, "a\nc\nd\ne\n", "a\nb\nc\nd\ne\nf\n"), ("a\nc\nd\ne\nf\n".to_string(), 0));
+    }
+
+    #[test]
+    fn one_sided_and_identical_changes_are_taken() {
+        let base = "a\nb\nc\n";
+        assert_eq!(merge(base, base, "a\nB\nc\n"), ("a\nB\nc\n".to_string(), 0));
+        assert_eq!(merge(base, "a\nB\nc\n", base), ("a\nB\nc\n".to_string(), 0));
+        assert_eq!(merge(base, "a\nB\nc\n", "a\nB\nc\n"), ("a\nB\nc\n".to_string(), 0));
+    }
+
+    #[test]
+    fn overlapping_changes_conflict() {
+        let (content, conflicts) = merge("a\nb\nc\n", "a\nours\nc\n", "a\ntheirs\nc\n");
+        assert_eq!(conflicts, 1);
+        assert_eq!(content, "a\n<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\nc\n");
+    }
+
+    #[test]
+    fn each_overlapping_region_is_its_own_conflict() {
+        let (_, conflicts) = merge("a\nb\nc\nd\ne\n", "1\nb\nc\nd\n5\n", "x\nb\nc\nd\ny\n");
+        assert_eq!(conflicts, 2);
+    }
+
+    #[test]
+    fn markers_stay_on_their_own_lines() {
+        let (content, conflicts) = merge("a\n", "ours", "theirs");
+        assert_eq!(conflicts, 1);
+        assert_eq!(content, "<<<<<<< HEAD\nours\n=======\ntheirs\n>>>>>>> topic\n");
+    }
+
+    #[test]
+    fn both_sides_adding_a_file_differently_conflicts() {
+        let (content, conflicts) = merge("", "one\n", "two\n");
+        assert_eq!(conflicts, 1);
+        assert_eq!(content, "<<<<<<< HEAD\none\n=======\ntwo\n>>>>>>> topic\n");
+        assert_eq!(merge("", "same\n", "same\n"), ("same\n".to_string(), 0));
+    }
+}
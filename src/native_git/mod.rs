@@ -1,17 +1,34 @@
 // TriForge/src/native_git/mod.rs
 pub mod objects;
+pub mod pack;
 pub mod refs;
 pub mod repository;
 pub mod tree;
 pub mod commit;
+pub mod tag;
 pub mod hash;
+pub mod index;
+pub mod diff;
+pub mod merge;
+pub mod ignore;
+pub mod config;
+pub mod revwalk;
+pub mod revparse;
+pub mod reflog;
+pub mod refspec;
+
+#[cfg(test)]
+mod testing;
 
 // Re-export commonly used types
 pub use objects::{GitObject, ObjectType};
 pub use repository::Repository;
 pub use refs::Refs;
 pub use tree::TreeBuilder;
-pub use commit::CommitBuilder;
+pub use commit::{CommitBuilder, Signature};
+pub use tag::TagBuilder;
+pub use index::{Index, IndexEntry};
+pub use ignore::IgnoreRules;
 
 use anyhow::Result;
 use std::path::Path;
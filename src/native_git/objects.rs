@@ -8,6 +8,7 @@ use flate2::Compression;
 use std::io::{Write, Read};
 
 use super::hash;
+use super::pack;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ObjectType {
@@ -85,11 +86,11 @@ impl GitObject {
 
     /// Load an object from a Git repository
     pub fn load(git_dir: &Path, hash: &str) -> Result<Self> {
-        let objects_dir = git_dir.join("objects");
-        let obj_path = objects_dir.join(&hash[..2]).join(&hash[2..]);
+        let obj_path = Self::loose_path(git_dir, hash);
         
         if !obj_path.exists() {
-            anyhow::bail!("Object not found: {}", hash);
+            return pack::read_object(git_dir, hash)?
+                .ok_or_else(|| anyhow::anyhow!("Object not found: {}", hash));
         }
 
         let compressed = fs::read(&obj_path)?;
@@ -99,7 +100,11 @@ impl GitObject {
         let mut data = Vec::new();
         decoder.read_to_end(&mut data)?;
         
-        // Parse header
+        Self::parse(hash, &data)
+    }
+
+    /// Parse uncompressed object data (`<type> <size>\0<content>`)
+    pub fn parse(hash: &str, data: &[u8]) -> Result<Self> {
         let null_pos = data.iter()
             .position(|&b| b == 0)
             .context("Invalid object format")?;
@@ -125,8 +130,22 @@ impl GitObject {
         (&self.hash[..2], &self.hash[2..])
     }
 
-    /// List all objects in a repository
+    /// Where the loose copy of an object lives
+    pub fn loose_path(git_dir: &Path, hash: &str) -> PathBuf {
+        git_dir.join("objects").join(&hash[..2]).join(&hash[2..])
+    }
+
+    /// List all objects in a repository, loose and packed
     pub fn list_all(git_dir: &Path) -> Result<Vec<String>> {
+        let mut objects = Self::list_loose(git_dir)?;
+        objects.extend(pack::list_packed(git_dir)?);
+        objects.sort();
+        objects.dedup();
+        Ok(objects)
+    }
+
+    /// List the objects stored as individual files under `objects/xx/`
+    pub fn list_loose(git_dir: &Path) -> Result<Vec<String>> {
         let objects_dir = git_dir.join("objects");
         let mut objects = Vec::new();
         
@@ -1,20 +1,820 @@
-pub use super::objects::{GitObject, ObjectType};
-pub use super::repository::Repository;
-pub use super::refs::Refs;
-pub use super::tree::TreeBuilder;
-pub use super::commit::CommitBuilder;
+// TriForge/src/native_git/pack.rs
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use anyhow::{Context, Result};
+use flate2::{Compress, Compression, Crc, Decompress, FlushCompress, FlushDecompress, Status};
+use sha1::{Digest, Sha1};
 
-use anyhow::Result;
-use std::path::Path;
+use super::objects::{GitObject, ObjectType};
 
-/// Initialize a new Git repository
-pub fn init<P: AsRef<Path>>(path: P) -> Result<Repository> {
-    Repository::init(path)
+const PACK_SIGNATURE: &[u8; 4] = b"PACK";
+const IDX_SIGNATURE: [u8; 4] = [0xff, b't', b'O', b'c'];
+const PACK_VERSION: u32 = 2;
+const IDX_VERSION: u32 = 2;
+
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+
+/// Offsets at or above this go to the 64-bit table of a v2 index
+const LARGE_OFFSET: u64 = 0x8000_0000;
+/// Recently written objects of the same type tried as delta bases
+const DELTA_WINDOW: usize = 10;
+/// Longest delta chain written before an object is stored whole
+const MAX_DELTA_DEPTH: usize = 50;
+/// Granularity at which a delta base is indexed for matches
+const DELTA_BLOCK: usize = 16;
+/// Objects smaller than this are never worth deltifying
+const MIN_DELTA_SIZE: usize = 64;
+
+/// Packs opened so far, keyed by path, so each is parsed once per process
+static PACK_CACHE: OnceLock<Mutex<HashMap<PathBuf, Arc<Pack>>>> = OnceLock::new();
+
+fn type_code(obj_type: ObjectType) -> u8 {
+    match obj_type {
+        ObjectType::Commit => 1,
+        ObjectType::Tree => 2,
+        ObjectType::Blob => 3,
+        ObjectType::Tag => 4,
+    }
+}
+
+fn type_from_code(code: u8) -> Option<ObjectType> {
+    match code {
+        1 => Some(ObjectType::Commit),
+        2 => Some(ObjectType::Tree),
+        3 => Some(ObjectType::Blob),
+        4 => Some(ObjectType::Tag),
+        _ => None,
+    }
+}
+
+/// A `.idx` v2 file: sorted object names with their offsets in the pack
+struct PackIndex {
+    names: Vec<[u8; 20]>,
+    offsets: Vec<u64>,
+    pack_checksum: [u8; 20],
+}
+
+impl PackIndex {
+    fn parse(data: &[u8]) -> Result<Self> {
+        if data.len() < 8 + 256 * 4 + 40 || data[..4] != IDX_SIGNATURE {
+            anyhow::bail!("Not a version 2 pack index");
+        }
+        let version = read_u32(data, 4);
+        if version != IDX_VERSION {
+            anyhow::bail!("Unsupported pack index version {}", version);
+        }
+
+        let count = read_u32(data, 8 + 255 * 4) as usize;
+        let names_at = 8 + 256 * 4;
+        let crcs_at = names_at + count * 20;
+        let offsets_at = crcs_at + count * 4;
+        let large_at = offsets_at + count * 4;
+        if data.len() < large_at + 40 {
+            anyhow::bail!("Pack index is truncated");
+        }
+
+        let names = (0..count)
+            .map(|i| {
+                let mut name = [0u8; 20];
+                name.copy_from_slice(&data[names_at + i * 20..names_at + (i + 1) * 20]);
+                name
+            })
+            .collect();
+
+        let mut offsets = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = read_u32(data, offsets_at + i * 4);
+            if offset & 0x8000_0000 == 0 {
+                offsets.push(offset as u64);
+            } else {
+                let at = large_at + (offset & 0x7fff_ffff) as usize * 8;
+                if data.len() < at + 8 + 40 {
+                    anyhow::bail!("Pack index is truncated");
+                }
+                offsets.push(((read_u32(data, at) as u64) << 32) | read_u32(data, at + 4) as u64);
+            }
+        }
+
+        let mut pack_checksum = [0u8; 20];
+        pack_checksum.copy_from_slice(&data[data.len() - 40..data.len() - 20]);
+
+        Ok(Self { names, offsets, pack_checksum })
+    }
+
+    fn find(&self, name: &[u8; 20]) -> Option<u64> {
+        self.names.binary_search(name).ok().map(|i| self.offsets[i])
+    }
+}
+
+/// A packfile and its index, held in memory
+pub struct Pack {
+    path: PathBuf,
+    index: PackIndex,
+    data: Vec<u8>,
+}
+
+impl Pack {
+    /// Open `pack-<hash>.pack` together with the `.idx` next to it
+    pub fn open(pack_path: &Path) -> Result<Self> {
+        let idx_path = pack_path.with_extension("idx");
+        let index = PackIndex::parse(&fs::read(&idx_path)
+            .with_context(|| format!("Failed to read {}", idx_path.display()))?)?;
+        let data = fs::read(pack_path)
+            .with_context(|| format!("Failed to read {}", pack_path.display()))?;
+
+        if data.len() < 32 || &data[..4] != PACK_SIGNATURE {
+            anyhow::bail!("{} is not a packfile", pack_path.display());
+        }
+        let version = read_u32(&data, 4);
+        if version != 2 && version != 3 {
+            anyhow::bail!("Unsupported pack version {} in {}", version, pack_path.display());
+        }
+        if data[data.len() - 20..] != index.pack_checksum {
+            anyhow::bail!("{} does not match its index", pack_path.display());
+        }
+
+        Ok(Self {
+            path: pack_path.to_path_buf(),
+            index,
+            data,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Hashes of every object in the pack
+    pub fn hashes(&self) -> impl Iterator<Item = String> + '_ {
+        self.index.names.iter().map(hex::encode)
+    }
+
+    /// Read an object, resolving deltas. REF_DELTA bases outside this pack
+    /// are loaded from the rest of the object store in `git_dir`.
+    pub fn read(&self, git_dir: &Path, hash: &str) -> Result<Option<GitObject>> {
+        let offset = match parse_name(hash).and_then(|n| self.index.find(&n)) {
+            Some(offset) => offset,
+            None => return Ok(None),
+        };
+
+        let (obj_type, content) = self.read_at(git_dir, offset)
+            .with_context(|| format!("Corrupt object {} in {}", hash, self.path.display()))?;
+
+        Ok(Some(GitObject {
+            obj_type,
+            content,
+            hash: hash.to_string(),
+        }))
+    }
+
+    fn read_at(&self, git_dir: &Path, offset: u64) -> Result<(ObjectType, Vec<u8>)> {
+        let start = offset as usize;
+        if start >= self.data.len() - 20 {
+            anyhow::bail!("Offset {} is outside the pack", offset);
+        }
+        let (code, size, mut pos) = read_entry_header(&self.data, start)?;
+
+        match code {
+            OBJ_OFS_DELTA => {
+                let (distance, next) = read_ofs_distance(&self.data, pos)?;
+                pos = next;
+                let base_offset = offset.checked_sub(distance)
+                    .filter(|_| distance > 0)
+                    .context("Delta base offset is out of range")?;
+
+                let (base_type, base) = self.read_at(git_dir, base_offset)?;
+                let delta = inflate(&self.data[pos..], size)?;
+                Ok((base_type, apply_delta(&base, &delta)?))
+            }
+            OBJ_REF_DELTA => {
+                let mut base_name = [0u8; 20];
+                base_name.copy_from_slice(self.data.get(pos..pos + 20).context("Truncated delta base")?);
+                pos += 20;
+
+                let (base_type, base) = match self.index.find(&base_name) {
+                    Some(base_offset) => self.read_at(git_dir, base_offset)?,
+                    None => {
+                        let obj = GitObject::load(git_dir, &hex::encode(base_name))?;
+                        (obj.obj_type, obj.content)
+                    }
+                };
+                let delta = inflate(&self.data[pos..], size)?;
+                Ok((base_type, apply_delta(&base, &delta)?))
+            }
+            code => {
+                let obj_type = type_from_code(code)
+                    .with_context(|| format!("Unknown pack object type {}", code))?;
+                Ok((obj_type, inflate(&self.data[pos..], size)?))
+            }
+        }
+    }
 }
 
-/// Open an existing Git repository
-pub fn open<P: AsRef<Path>>(path: P) -> Result<Repository> {
-    Repository::open(path)
+/// Every pack under `objects/pack`, newest first
+pub fn packs(git_dir: &Path) -> Result<Vec<Arc<Pack>>> {
+    let pack_dir = git_dir.join("objects").join("pack");
+    if !pack_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&pack_dir)? {
+        let path = entry?.path();
+        if path.extension().map(|e| e == "pack").unwrap_or(false) && path.with_extension("idx").is_file() {
+            let modified = fs::metadata(&path)?.modified()?;
+            paths.push((modified, path));
+        }
+    }
+    paths.sort();
+    paths.reverse();
+
+    let cache = PACK_CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+
+    let mut result = Vec::new();
+    for (_, path) in paths {
+        let pack = match cache.get(&path) {
+            Some(pack) => pack.clone(),
+            None => {
+                let pack = Arc::new(Pack::open(&path)?);
+                cache.insert(path, pack.clone());
+                pack
+            }
+        };
+        result.push(pack);
+    }
+    Ok(result)
+}
+
+/// Look an object up in the repository's packs
+pub fn read_object(git_dir: &Path, hash: &str) -> Result<Option<GitObject>> {
+    for pack in packs(git_dir)? {
+        if let Some(obj) = pack.read(git_dir, hash)? {
+            return Ok(Some(obj));
+        }
+    }
+    Ok(None)
+}
+
+/// Hashes of every packed object
+pub fn list_packed(git_dir: &Path) -> Result<Vec<String>> {
+    let mut hashes = Vec::new();
+    for pack in packs(git_dir)? {
+        hashes.extend(pack.hashes());
+    }
+    Ok(hashes)
+}
+
+/// Write `objects` into a new pack under `objects/pack`, storing objects as
+/// deltas against similar ones where that saves space. Returns the pack's
+/// checksum, which names both the `.pack` and the `.idx`.
+pub fn write_pack(git_dir: &Path, objects: &[GitObject]) -> Result<String> {
+    let (pack, entries) = build_pack(objects)?;
+    let checksum = hex::encode(&pack[pack.len() - 20..]);
+
+    let pack_dir = git_dir.join("objects").join("pack");
+    fs::create_dir_all(&pack_dir)?;
+
+    let base = pack_dir.join(format!("pack-{}", checksum));
+    fs::write(base.with_extension("pack"), &pack)?;
+    // The index goes last: readers only pick up packs that have one
+    fs::write(base.with_extension("idx"), build_index(&entries, &pack[pack.len() - 20..]))?;
+
+    Ok(checksum)
 }
 
-// TriForge/src/native_git/hash.rs
+/// Where an object ended up in a pack being written
+struct PackedEntry {
+    name: [u8; 20],
+    offset: u64,
+    crc: u32,
+}
+
+fn build_pack(objects: &[GitObject]) -> Result<(Vec<u8>, Vec<PackedEntry>)> {
+    // Group by type and put big objects first so smaller, usually older
+    // versions become deltas against them
+    let mut order: Vec<&GitObject> = objects.iter().collect();
+    order.sort_by(|a, b| {
+        type_code(a.obj_type).cmp(&type_code(b.obj_type))
+            .then(b.content.len().cmp(&a.content.len()))
+            .then(a.hash.cmp(&b.hash))
+    });
+    order.dedup_by(|a, b| a.hash == b.hash);
+
+    let mut pack = Vec::new();
+    pack.extend_from_slice(PACK_SIGNATURE);
+    pack.extend_from_slice(&PACK_VERSION.to_be_bytes());
+    pack.extend_from_slice(&(order.len() as u32).to_be_bytes());
+
+    let mut entries = Vec::with_capacity(order.len());
+    // (index into `order`, offset, delta depth) of recent candidates
+    let mut window: Vec<(usize, u64, usize)> = Vec::new();
+
+    for (i, obj) in order.iter().enumerate() {
+        let offset = pack.len() as u64;
+
+        let mut best: Option<(Vec<u8>, u64, usize)> = None;
+        if obj.content.len() >= MIN_DELTA_SIZE {
+            for &(base_i, base_offset, depth) in &window {
+                let base = order[base_i];
+                if base.obj_type != obj.obj_type || depth >= MAX_DELTA_DEPTH {
+                    continue;
+                }
+                let delta = create_delta(&base.content, &obj.content);
+                let limit = best.as_ref().map(|(d, _, _)| d.len()).unwrap_or(obj.content.len() / 2);
+                if delta.len() < limit {
+                    best = Some((delta, base_offset, depth + 1));
+                }
+            }
+        }
+
+        let mut entry = Vec::new();
+        let depth = match best {
+            Some((delta, base_offset, depth)) => {
+                write_entry_header(&mut entry, OBJ_OFS_DELTA, delta.len());
+                entry.extend_from_slice(&encode_ofs_distance(offset - base_offset));
+                entry.extend_from_slice(&deflate(&delta)?);
+                depth
+            }
+            None => {
+                write_entry_header(&mut entry, type_code(obj.obj_type), obj.content.len());
+                entry.extend_from_slice(&deflate(&obj.content)?);
+                0
+            }
+        };
+
+        let mut crc = Crc::new();
+        crc.update(&entry);
+        pack.extend_from_slice(&entry);
+
+        entries.push(PackedEntry {
+            name: parse_name(&obj.hash).unwrap_or([0; 20]),
+            offset,
+            crc: crc.sum(),
+        });
+
+        window.push((i, offset, depth));
+        if window.len() > DELTA_WINDOW {
+            window.remove(0);
+        }
+    }
+
+    let checksum = Sha1::digest(&pack);
+    pack.extend_from_slice(&checksum);
+
+    Ok((pack, entries))
+}
+
+fn build_index(entries: &[PackedEntry], pack_checksum: &[u8]) -> Vec<u8> {
+    let mut sorted: Vec<&PackedEntry> = entries.iter().collect();
+    sorted.sort_by_key(|e| e.name);
+
+    let mut idx = Vec::new();
+    idx.extend_from_slice(&IDX_SIGNATURE);
+    idx.extend_from_slice(&IDX_VERSION.to_be_bytes());
+
+    let mut fanout = [0u32; 256];
+    for entry in &sorted {
+        fanout[entry.name[0] as usize] += 1;
+    }
+    let mut total = 0;
+    for count in fanout {
+        total += count;
+        idx.extend_from_slice(&total.to_be_bytes());
+    }
+
+    for entry in &sorted {
+        idx.extend_from_slice(&entry.name);
+    }
+    for entry in &sorted {
+        idx.extend_from_slice(&entry.crc.to_be_bytes());
+    }
+
+    let mut large = Vec::new();
+    for entry in &sorted {
+        if entry.offset < LARGE_OFFSET {
+            idx.extend_from_slice(&(entry.offset as u32).to_be_bytes());
+        } else {
+            idx.extend_from_slice(&(0x8000_0000 | large.len() as u32).to_be_bytes());
+            large.push(entry.offset);
+        }
+    }
+    for offset in large {
+        idx.extend_from_slice(&offset.to_be_bytes());
+    }
+
+    idx.extend_from_slice(pack_checksum);
+    let checksum = Sha1::digest(&idx);
+    idx.extend_from_slice(&checksum);
+    idx
+}
+
+/// Rebuild an object from its base and a Git delta
+pub fn apply_delta(base: &[u8], delta: &[u8]) -> Result<Vec<u8>> {
+    let mut pos = 0;
+    let base_size = read_size(delta, &mut pos)?;
+    let result_size = read_size(delta, &mut pos)?;
+    if base_size != base.len() {
+        anyhow::bail!("Delta expects a {}-byte base, got {}", base_size, base.len());
+    }
+
+    let mut out = Vec::with_capacity(result_size);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+
+        if op & 0x80 != 0 {
+            // Copy a range of the base; flag bits say which offset and
+            // size bytes follow
+            let mut offset = 0usize;
+            let mut size = 0usize;
+            for i in 0..4 {
+                if op & (1 << i) != 0 {
+                    offset |= (*delta.get(pos).context("Truncated delta")? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            for i in 0..3 {
+                if op & (0x10 << i) != 0 {
+                    size |= (*delta.get(pos).context("Truncated delta")? as usize) << (8 * i);
+                    pos += 1;
+                }
+            }
+            if size == 0 {
+                size = 0x10000;
+            }
+            let chunk = base.get(offset..offset + size).context("Delta copies past the end of its base")?;
+            out.extend_from_slice(chunk);
+        } else if op != 0 {
+            // Insert the next `op` bytes literally
+            let chunk = delta.get(pos..pos + op as usize).context("Truncated delta")?;
+            out.extend_from_slice(chunk);
+            pos += op as usize;
+        } else {
+            anyhow::bail!("Invalid delta opcode 0");
+        }
+    }
+
+    if out.len() != result_size {
+        anyhow::bail!("Delta produced {} bytes, expected {}", out.len(), result_size);
+    }
+    Ok(out)
+}
+
+/// Encode `target` as a Git delta against `base`: blocks of the base are
+/// indexed, and runs of the target found there become copy instructions
+pub fn create_delta(base: &[u8], target: &[u8]) -> Vec<u8> {
+    let mut delta = Vec::new();
+    write_size(&mut delta, base.len());
+    write_size(&mut delta, target.len());
+
+    let mut blocks: HashMap<&[u8], usize> = HashMap::new();
+    if base.len() >= DELTA_BLOCK {
+        for start in (0..=base.len() - DELTA_BLOCK).step_by(DELTA_BLOCK) {
+            blocks.entry(&base[start..start + DELTA_BLOCK]).or_insert(start);
+        }
+    }
+
+    let mut pending = 0;
+    let mut i = 0;
+    while i + DELTA_BLOCK <= target.len() {
+        let Some(&found) = blocks.get(&target[i..i + DELTA_BLOCK]) else {
+            i += 1;
+            continue;
+        };
+
+        // Grow the match backwards over not yet emitted bytes, then forwards
+        let (mut from, mut to) = (found, i);
+        while from > 0 && to > pending && base[from - 1] == target[to - 1] {
+            from -= 1;
+            to -= 1;
+        }
+        let mut len = i + DELTA_BLOCK - to;
+        while from + len < base.len() && to + len < target.len() && base[from + len] == target[to + len] {
+            len += 1;
+        }
+
+        push_insert(&mut delta, &target[pending..to]);
+        push_copy(&mut delta, from, len);
+        i = to + len;
+        pending = i;
+    }
+    push_insert(&mut delta, &target[pending..]);
+
+    delta
+}
+
+fn push_insert(delta: &mut Vec<u8>, mut data: &[u8]) {
+    while !data.is_empty() {
+        let n = data.len().min(0x7f);
+        delta.push(n as u8);
+        delta.extend_from_slice(&data[..n]);
+        data = &data[n..];
+    }
+}
+
+fn push_copy(delta: &mut Vec<u8>, mut offset: usize, mut len: usize) {
+    while len > 0 {
+        let n = len.min(0xff_ffff);
+        let mut op = 0x80u8;
+        let mut args = Vec::new();
+        for i in 0..4 {
+            let byte = (offset >> (8 * i)) as u8;
+            if byte != 0 {
+                op |= 1 << i;
+                args.push(byte);
+            }
+        }
+        for i in 0..3 {
+            let byte = (n >> (8 * i)) as u8;
+            if byte != 0 {
+                op |= 0x10 << i;
+                args.push(byte);
+            }
+        }
+        delta.push(op);
+        delta.extend_from_slice(&args);
+        offset += n;
+        len -= n;
+    }
+}
+
+/// Type and inflated size at the start of a pack entry
+fn read_entry_header(data: &[u8], mut pos: usize) -> Result<(u8, usize, usize)> {
+    let mut byte = *data.get(pos).context("Truncated pack entry")?;
+    let code = (byte >> 4) & 0x07;
+    let mut size = (byte & 0x0f) as usize;
+    let mut shift = 4;
+    pos += 1;
+    while byte & 0x80 != 0 {
+        byte = *data.get(pos).context("Truncated pack entry")?;
+        size |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        pos += 1;
+    }
+    Ok((code, size, pos))
+}
+
+fn write_entry_header(out: &mut Vec<u8>, code: u8, size: usize) {
+    let mut byte = (code << 4) | (size & 0x0f) as u8;
+    let mut rest = size >> 4;
+    while rest > 0 {
+        out.push(byte | 0x80);
+        byte = (rest & 0x7f) as u8;
+        rest >>= 7;
+    }
+    out.push(byte);
+}
+
+/// Distance back to an OFS_DELTA base, in Git's offset encoding
+fn read_ofs_distance(data: &[u8], mut pos: usize) -> Result<(u64, usize)> {
+    let mut byte = *data.get(pos).context("Truncated delta offset")?;
+    let mut distance = (byte & 0x7f) as u64;
+    pos += 1;
+    while byte & 0x80 != 0 {
+        byte = *data.get(pos).context("Truncated delta offset")?;
+        distance = ((distance + 1) << 7) | (byte & 0x7f) as u64;
+        pos += 1;
+    }
+    Ok((distance, pos))
+}
+
+fn encode_ofs_distance(mut distance: u64) -> Vec<u8> {
+    let mut bytes = vec![(distance & 0x7f) as u8];
+    distance >>= 7;
+    while distance > 0 {
+        distance -= 1;
+        bytes.push(0x80 | (distance & 0x7f) as u8);
+        distance >>= 7;
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// Little-endian base-128 size at the start of a delta
+fn read_size(delta: &[u8], pos: &mut usize) -> Result<usize> {
+    let mut size = 0usize;
+    let mut shift = 0;
+    loop {
+        let byte = *delta.get(*pos).context("Truncated delta header")?;
+        *pos += 1;
+        size |= ((byte & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte & 0x80 == 0 {
+            return Ok(size);
+        }
+    }
+}
+
+fn write_size(out: &mut Vec<u8>, mut size: usize) {
+    loop {
+        let byte = (size & 0x7f) as u8;
+        size >>= 7;
+        if size == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Inflate one zlib stream from the start of `data`, which may run on into
+/// the next pack entry
+fn inflate(data: &[u8], size: usize) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(size + 1);
+    let mut stream = Decompress::new(true);
+    loop {
+        if out.len() == out.capacity() {
+            out.reserve(64);
+        }
+        let (consumed, produced) = (stream.total_in(), stream.total_out());
+        let status = stream.decompress_vec(&data[consumed as usize..], &mut out, FlushDecompress::Finish)?;
+        if status == Status::StreamEnd {
+            break;
+        }
+        if stream.total_in() == consumed && stream.total_out() == produced {
+            anyhow::bail!("Truncated compressed data");
+        }
+    }
+
+    if out.len() != size {
+        anyhow::bail!("Inflated to {} bytes, expected {}", out.len(), size);
+    }
+    Ok(out)
+}
+
+fn deflate(data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(data.len() / 2 + 64);
+    let mut stream = Compress::new(Compression::default(), true);
+    loop {
+        if out.len() == out.capacity() {
+            out.reserve(out.capacity());
+        }
+        let consumed = stream.total_in() as usize;
+        if stream.compress_vec(&data[consumed..], &mut out, FlushCompress::Finish)? == Status::StreamEnd {
+            return Ok(out);
+        }
+    }
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([data[at], data[at + 1], data[at + 2], data[at + 3]])
+}
+
+fn parse_name(hash: &str) -> Option<[u8; 20]> {
+    let mut name = [0u8; 20];
+    hex::decode_to_slice(hash, &mut name).ok()?;
+    Some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native_git::testing;
+
+    fn text(lines: usize, changed: usize) -> Vec<u8> {
+        (0..lines)
+            .map(|i| if i == changed { "this line was edited\n".to_string() } else { format!("line number {}\n", i) })
+            .collect::<String>()
+            .into_bytes()
+    }
+
+    fn blob(content: Vec<u8>) -> GitObject {
+        GitObject::new(ObjectType::Blob, content)
+    }
+
+    /// Type codes of every entry in a pack, in the order they were written
+    fn entry_codes(pack: &Pack) -> Vec<u8> {
+        let mut offsets = pack.index.offsets.clone();
+        offsets.sort();
+        offsets.iter().map(|&o| read_entry_header(&pack.data, o as usize).unwrap().0).collect()
+    }
+
+    #[test]
+    fn delta_round_trips() {
+        let base = text(200, usize::MAX);
+        let target = text(210, 57);
+        let delta = create_delta(&base, &target);
+        assert!(delta.len() < target.len() / 4);
+        assert_eq!(apply_delta(&base, &delta).unwrap(), target);
+
+        let unrelated = b"nothing in common with the base at all".to_vec();
+        assert_eq!(apply_delta(&base, &create_delta(&base, &unrelated)).unwrap(), unrelated);
+        assert_eq!(apply_delta(&base, &create_delta(&base, b"")).unwrap(), b"");
+    }
+
+    #[test]
+    fn delta_rejects_wrong_base() {
+        let base = text(50, usize::MAX);
+        let delta = create_delta(&base, &text(50, 3));
+        assert!(apply_delta(&base[1..], &delta).is_err());
+    }
+
+    #[test]
+    fn header_encodings_round_trip() {
+        for distance in [1u64, 127, 128, 16_511, 16_512, 1 << 32] {
+            let encoded = encode_ofs_distance(distance);
+            assert_eq!(read_ofs_distance(&encoded, 0).unwrap(), (distance, encoded.len()));
+        }
+        for size in [0usize, 15, 16, 127, 128, 1 << 20] {
+            let mut entry = Vec::new();
+            write_entry_header(&mut entry, OBJ_OFS_DELTA, size);
+            assert_eq!(read_entry_header(&entry, 0).unwrap(), (OBJ_OFS_DELTA, size, entry.len()));
+
+            let mut encoded = Vec::new();
+            write_size(&mut encoded, size);
+            let mut pos = 0;
+            assert_eq!(read_size(&encoded, &mut pos).unwrap(), size);
+            assert_eq!(pos, encoded.len());
+        }
+    }
+
+    #[test]
+    fn written_pack_reads_back_through_ofs_deltas() {
+        let (_dir, repo) = testing::repo("pack-ofs");
+        let objects: Vec<GitObject> = (0..5)
+            .map(|i| blob(text(300 + i, i * 40)))
+            .chain([blob(b"tiny".to_vec()), GitObject::new(ObjectType::Tree, Vec::new())])
+            .collect();
+
+        let checksum = write_pack(repo.git_dir(), &objects).unwrap();
+        let pack_path = repo.git_dir().join("objects").join("pack").join(format!("pack-{}.pack", checksum));
+        let pack = Pack::open(&pack_path).unwrap();
+
+        assert!(entry_codes(&pack).contains(&OBJ_OFS_DELTA));
+        let mut hashes: Vec<String> = pack.hashes().collect();
+        let mut expected: Vec<String> = objects.iter().map(|o| o.hash.clone()).collect();
+        hashes.sort();
+        expected.sort();
+        assert_eq!(hashes, expected);
+
+        for obj in &objects {
+            let read = read_object(repo.git_dir(), &obj.hash).unwrap().expect("object in pack");
+            assert_eq!(read.obj_type, obj.obj_type);
+            assert_eq!(read.content, obj.content);
+        }
+        assert!(read_object(repo.git_dir(), &"0".repeat(40)).unwrap().is_none());
+    }
+
+    #[test]
+    fn ref_delta_resolves_in_pack_and_from_loose_objects() {
+        let (_dir, repo) = testing::repo("pack-ref");
+        let base = blob(text(100, usize::MAX));
+        let target = blob(text(100, 10));
+        let delta = create_delta(&base.content, &target.content);
+
+        // The base is written whole in one pack and only loose for another
+        for packed_base in [true, false] {
+            let mut pack = Vec::new();
+            pack.extend_from_slice(PACK_SIGNATURE);
+            pack.extend_from_slice(&PACK_VERSION.to_be_bytes());
+            pack.extend_from_slice(&(1 + packed_base as u32).to_be_bytes());
+
+            let mut entries = Vec::new();
+            if packed_base {
+                let offset = pack.len() as u64;
+                write_entry_header(&mut pack, type_code(ObjectType::Blob), base.content.len());
+                pack.extend_from_slice(&deflate(&base.content).unwrap());
+                entries.push(PackedEntry { name: parse_name(&base.hash).unwrap(), offset, crc: 0 });
+            } else {
+                base.store(repo.git_dir()).unwrap();
+            }
+
+            let offset = pack.len() as u64;
+            write_entry_header(&mut pack, OBJ_REF_DELTA, delta.len());
+            pack.extend_from_slice(&parse_name(&base.hash).unwrap());
+            pack.extend_from_slice(&deflate(&delta).unwrap());
+            entries.push(PackedEntry { name: parse_name(&target.hash).unwrap(), offset, crc: 0 });
+
+            let checksum = Sha1::digest(&pack);
+            pack.extend_from_slice(&checksum);
+
+            let scratch = testing::ScratchDir::new("pack-ref-file");
+            let pack_path = scratch.path().join("pack-test.pack");
+            fs::write(&pack_path, &pack).unwrap();
+            fs::write(pack_path.with_extension("idx"), build_index(&entries, &checksum)).unwrap();
+
+            let read = Pack::open(&pack_path).unwrap()
+                .read(repo.git_dir(), &target.hash).unwrap()
+                .expect("delta object in pack");
+            assert_eq!(read.obj_type, ObjectType::Blob);
+            assert_eq!(read.content, target.content);
+        }
+    }
+
+    #[test]
+    fn pack_must_match_its_index() {
+        let (_dir, repo) = testing::repo("pack-mismatch");
+        let checksum = write_pack(repo.git_dir(), &[blob(text(10, 1))]).unwrap();
+        let pack_path = repo.git_dir().join("objects").join("pack").join(format!("pack-{}.pack", checksum));
+
+        let mut data = fs::read(&pack_path).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        fs::write(&pack_path, &data).unwrap();
+        assert!(Pack::open(&pack_path).is_err());
+    }
+}
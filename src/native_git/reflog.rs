@@ -0,0 +1,104 @@
+// TriForge/src/native_git/reflog.rs
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+
+use super::commit::Signature;
+
+/// Old value recorded when a ref is created
+pub const NULL_HASH: &str = "0000000000000000000000000000000000000000";
+
+/// One line of `.git/logs/<ref>`:
+/// `<old> <new> Name <email> 1700000000 +0100\t<message>`
+#[derive(Debug, Clone)]
+pub struct ReflogEntry {
+    pub old: String,
+    pub new: String,
+    pub committer: Signature,
+    pub message: String,
+}
+
+impl ReflogEntry {
+    pub fn new(old: Option<&str>, new: &str, committer: Signature, message: &str) -> Self {
+        Self {
+            old: old.unwrap_or(NULL_HASH).to_string(),
+            new: new.to_string(),
+            committer,
+            // The log is line based; keep only the first line
+            message: message.lines().next().unwrap_or_default().to_string(),
+        }
+    }
+
+    fn parse(line: &str) -> Result<Self> {
+        let (head, message) = line.split_once('\t').unwrap_or((line, ""));
+        let mut parts = head.splitn(3, ' ');
+        let old = parts.next().unwrap_or_default();
+        let new = parts.next().unwrap_or_default();
+        let committer = parts.next().unwrap_or_default();
+
+        if old.len() != 40 || new.len() != 40 {
+            anyhow::bail!("Invalid reflog line: {}", line);
+        }
+
+        Ok(Self {
+            old: old.to_string(),
+            new: new.to_string(),
+            committer: Signature::parse(committer)?,
+            message: message.to_string(),
+        })
+    }
+
+    fn to_line(&self) -> String {
+        format!("{} {} {}\t{}\n", self.old, self.new, self.committer.to_raw(), self.message)
+    }
+}
+
+/// Where the log for a ref lives
+pub fn log_path(git_dir: &Path, ref_name: &str) -> PathBuf {
+    git_dir.join("logs").join(ref_name)
+}
+
+/// Entries for a ref, oldest first. A ref without a log has none.
+pub fn read(git_dir: &Path, ref_name: &str) -> Result<Vec<ReflogEntry>> {
+    let path = log_path(git_dir, ref_name);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    fs::read_to_string(path)?
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(ReflogEntry::parse)
+        .collect()
+}
+
+/// Record one update of a ref
+pub fn append(git_dir: &Path, ref_name: &str, entry: &ReflogEntry) -> Result<()> {
+    let path = log_path(git_dir, ref_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = fs::OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(entry.to_line().as_bytes())?;
+    Ok(())
+}
+
+/// Replace a ref's log, removing the file when no entries are left
+pub fn write(git_dir: &Path, ref_name: &str, entries: &[ReflogEntry]) -> Result<()> {
+    let path = log_path(git_dir, ref_name);
+    if entries.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let content: String = entries.iter().map(|e| e.to_line()).collect();
+    fs::write(path, content)?;
+    Ok(())
+}
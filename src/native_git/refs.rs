@@ -1,60 +1,236 @@
 
+use std::collections::BTreeMap;
+use std::env;
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use anyhow::Result;
 
+use crate::config::AppConfig;
+
+use super::commit::Signature;
+use super::config::GitConfig;
+use super::objects::{GitObject, ObjectType};
+use super::reflog::{self, ReflogEntry};
+use super::tag::TagBuilder;
+
+const PACKED_REFS: &str = "packed-refs";
+const PACKED_REFS_HEADER: &str = "# pack-refs with: peeled fully-peeled sorted \n";
+const LOCK_SUFFIX: &str = ".lock";
+
 pub struct Refs {
     git_dir: PathBuf,
 }
 
+/// What a ref must hold for a transaction to go ahead
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expected {
+    /// Whatever the ref holds
+    Any,
+    /// The ref must not exist yet
+    Missing,
+    /// The ref must hold exactly this hash
+    Value(String),
+}
+
+enum Change {
+    Update(String),
+    Delete,
+}
+
+struct RefUpdate {
+    name: String,
+    change: Change,
+    expected: Expected,
+}
+
+/// A set of ref changes applied all or nothing. Every ref is locked with a
+/// `<ref>.lock` file and checked against its expected value before any of
+/// them is written, so a concurrent writer or a stale expectation fails the
+/// whole transaction.
+pub struct Transaction<'a> {
+    refs: &'a Refs,
+    updates: Vec<RefUpdate>,
+}
+
+impl<'a> Transaction<'a> {
+    /// Point `name` at `hash`
+    pub fn update(mut self, name: &str, hash: &str, expected: Expected) -> Self {
+        self.updates.push(RefUpdate {
+            name: name.to_string(),
+            change: Change::Update(hash.to_string()),
+            expected,
+        });
+        self
+    }
+
+    /// Remove `name`, loose or packed, along with its reflog
+    pub fn delete(mut self, name: &str, expected: Expected) -> Self {
+        self.updates.push(RefUpdate {
+            name: name.to_string(),
+            change: Change::Delete,
+            expected,
+        });
+        self
+    }
+
+    /// Lock every ref, check expectations, then write all changes and log
+    /// them with `reason`
+    pub fn commit(mut self, reason: &str) -> Result<()> {
+        let refs = self.refs;
+        self.updates.sort_by(|a, b| a.name.cmp(&b.name));
+        if self.updates.windows(2).any(|w| w[0].name == w[1].name) {
+            anyhow::bail!("A ref may only be changed once per transaction");
+        }
+        for update in &self.updates {
+            check_writable(&update.name)?;
+        }
+
+        // Lock in name order so two transactions cannot deadlock
+        let mut locks = Vec::with_capacity(self.updates.len());
+        for update in &self.updates {
+            locks.push(LockFile::acquire(&refs.git_dir.join(&update.name))?);
+        }
+
+        let packed = refs.packed()?;
+        let mut old_values = Vec::with_capacity(self.updates.len());
+        for update in &self.updates {
+            let current = refs.resolve(&update.name);
+            check_expected(&update.name, current.as_deref(), &update.expected)?;
+            old_values.push(current);
+        }
+
+        let unpack: Vec<&str> = self.updates.iter()
+            .filter(|u| matches!(u.change, Change::Delete) && packed.contains_key(&u.name))
+            .map(|u| u.name.as_str())
+            .collect();
+        let mut packed_lock = if unpack.is_empty() {
+            None
+        } else {
+            Some(LockFile::acquire(&refs.git_dir.join(PACKED_REFS))?)
+        };
+
+        for (update, lock) in self.updates.iter().zip(locks.iter_mut()) {
+            if let Change::Update(hash) = &update.change {
+                lock.write(format!("{}\n", hash).as_bytes())?;
+            }
+        }
+
+        // Nothing has changed on disk up to here; now apply everything
+        if let Some(mut lock) = packed_lock.take() {
+            let kept: BTreeMap<String, String> = packed.into_iter()
+                .filter(|(name, _)| !unpack.contains(&name.as_str()))
+                .collect();
+            lock.write(refs.format_packed(&kept).as_bytes())?;
+            lock.commit()?;
+        }
+
+        let head_target = refs.read_symbolic("HEAD").ok().flatten();
+        let committer = refs.committer();
+        for ((update, lock), old) in self.updates.iter().zip(locks).zip(old_values) {
+            match &update.change {
+                Change::Update(hash) => {
+                    lock.commit()?;
+
+                    let entry = ReflogEntry::new(old.as_deref(), hash, committer.clone(), reason);
+                    reflog::append(&refs.git_dir, &update.name, &entry)?;
+                    // Moving the branch HEAD is on moves HEAD too
+                    if update.name != "HEAD" && head_target.as_deref() == Some(update.name.as_str()) {
+                        reflog::append(&refs.git_dir, "HEAD", &entry)?;
+                    }
+                }
+                Change::Delete => {
+                    let ref_path = refs.git_dir.join(&update.name);
+                    if ref_path.is_file() {
+                        fs::remove_file(&ref_path)?;
+                    }
+                    drop(lock);
+                    remove_empty_parents(&ref_path, &refs.git_dir.join("refs"));
+
+                    let log_path = reflog::log_path(&refs.git_dir, &update.name);
+                    if log_path.exists() {
+                        fs::remove_file(&log_path)?;
+                    }
+                    remove_empty_parents(&log_path, &refs.git_dir.join("logs").join("refs"));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 impl Refs {
     pub fn new(git_dir: PathBuf) -> Self {
         Self { git_dir }
     }
 
-    /// Update a reference
-    pub fn update(&self, ref_name: &str, hash: &str) -> Result<()> {
-        let ref_path = self.git_dir.join(ref_name);
-        
-        if let Some(parent) = ref_path.parent() {
-            fs::create_dir_all(parent)?;
+    /// Start a transaction over several refs
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            refs: self,
+            updates: Vec::new(),
         }
-        
-        fs::write(ref_path, format!("{}\n", hash))?;
-        Ok(())
     }
 
-    /// Read a reference
+    /// Update a reference, recording the change and its reason in the
+    /// ref's reflog. Updating the branch HEAD is on logs the change for HEAD too.
+    pub fn update(&self, ref_name: &str, hash: &str, reason: &str) -> Result<()> {
+        self.transaction()
+            .update(ref_name, hash, Expected::Any)
+            .commit(reason)
+    }
+
+    /// Read a reference, loose or packed
     pub fn read(&self, ref_name: &str) -> Result<String> {
         let ref_path = self.git_dir.join(ref_name);
-        
-        if !ref_path.exists() {
-            anyhow::bail!("Reference not found: {}", ref_name);
+
+        if ref_path.is_file() {
+            let content = fs::read_to_string(ref_path)?;
+            return Ok(content.trim().to_string());
+        }
+
+        match self.packed()?.remove(ref_name) {
+            Some(hash) => Ok(hash),
+            None => anyhow::bail!("Reference not found: {}", ref_name),
         }
-        
-        let content = fs::read_to_string(ref_path)?;
-        Ok(content.trim().to_string())
     }
 
-    /// Delete a reference
+    /// Delete a reference and its reflog, along with directories they leave
+    /// empty so the name can later be reused as a prefix
     pub fn delete(&self, ref_name: &str) -> Result<()> {
-        let ref_path = self.git_dir.join(ref_name);
-        if ref_path.exists() {
-            fs::remove_file(ref_path)?;
-        }
-        Ok(())
+        self.transaction()
+            .delete(ref_name, Expected::Any)
+            .commit("")
+    }
+
+    /// Move a reference and its reflog to a new name
+    pub fn rename(&self, old: &str, new: &str, reason: &str) -> Result<()> {
+        let hash = self.read(old)?;
+        let mut entries = reflog::read(&self.git_dir, old)?;
+
+        self.transaction()
+            .delete(old, Expected::Value(hash.clone()))
+            .update(new, &hash, Expected::Any)
+            .commit(reason)?;
+
+        entries.push(ReflogEntry::new(Some(&hash), &hash, self.committer(), reason));
+        reflog::write(&self.git_dir, new, &entries)
     }
 
-    /// List all references with a prefix
+    /// List all references with a prefix, loose and packed, sorted by name
     pub fn list(&self, prefix: &str) -> Result<Vec<String>> {
         let prefix_path = self.git_dir.join(prefix);
         let mut refs = Vec::new();
-        
-        if !prefix_path.exists() {
-            return Ok(refs);
+
+        if prefix_path.is_dir() {
+            self.walk_refs(&prefix_path, prefix, &mut refs)?;
         }
-        
-        self.walk_refs(&prefix_path, prefix, &mut refs)?;
+
+        let dir = format!("{}/", prefix.trim_end_matches('/'));
+        refs.extend(self.packed()?.into_keys().filter(|name| name.starts_with(&dir)));
+        refs.sort();
+        refs.dedup();
         Ok(refs)
     }
 
@@ -62,39 +238,395 @@ impl Refs {
         for entry in fs::read_dir(dir)? {
             let entry = entry?;
             let path = entry.path();
-            
+
             if path.is_dir() {
                 let name = entry.file_name();
                 let new_base = format!("{}/{}", base, name.to_string_lossy());
                 self.walk_refs(&path, &new_base, refs)?;
             } else {
                 let name = entry.file_name();
+                // Lock files belong to transactions in progress
+                if name.to_string_lossy().ends_with(LOCK_SUFFIX) {
+                    continue;
+                }
                 refs.push(format!("{}/{}", base, name.to_string_lossy()));
             }
         }
         Ok(())
     }
 
+    /// Refs stored in `.git/packed-refs`, by name
+    pub fn packed(&self) -> Result<BTreeMap<String, String>> {
+        let path = self.git_dir.join(PACKED_REFS);
+        if !path.exists() {
+            return Ok(BTreeMap::new());
+        }
+
+        // Skip the header and the `^<hash>` peeled values of annotated tags
+        Ok(fs::read_to_string(path)?
+            .lines()
+            .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+            .filter_map(|line| line.split_once(' '))
+            .map(|(hash, name)| (name.to_string(), hash.to_string()))
+            .collect())
+    }
+
+    /// Move every loose ref under `refs/` into `.git/packed-refs`, like
+    /// `git pack-refs --all`. Returns how many refs were packed.
+    pub fn pack(&self) -> Result<usize> {
+        let mut packed_lock = LockFile::acquire(&self.git_dir.join(PACKED_REFS))?;
+        let mut packed = self.packed()?;
+
+        let refs_dir = self.git_dir.join("refs");
+        let mut loose = Vec::new();
+        if refs_dir.is_dir() {
+            self.walk_refs(&refs_dir, "refs", &mut loose)?;
+        }
+
+        let mut moved = Vec::new();
+        for name in loose {
+            let value = fs::read_to_string(self.git_dir.join(&name))?.trim().to_string();
+            // Symbolic refs such as refs/remotes/origin/HEAD stay loose
+            if is_hash(&value) {
+                packed.insert(name.clone(), value.clone());
+                moved.push((name, value));
+            }
+        }
+
+        packed_lock.write(self.format_packed(&packed).as_bytes())?;
+        packed_lock.commit()?;
+
+        // Drop the loose copies unless they changed while we were packing
+        for (name, value) in &moved {
+            let ref_path = self.git_dir.join(name);
+            let _lock = LockFile::acquire(&ref_path)?;
+            if fs::read_to_string(&ref_path)?.trim() == value {
+                fs::remove_file(&ref_path)?;
+            }
+            remove_empty_parents(&ref_path, &refs_dir);
+        }
+
+        Ok(moved.len())
+    }
+
+    /// `packed-refs` contents, with the peeled target after each annotated tag
+    fn format_packed(&self, packed: &BTreeMap<String, String>) -> String {
+        let mut content = String::from(PACKED_REFS_HEADER);
+        for (name, hash) in packed {
+            content.push_str(&format!("{} {}\n", hash, name));
+            if let Some(peeled) = self.peel_tag(hash) {
+                content.push_str(&format!("^{}\n", peeled));
+            }
+        }
+        content
+    }
+
+    /// What an annotated tag finally points at, or `None` for anything else
+    fn peel_tag(&self, hash: &str) -> Option<String> {
+        let mut obj = GitObject::load(&self.git_dir, hash).ok()?;
+        if obj.obj_type != ObjectType::Tag {
+            return None;
+        }
+        while obj.obj_type == ObjectType::Tag {
+            let target = TagBuilder::parse(&obj).ok()?.target;
+            obj = GitObject::load(&self.git_dir, &target).ok()?;
+        }
+        Some(obj.hash)
+    }
+
+    /// Hashes held by every ref plus HEAD and in-progress state such as
+    /// ORIG_HEAD and MERGE_HEAD, and every value in their reflogs: the roots
+    /// that keep objects alive
+    pub fn tips(&self) -> Result<Vec<String>> {
+        let mut names = self.list("refs")?;
+        names.extend(["HEAD", "ORIG_HEAD", "MERGE_HEAD"].map(String::from));
+
+        let mut tips = Vec::new();
+        for name in &names {
+            // Symbolic refs are covered by the ref they point at
+            if let Some(value) = self.read(name).ok().filter(|v| is_hash(v)) {
+                tips.push(value);
+            }
+        }
+
+        for name in names {
+            for entry in reflog::read(&self.git_dir, &name)? {
+                tips.extend([entry.old, entry.new].into_iter().filter(|h| h != reflog::NULL_HASH));
+            }
+        }
+        tips.sort();
+        tips.dedup();
+        Ok(tips)
+    }
+
     /// Get HEAD commit hash
     pub fn head(&self) -> Result<String> {
-        // Try HEAD file first
-        let head_path = self.git_dir.join("HEAD");
-        if head_path.exists() {
-            let content = fs::read_to_string(head_path)?;
-            let content = content.trim();
-            
-            // If it's a symbolic ref
-            if content.starts_with("ref: ") {
-                let ref_name = &content[5..];
-                return self.read(ref_name);
-            }
-            
-            // Direct hash
-            return Ok(content.to_string());
+        if self.git_dir.join("HEAD").exists() {
+            return match self.read_symbolic("HEAD")? {
+                Some(target) => self.read(&target),
+                None => self.read("HEAD"),
+            };
         }
-        
+
         // Try refs/heads/main
         self.read("refs/heads/main")
             .or_else(|_| self.read("refs/heads/master"))
     }
+
+    /// Target of a symbolic ref, or `None` if it holds a hash directly
+    pub fn read_symbolic(&self, name: &str) -> Result<Option<String>> {
+        let content = self.read(name)?;
+        Ok(content.strip_prefix("ref: ").map(|t| t.to_string()))
+    }
+
+    /// Make a symbolic ref (normally HEAD) point at another ref. The move is
+    /// logged when both the old and the new target hold a commit.
+    pub fn set_symbolic(&self, name: &str, target: &str, reason: &str) -> Result<()> {
+        check_writable(name)?;
+        check_writable(target)?;
+        let mut lock = LockFile::acquire(&self.git_dir.join(name))?;
+        let old = self.resolve(name);
+        lock.write(format!("ref: {}\n", target).as_bytes())?;
+        lock.commit()?;
+
+        if let (Some(old), Ok(new)) = (old, self.read(target)) {
+            let entry = ReflogEntry::new(Some(&old), &new, self.committer(), reason);
+            reflog::append(&self.git_dir, name, &entry)?;
+        }
+        Ok(())
+    }
+
+    /// Hash a ref ends up at after following symbolic refs
+    fn resolve(&self, name: &str) -> Option<String> {
+        let mut value = self.read(name).ok()?;
+        for _ in 0..5 {
+            match value.strip_prefix("ref: ") {
+                Some(target) => value = self.read(target).ok()?,
+                None => return Some(value),
+            }
+        }
+        None
+    }
+
+    /// Identity recorded in reflog entries, resolved like a commit's
+    /// committer; the login name stands in when none is configured
+    fn committer(&self) -> Signature {
+        let config = GitConfig::load(&self.git_dir).ok();
+        let defaults = AppConfig::load().map(|c| c.identity()).unwrap_or_default();
+
+        Signature::for_role("COMMITTER", None, config.as_ref(), defaults)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| {
+                let name = env::var("USER").unwrap_or_else(|_| "unknown".to_string());
+                Signature::now(&name, "")
+            })
+    }
+
+    /// Short name of the branch HEAD points at, or `None` when detached
+    pub fn current_branch(&self) -> Result<Option<String>> {
+        Ok(self.read_symbolic("HEAD")?.map(|target| {
+            target.strip_prefix("refs/heads/").unwrap_or(&target).to_string()
+        }))
+    }
+
+    /// Point whatever HEAD refers to at a new commit. A symbolic HEAD moves
+    /// its branch; a detached HEAD is rewritten directly.
+    pub fn update_head(&self, hash: &str, reason: &str) -> Result<()> {
+        match self.read_symbolic("HEAD").ok().flatten() {
+            Some(target) => self.update(&target, hash, reason),
+            None => self.update("HEAD", hash, reason),
+        }
+    }
+}
+
+/// An exclusively created `<path>.lock`. Committing renames it over `path`;
+/// dropping it uncommitted removes it and leaves `path` untouched.
+pub(super) struct LockFile {
+    path: PathBuf,
+    lock_path: PathBuf,
+    file: Option<fs::File>,
+}
+
+impl LockFile {
+    pub(super) fn acquire(path: &Path) -> Result<Self> {
+        let mut lock_name = path.as_os_str().to_owned();
+        lock_name.push(LOCK_SUFFIX);
+        let lock_path = PathBuf::from(lock_name);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&lock_path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => anyhow::anyhow!(
+                    "Unable to lock {}: {} exists. If no other Triforge or Git process is running, remove it and try again",
+                    path.display(),
+                    lock_path.display()
+                ),
+                _ => anyhow::anyhow!("Unable to lock {}: {}", path.display(), e),
+            })?;
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            lock_path,
+            file: Some(file),
+        })
+    }
+
+    pub(super) fn write(&mut self, content: &[u8]) -> Result<()> {
+        let file = self.file.as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Lock on {} already released", self.path.display()))?;
+        file.write_all(content)?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    pub(super) fn commit(mut self) -> Result<()> {
+        self.file.take();
+        fs::rename(&self.lock_path, &self.path)?;
+        Ok(())
+    }
+}
+
+impl Drop for LockFile {
+    fn drop(&mut self) {
+        if self.lock_path.exists() {
+            let _ = fs::remove_file(&self.lock_path);
+        }
+    }
+}
+
+fn check_expected(name: &str, current: Option<&str>, expected: &Expected) -> Result<()> {
+    match (expected, current) {
+        (Expected::Any, _) | (Expected::Missing, None) => Ok(()),
+        (Expected::Missing, Some(_)) => anyhow::bail!("Ref '{}' already exists", name),
+        (Expected::Value(want), Some(have)) if want == have => Ok(()),
+        (Expected::Value(want), Some(have)) => anyhow::bail!(
+            "Ref '{}' is at {} but expected {}",
+            name,
+            &have[..have.len().min(8)],
+            &want[..want.len().min(8)]
+        ),
+        (Expected::Value(_), None) => anyhow::bail!("Ref '{}' does not exist", name),
+    }
+}
+
+/// Only HEAD and valid names under `refs/` may be written, so no name can
+/// reach files elsewhere in the git directory
+fn check_writable(name: &str) -> Result<()> {
+    if name == "HEAD" {
+        return Ok(());
+    }
+    if !name.starts_with("refs/") {
+        anyhow::bail!("Refusing to write '{}': not under refs/", name);
+    }
+    validate_name(name)
+}
+
+/// A full 40-character hex object id
+pub fn is_hash(value: &str) -> bool {
+    value.len() == 40 && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Remove directories below `stop` that `path` leaves empty, keeping
+/// top-level ones such as `refs/heads`
+fn remove_empty_parents(path: &Path, stop: &Path) {
+    let mut dir = path.parent();
+    while let Some(d) = dir {
+        if !d.starts_with(stop) || d == stop || d.parent() == Some(stop) || fs::remove_dir(d).is_err() {
+            break;
+        }
+        dir = d.parent();
+    }
+}
+
+/// Reject ref names Git would refuse (`git check-ref-format`)
+pub fn validate_name(name: &str) -> Result<()> {
+    let invalid = name.is_empty()
+        || name.starts_with('-')
+        || name.starts_with('/')
+        || name.ends_with('/')
+        || name.ends_with('.')
+        || name.ends_with(".lock")
+        || name == "@"
+        || name.contains("..")
+        || name.contains("//")
+        || name.contains("@{")
+        || name.split('/').any(|c| c.starts_with('.'))
+        || name.chars().any(|c| c.is_ascii_control() || " ~^:?*[\\".contains(c));
+
+    if invalid {
+        anyhow::bail!("'{}' is not a valid ref name", name);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native_git::testing;
+
+    const ONE: &str = "1111111111111111111111111111111111111111";
+    const TWO: &str = "2222222222222222222222222222222222222222";
+
+    #[test]
+    fn validate_name_accepts_ordinary_names() {
+        for name in ["main", "feature/login", "refs/heads/v1.0", "fix-123_b", "a@b", "release/2024.10"] {
+            assert!(validate_name(name).is_ok(), "{} should be valid", name);
+        }
+    }
+
+    #[test]
+    fn validate_name_rejects_what_git_rejects() {
+        let invalid = [
+            "", "-main", "/main", "main/", "main.", "main.lock", "@", "a..b", "a//b", "a@{1}",
+            ".hidden", "dir/.hidden", "with space", "tilde~1", "caret^", "colon:", "what?",
+            "star*", "bracket[", "back\\slash", "tab\tname", "nul\0",
+        ];
+        for name in invalid {
+            assert!(validate_name(name).is_err(), "{:?} should be invalid", name);
+        }
+    }
+
+    #[test]
+    fn transaction_refuses_names_outside_refs() {
+        let (_dir, repo) = testing::repo("refs-outside");
+        let refs = Refs::new(repo.git_dir().to_path_buf());
+
+        for name in ["config", "refs/heads/../../config", "refs/heads/bad name", "objects/info/x"] {
+            let result = refs.transaction().update(name, ONE, Expected::Any).commit("test");
+            assert!(result.is_err(), "{} should be refused", name);
+        }
+        assert!(fs::read_to_string(repo.git_dir().join("config")).unwrap().contains("[core]"));
+        assert!(refs.set_symbolic("HEAD", "refs/heads/../../config", "test").is_err());
+    }
+
+    #[test]
+    fn transaction_is_all_or_nothing() {
+        let (_dir, repo) = testing::repo("refs-atomic");
+        let refs = Refs::new(repo.git_dir().to_path_buf());
+        refs.transaction().update("refs/heads/a", ONE, Expected::Missing).commit("create").unwrap();
+
+        // The stale expectation on `a` must keep `b` from being created
+        let result = refs.transaction()
+            .update("refs/heads/a", TWO, Expected::Value(TWO.to_string()))
+            .update("refs/heads/b", TWO, Expected::Missing)
+            .commit("move");
+        assert!(result.is_err());
+        assert_eq!(refs.read("refs/heads/a").unwrap(), ONE);
+        assert!(refs.read("refs/heads/b").is_err());
+
+        refs.transaction()
+            .update("refs/heads/a", TWO, Expected::Value(ONE.to_string()))
+            .update("refs/heads/b", ONE, Expected::Missing)
+            .commit("move")
+            .unwrap();
+        assert_eq!(refs.read("refs/heads/a").unwrap(), TWO);
+        assert_eq!(refs.read("refs/heads/b").unwrap(), ONE);
+    }
 }
@@ -0,0 +1,190 @@
+// TriForge/src/native_git/refspec.rs
+use anyhow::Result;
+
+/// A `[+]<src>[:<dst>]` mapping between refs on the two sides of a push or
+/// fetch. Both sides may hold a single `*`, as in
+/// `+refs/heads/*:refs/remotes/origin/*`. A leading `+` allows updates that
+/// are not fast-forwards.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refspec {
+    pub force: bool,
+    pub src: String,
+    pub dst: Option<String>,
+}
+
+impl Refspec {
+    pub fn parse(spec: &str) -> Result<Self> {
+        let (force, rest) = match spec.strip_prefix('+') {
+            Some(rest) => (true, rest),
+            None => (false, spec),
+        };
+        let (src, dst) = match rest.split_once(':') {
+            Some((src, dst)) => (src, Some(dst)),
+            None => (rest, None),
+        };
+
+        if src.is_empty() {
+            anyhow::bail!("Deleting remote refs is not supported: '{}'", spec);
+        }
+        if dst == Some("") {
+            anyhow::bail!("Invalid refspec '{}': empty destination", spec);
+        }
+
+        let src_globs = src.matches('*').count();
+        let dst_globs = dst.map(|d| d.matches('*').count()).unwrap_or(src_globs);
+        if src_globs > 1 || src_globs != dst_globs {
+            anyhow::bail!("Invalid refspec '{}': both sides need the same single '*'", spec);
+        }
+
+        Ok(Self {
+            force,
+            src: src.to_string(),
+            dst: dst.map(|d| d.to_string()),
+        })
+    }
+
+    /// `+refs/heads/*:refs/remotes/<remote>/*`, what a plain fetch uses
+    pub fn default_fetch(remote: &str) -> Self {
+        Self {
+            force: true,
+            src: "refs/heads/*".to_string(),
+            dst: Some(format!("refs/remotes/{}/*", remote)),
+        }
+    }
+
+    pub fn is_glob(&self) -> bool {
+        self.src.contains('*')
+    }
+
+    /// Where `name` goes when it matches the source side; without a
+    /// destination a ref maps onto itself
+    pub fn map(&self, name: &str) -> Option<String> {
+        let dst = self.dst.as_deref().unwrap_or(&self.src);
+        match self.src.split_once('*') {
+            Some((prefix, suffix)) => {
+                let middle = name.strip_prefix(prefix)?.strip_suffix(suffix)?;
+                if middle.is_empty() {
+                    return None;
+                }
+                Some(dst.replacen('*', middle, 1))
+            }
+            None => (name == self.src).then(|| dst.to_string()),
+        }
+    }
+}
+
+/// The full name a short ref name stands for among the refs `exists`
+/// accepts, tried in Git's order
+pub fn expand(name: &str, exists: impl Fn(&str) -> bool) -> Option<String> {
+    [
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+    ]
+    .into_iter()
+    .find(|candidate| candidate.starts_with("refs/") && exists(candidate))
+}
+
+/// Qualify a short destination the way the source is qualified: tags stay
+/// tags, anything else becomes a branch
+pub fn qualify_dst(dst: &str, src: &str) -> String {
+    if dst.starts_with("refs/") {
+        dst.to_string()
+    } else if src.starts_with("refs/tags/") {
+        format!("refs/tags/{}", dst)
+    } else {
+        format!("refs/heads/{}", dst)
+    }
+}
+
+/// The remote-tracking ref for a branch on `remote`
+pub fn tracking_ref(remote: &str, remote_ref: &str) -> Option<String> {
+    Refspec::default_fetch(remote).map(remote_ref)
+}
+
+/// `main` for `refs/heads/main`, `origin/main` for
+/// `refs/remotes/origin/main`, `v1.0` for `refs/tags/v1.0`
+pub fn shorten(name: &str) -> &str {
+    ["refs/heads/", "refs/tags/", "refs/remotes/"]
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_force_and_both_sides() {
+        let spec = Refspec::parse("+refs/heads/*:refs/remotes/origin/*").unwrap();
+        assert_eq!(spec, Refspec::default_fetch("origin"));
+        assert!(spec.is_glob());
+
+        let spec = Refspec::parse("main").unwrap();
+        assert_eq!(spec, Refspec { force: false, src: "main".to_string(), dst: None });
+        assert!(!spec.is_glob());
+
+        let spec = Refspec::parse("refs/heads/a:refs/heads/b").unwrap();
+        assert_eq!(spec.dst.as_deref(), Some("refs/heads/b"));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_specs() {
+        for spec in [":refs/heads/gone", "+", "main:", "refs/heads/*:refs/heads/x", "a:b/*", "refs/*/*:refs/*/*"] {
+            assert!(Refspec::parse(spec).is_err(), "{} should be rejected", spec);
+        }
+    }
+
+    #[test]
+    fn map_substitutes_the_glob() {
+        let spec = Refspec::default_fetch("origin");
+        assert_eq!(spec.map("refs/heads/main").as_deref(), Some("refs/remotes/origin/main"));
+        assert_eq!(spec.map("refs/heads/feature/x").as_deref(), Some("refs/remotes/origin/feature/x"));
+        assert_eq!(spec.map("refs/heads/"), None);
+        assert_eq!(spec.map("refs/tags/v1"), None);
+
+        let spec = Refspec::parse("refs/heads/*-wip:refs/wip/*").unwrap();
+        assert_eq!(spec.map("refs/heads/login-wip").as_deref(), Some("refs/wip/login"));
+        assert_eq!(spec.map("refs/heads/login"), None);
+    }
+
+    #[test]
+    fn map_without_glob_matches_exactly() {
+        let spec = Refspec::parse("refs/heads/main:refs/heads/release").unwrap();
+        assert_eq!(spec.map("refs/heads/main").as_deref(), Some("refs/heads/release"));
+        assert_eq!(spec.map("refs/heads/mainline"), None);
+
+        let spec = Refspec::parse("refs/tags/v1").unwrap();
+        assert_eq!(spec.map("refs/tags/v1").as_deref(), Some("refs/tags/v1"));
+    }
+
+    #[test]
+    fn qualify_dst_follows_the_source() {
+        assert_eq!(qualify_dst("release", "refs/heads/main"), "refs/heads/release");
+        assert_eq!(qualify_dst("v2", "refs/tags/v1"), "refs/tags/v2");
+        assert_eq!(qualify_dst("refs/notes/x", "refs/heads/main"), "refs/notes/x");
+    }
+
+    #[test]
+    fn expand_tries_tags_before_branches() {
+        let existing = ["refs/heads/dev", "refs/heads/v1", "refs/tags/v1", "refs/remotes/origin/dev"];
+        let exists = |name: &str| existing.contains(&name);
+
+        assert_eq!(expand("dev", exists).as_deref(), Some("refs/heads/dev"));
+        assert_eq!(expand("v1", exists).as_deref(), Some("refs/tags/v1"));
+        assert_eq!(expand("remotes/origin/dev", exists).as_deref(), Some("refs/remotes/origin/dev"));
+        assert_eq!(expand("refs/heads/dev", exists).as_deref(), Some("refs/heads/dev"));
+        assert_eq!(expand("missing", exists), None);
+    }
+
+    #[test]
+    fn shorten_strips_known_prefixes() {
+        assert_eq!(shorten("refs/heads/main"), "main");
+        assert_eq!(shorten("refs/tags/v1.0"), "v1.0");
+        assert_eq!(shorten("refs/remotes/origin/main"), "origin/main");
+        assert_eq!(shorten("refs/notes/commits"), "refs/notes/commits");
+        assert_eq!(tracking_ref("up", "refs/heads/main").as_deref(), Some("refs/remotes/up/main"));
+    }
+}
@@ -1,13 +1,18 @@
 // TriForge/src/native_git/repository.rs
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 use anyhow::Result;
 use walkdir::WalkDir;
 
 use super::objects::{GitObject, ObjectType};
 use super::refs::Refs;
-use super::tree::TreeBuilder;
 use super::commit::CommitBuilder;
+use super::index::{Index, IndexEntry};
+use super::ignore::IgnoreRules;
+use super::config::GitConfig;
+use super::revparse;
+use super::tree::{TreeEntry, SYMLINK_MODE};
 
 pub struct Repository {
     work_dir: PathBuf,
@@ -114,8 +119,306 @@ impl Repository {
         Ok(total)
     }
 
+    /// Convert a path to the `/`-separated form used in trees and the index.
+    /// `.` and `..` are resolved lexically; the work tree itself gives `None`
+    /// and a path outside it is an error.
+    pub fn relative_path(&self, path: &Path) -> Result<Option<String>> {
+        let root = normalize(&std::path::absolute(&self.work_dir)?);
+        let full = normalize(&root.join(path));
+        let rel = full.strip_prefix(&root)
+            .map_err(|_| anyhow::anyhow!("'{}' is outside the repository", path.display()))?;
+
+        let parts: Vec<String> = rel.components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+
+        if parts.is_empty() {
+            Ok(None)
+        } else {
+            Ok(Some(parts.join("/")))
+        }
+    }
+
+    /// Load the staging area
+    pub fn index(&self) -> Result<Index> {
+        Index::load(&self.git_dir)
+    }
+
+    /// Load the repository's `.git/config`
+    pub fn config(&self) -> Result<GitConfig> {
+        GitConfig::load(&self.git_dir)
+    }
+
+    /// Load `.gitignore`/`.triforgeignore` rules for the working tree
+    pub fn ignore_rules(&self) -> Result<IgnoreRules> {
+        IgnoreRules::load(&self.work_dir, &self.git_dir)
+    }
+
+    /// Hash a working tree file into the object store and record it in the index
+    pub fn stage_file(&self, index: &mut Index, rel_path: &str) -> Result<()> {
+        let path = self.work_dir.join(rel_path);
+        let meta = fs::symlink_metadata(&path)?;
+
+        // Skip re-hashing files whose stat data is unchanged since they were staged
+        if let Some(existing) = index.get(rel_path) {
+            if existing.matches_metadata(&meta) {
+                return Ok(());
+            }
+        }
+
+        let content = self.read_work_file(rel_path)?;
+        let blob = self.create_blob(&content)?;
+        let hash = self.store_object(&blob)?;
+
+        // A path is a file or a directory in the index, never both
+        let mut dir = rel_path;
+        while let Some((parent, _)) = dir.rsplit_once('/') {
+            index.remove(parent);
+            dir = parent;
+        }
+        index.remove_dir(rel_path);
+
+        index.add(IndexEntry::from_metadata(rel_path.to_string(), hash, &meta));
+        Ok(())
+    }
+
+    /// Read a working tree file as blob content; symlinks yield their target
+    pub fn read_work_file(&self, rel_path: &str) -> Result<Vec<u8>> {
+        let path = self.work_dir.join(rel_path);
+        if fs::symlink_metadata(&path)?.file_type().is_symlink() {
+            let target = fs::read_link(&path)?;
+            return Ok(target.to_string_lossy().into_owned().into_bytes());
+        }
+        Ok(fs::read(&path)?)
+    }
+
+    /// Write a blob to the working tree, restoring its executable bit or
+    /// recreating it as a symlink according to the tree entry mode
+    pub fn checkout_file(&self, rel_path: &str, mode: &str, hash: &str) -> Result<()> {
+        let blob = self.load_object(hash)?;
+        self.write_work_file(rel_path, mode, &blob.content)
+    }
+
+    /// Write raw content to the working tree with the given tree entry mode
+    pub fn write_work_file(&self, rel_path: &str, mode: &str, content: &[u8]) -> Result<()> {
+        let path = self.work_dir.join(rel_path);
+
+        // A file where a directory is needed gives way to it
+        let mut dir = self.work_dir.clone();
+        if let Some((parents, _)) = rel_path.rsplit_once('/') {
+            for component in parents.split('/') {
+                dir.push(component);
+                if fs::symlink_metadata(&dir).is_ok_and(|meta| !meta.is_dir()) {
+                    fs::remove_file(&dir)?;
+                }
+            }
+        }
+        fs::create_dir_all(&dir)?;
+
+        // And an empty directory where a file is needed gives way to the file
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.is_dir() => fs::remove_dir(&path)
+                .map_err(|e| anyhow::anyhow!("Cannot replace directory {} with a file: {}", rel_path, e))?,
+            Ok(_) => fs::remove_file(&path)?,
+            Err(_) => {}
+        }
+
+        if mode == SYMLINK_MODE {
+            write_symlink(content, &path)?;
+            return Ok(());
+        }
+
+        fs::write(&path, content)?;
+        set_executable(&path, mode == "100755")?;
+        Ok(())
+    }
+
+    /// Move the working tree and index from one flattened tree to another,
+    /// rewriting only the paths that differ. Deletions go first so a file
+    /// can turn into a directory of the same name and back.
+    pub fn switch_tree(
+        &self,
+        index: &mut Index,
+        from: &BTreeMap<String, TreeEntry>,
+        to: &BTreeMap<String, TreeEntry>,
+    ) -> Result<()> {
+        for path in from.keys() {
+            if !to.contains_key(path) {
+                self.remove_work_file(path)?;
+                index.remove(path);
+            }
+        }
+
+        for (path, entry) in to {
+            let unchanged = from.get(path)
+                .map(|old| old.hash == entry.hash && old.mode == entry.mode)
+                .unwrap_or(false);
+            // Staged or unstaged changes to such paths are carried over
+            if unchanged {
+                continue;
+            }
+
+            self.checkout_file(path, &entry.mode, &entry.hash)?;
+            let meta = fs::symlink_metadata(self.work_dir.join(path))?;
+            index.add(IndexEntry::from_metadata(path.clone(), entry.hash.clone(), &meta));
+        }
+
+        Ok(())
+    }
+
+    /// Delete a working tree file and any directories it leaves empty
+    pub fn remove_work_file(&self, rel_path: &str) -> Result<()> {
+        let path = self.work_dir.join(rel_path);
+        if fs::symlink_metadata(&path).is_ok() {
+            fs::remove_file(&path)?;
+        }
+
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            if d == self.work_dir || fs::remove_dir(d).is_err() {
+                break;
+            }
+            dir = d.parent();
+        }
+        Ok(())
+    }
+
     /// Get HEAD commit
     pub fn head_commit(&self) -> Result<String> {
         self.refs.head()
     }
+
+    /// Resolve a revision expression such as `main~2`, `v1.0^{tree}` or
+    /// `HEAD:src/main.rs` to an object hash
+    pub fn resolve(&self, name: &str) -> Result<String> {
+        revparse::rev_parse(self, name)
+    }
+
+    /// Resolve a revision and peel it to a commit
+    pub fn resolve_commit(&self, name: &str) -> Result<String> {
+        let hash = self.resolve(name)?;
+        revparse::peel(self, &hash, ObjectType::Commit)
+    }
+
+    /// Peel a commit hash to its root tree; tree hashes are returned as-is
+    pub fn tree_of(&self, hash: &str) -> Result<String> {
+        let obj = self.load_object(&revparse::peel_tags(self, hash)?)?;
+        match obj.obj_type {
+            ObjectType::Commit => Ok(CommitBuilder::parse(&obj)?.tree),
+            ObjectType::Tree => Ok(obj.hash),
+            other => anyhow::bail!("{} is a {}, not a commit or tree", hash, other.as_str()),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn write_symlink(target: &[u8], path: &Path) -> Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+    std::os::unix::fs::symlink(std::ffi::OsStr::from_bytes(target), path)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn write_symlink(target: &[u8], path: &Path) -> Result<()> {
+    // Without symlink support, store the link target as a plain file like Git does
+    fs::write(path, target)?;
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_executable(path: &Path, executable: bool) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    let mut perms = fs::metadata(path)?.permissions();
+    let mode = perms.mode();
+    let mode = if executable {
+        // Grant execute wherever read is allowed, as Git does
+        mode | ((mode & 0o444) >> 2)
+    } else {
+        mode & !0o111
+    };
+    perms.set_mode(mode);
+    fs::set_permissions(path, perms)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &Path, _executable: bool) -> Result<()> {
+    Ok(())
+}
+
+/// Resolve `.` and `..` components without touching the filesystem
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native_git::testing;
+
+    fn blob_entry(repo: &Repository, path: &str, content: &[u8]) -> (String, TreeEntry) {
+        let hash = repo.store_object(&GitObject::new(ObjectType::Blob, content.to_vec())).unwrap();
+        (path.to_string(), TreeEntry { mode: "100644".to_string(), name: path.to_string(), hash })
+    }
+
+    #[test]
+    fn checkout_keeps_a_staged_deletion() {
+        let (dir, repo) = testing::repo("switch-staged-delete");
+        let a = blob_entry(&repo, "a", b"same on both branches\n");
+        let from: BTreeMap<String, TreeEntry> = [a.clone()].into_iter().collect();
+        let to: BTreeMap<String, TreeEntry> = [a, blob_entry(&repo, "b", b"only on the target\n")]
+            .into_iter()
+            .collect();
+
+        // `rm a; triforge add a` before switching
+        let mut index = repo.index().unwrap();
+        repo.switch_tree(&mut index, &from, &to).unwrap();
+        assert!(index.get("a").is_none());
+        assert!(!dir.path().join("a").exists());
+        assert!(index.get("b").is_some());
+        assert_eq!(fs::read(dir.path().join("b")).unwrap(), b"only on the target\n");
+    }
+
+    #[test]
+    fn checkout_rewrites_paths_that_differ() {
+        let (dir, repo) = testing::repo("switch-differ");
+        let from: BTreeMap<String, TreeEntry> = [blob_entry(&repo, "a", b"old\n")].into_iter().collect();
+        let to: BTreeMap<String, TreeEntry> = [blob_entry(&repo, "a/b", b"new\n")].into_iter().collect();
+
+        let mut index = repo.index().unwrap();
+        repo.switch_tree(&mut index, &BTreeMap::new(), &from).unwrap();
+        repo.switch_tree(&mut index, &from, &to).unwrap();
+        assert!(index.get("a").is_none());
+        assert_eq!(index.get("a/b").map(|e| e.hash.clone()), Some(to["a/b"].hash.clone()));
+        assert_eq!(fs::read(dir.path().join("a/b")).unwrap(), b"new\n");
+    }
+
+    #[test]
+    fn relative_path_resolves_dot_components() {
+        let (dir, repo) = testing::repo("relative-path");
+        let rel = |p: &str| repo.relative_path(Path::new(p)).unwrap();
+
+        assert_eq!(rel("sub/../a").as_deref(), Some("a"));
+        assert_eq!(rel("./sub/./b").as_deref(), Some("sub/b"));
+        assert_eq!(rel("sub/.."), None);
+        assert_eq!(repo.relative_path(&dir.path().join("sub/a")).unwrap().as_deref(), Some("sub/a"));
+    }
+
+    #[test]
+    fn relative_path_rejects_paths_outside_the_work_tree() {
+        let (dir, repo) = testing::repo("relative-outside");
+        assert!(repo.relative_path(Path::new("../outside")).is_err());
+        assert!(repo.relative_path(Path::new("sub/../../outside")).is_err());
+        assert!(repo.relative_path(&dir.path().join("..").join("outside")).is_err());
+    }
 }
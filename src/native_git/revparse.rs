@@ -0,0 +1,474 @@
+// TriForge/src/native_git/revparse.rs
+use anyhow::Result;
+
+use super::commit::CommitBuilder;
+use super::config::GitConfig;
+use super::objects::ObjectType;
+use super::reflog;
+use super::repository::Repository;
+use super::tree::TreeBuilder;
+
+/// Shortest hash prefix accepted as an object name
+const MIN_PREFIX_LEN: usize = 4;
+
+/// Resolve a Git revision expression to an object hash.
+///
+/// Supports ref names, full and abbreviated hashes, `@`, `<rev>~<n>`,
+/// `<rev>^<n>`, `<rev>^{<type>}`, `<rev>^{}`, `<branch>@{upstream}`,
+/// `<ref>@{n}`, `<rev>:<path>` and `:<path>` (the staged version of a file).
+pub fn rev_parse(repo: &Repository, spec: &str) -> Result<String> {
+    if let Some((rev, path)) = split_path(spec) {
+        if rev.is_empty() {
+            return staged_blob(repo, path);
+        }
+        let tree = peel(repo, &rev_parse(repo, rev)?, ObjectType::Tree)?;
+        return lookup_path(repo, &tree, path, spec);
+    }
+
+    let (base, mut ops) = split_base(spec);
+    let mut hash = resolve_base(repo, base)?;
+
+    while let Some(op) = ops.chars().next() {
+        if op != '~' && op != '^' {
+            anyhow::bail!("Invalid revision: {}", spec);
+        }
+        ops = &ops[1..];
+
+        if op == '^' && ops.starts_with('{') {
+            let end = ops.find('}')
+                .ok_or_else(|| anyhow::anyhow!("Invalid revision: {}", spec))?;
+            let kind = &ops[1..end];
+            ops = &ops[end + 1..];
+
+            hash = if kind.is_empty() {
+                peel_tags(repo, &hash)?
+            } else {
+                let target = ObjectType::from_str(kind)
+                    .ok_or_else(|| anyhow::anyhow!("Unknown object type '{}' in {}", kind, spec))?;
+                peel(repo, &hash, target)?
+            };
+            continue;
+        }
+
+        let digits = ops.chars().take_while(|c| c.is_ascii_digit()).count();
+        let n: usize = if digits == 0 { 1 } else { ops[..digits].parse()? };
+        ops = &ops[digits..];
+
+        hash = peel(repo, &hash, ObjectType::Commit)?;
+        match op {
+            // n-th generation ancestor, following first parents
+            '~' => {
+                for _ in 0..n {
+                    hash = parents(repo, &hash)?.into_iter().next()
+                        .ok_or_else(|| anyhow::anyhow!("{} has no parent in {}", &hash[..8], spec))?;
+                }
+            }
+            // n-th parent; `^0` is the commit itself
+            '^' if n > 0 => {
+                hash = parents(repo, &hash)?.into_iter().nth(n - 1)
+                    .ok_or_else(|| anyhow::anyhow!("{} has no parent {} in {}", &hash[..8], n, spec))?;
+            }
+            '^' => {}
+            _ => anyhow::bail!("Invalid revision: {}", spec),
+        }
+    }
+
+    Ok(hash)
+}
+
+/// Follow tags (and commits to their trees) until reaching an object of
+/// the wanted type
+pub fn peel(repo: &Repository, hash: &str, target: ObjectType) -> Result<String> {
+    let mut hash = hash.to_string();
+    loop {
+        let obj = repo.load_object(&hash)?;
+        if obj.obj_type == target {
+            return Ok(hash);
+        }
+        hash = match (obj.obj_type, target) {
+            (ObjectType::Tag, _) => tag_target(&obj.content)?,
+            (ObjectType::Commit, ObjectType::Tree) => CommitBuilder::parse(&obj)?.tree,
+            (found, _) => anyhow::bail!("{} is a {}, not a {}", &hash[..8], found.as_str(), target.as_str()),
+        };
+    }
+}
+
+/// Follow tags until reaching a non-tag object
+pub fn peel_tags(repo: &Repository, hash: &str) -> Result<String> {
+    let mut hash = hash.to_string();
+    loop {
+        let obj = repo.load_object(&hash)?;
+        if obj.obj_type != ObjectType::Tag {
+            return Ok(hash);
+        }
+        hash = tag_target(&obj.content)?;
+    }
+}
+
+/// The `object` line of an annotated tag
+pub fn tag_target(content: &[u8]) -> Result<String> {
+    String::from_utf8_lossy(content)
+        .lines()
+        .find_map(|l| l.strip_prefix("object ").map(|h| h.to_string()))
+        .ok_or_else(|| anyhow::anyhow!("Tag object has no target"))
+}
+
+fn parents(repo: &Repository, hash: &str) -> Result<Vec<String>> {
+    Ok(CommitBuilder::parse(&repo.load_object(hash)?)?.parents)
+}
+
+/// Split `rev:path` at the first colon outside `@{...}`
+fn split_path(spec: &str) -> Option<(&str, &str)> {
+    let mut depth = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ':' if depth == 0 => return Some((&spec[..i], &spec[i + 1..])),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split the name part from trailing `~`/`^` operators
+fn split_base(spec: &str) -> (&str, &str) {
+    let mut depth = 0;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '~' | '^' if depth == 0 => return (&spec[..i], &spec[i..]),
+            _ => {}
+        }
+    }
+    (spec, "")
+}
+
+fn resolve_base(repo: &Repository, base: &str) -> Result<String> {
+    if let Some(open) = base.find("@{") {
+        let name = &base[..open];
+        let selector = base[open + 2..].strip_suffix('}')
+            .ok_or_else(|| anyhow::anyhow!("Invalid revision: {}", base))?;
+
+        if let Ok(n) = selector.parse::<usize>() {
+            return reflog_entry(repo, name, n);
+        }
+        return match selector {
+            "upstream" | "u" => resolve_ref(repo, &upstream_ref(repo, name)?)
+                .ok_or_else(|| anyhow::anyhow!("Upstream of '{}' has not been fetched", branch_or_head(repo, name))),
+            _ => anyhow::bail!("Unsupported revision selector: @{{{}}}", selector),
+        };
+    }
+
+    let name = if base.is_empty() || base == "@" { "HEAD" } else { base };
+
+    if let Some(hash) = resolve_ref(repo, name) {
+        return Ok(hash);
+    }
+
+    if name.len() >= MIN_PREFIX_LEN && name.len() <= 40 && name.chars().all(|c| c.is_ascii_hexdigit()) {
+        return resolve_prefix(repo, &name.to_lowercase());
+    }
+
+    anyhow::bail!("Unknown revision: {}", name)
+}
+
+/// Look a name up the way Git does: as given, then under refs/, refs/tags/,
+/// refs/heads/, refs/remotes/ and finally as a remote's HEAD
+fn resolve_ref(repo: &Repository, name: &str) -> Option<String> {
+    if name == "HEAD" {
+        return repo.head_commit().ok();
+    }
+
+    let mut value = repo.refs().read(&full_ref_name(repo, name)?).ok()?;
+
+    // Follow symbolic refs such as refs/remotes/origin/HEAD
+    for _ in 0..5 {
+        match value.strip_prefix("ref: ") {
+            Some(target) => value = repo.refs().read(target).ok()?,
+            None => return Some(value),
+        }
+    }
+    None
+}
+
+/// The full name of the ref a short name such as `main`, `v1.0` or `stash`
+/// refers to, found the way `resolve_ref` looks it up
+pub fn full_ref_name(repo: &Repository, name: &str) -> Option<String> {
+    if name == "HEAD" {
+        return Some(name.to_string());
+    }
+
+    let candidates = [
+        name.to_string(),
+        format!("refs/{}", name),
+        format!("refs/tags/{}", name),
+        format!("refs/heads/{}", name),
+        format!("refs/remotes/{}", name),
+        format!("refs/remotes/{}/HEAD", name),
+    ];
+
+    // Outside refs/ only special names such as ORIG_HEAD are looked up
+    let special = name.chars().all(|c| c.is_ascii_uppercase() || c == '_');
+
+    candidates.into_iter()
+        .filter(|c| c.starts_with("refs/") || (special && c == name))
+        .find(|c| repo.refs().read(c).is_ok())
+}
+
+/// `<ref>@{n}`: the value a ref had `n` updates ago, from its reflog. A bare
+/// `@{n}` reads the current branch's log.
+fn reflog_entry(repo: &Repository, name: &str, n: usize) -> Result<String> {
+    let ref_name = match name {
+        "" => match repo.refs().current_branch()? {
+            Some(branch) => format!("refs/heads/{}", branch),
+            None => "HEAD".to_string(),
+        },
+        "@" => "HEAD".to_string(),
+        _ => full_ref_name(repo, name)
+            .ok_or_else(|| anyhow::anyhow!("Unknown revision: {}", name))?,
+    };
+
+    let entries = reflog::read(repo.git_dir(), &ref_name)?;
+    if entries.is_empty() {
+        anyhow::bail!("No reflog for '{}'", ref_name);
+    }
+    entries.iter()
+        .rev()
+        .nth(n)
+        .map(|e| e.new.clone())
+        .ok_or_else(|| anyhow::anyhow!("Log for '{}' only has {} entries", ref_name, entries.len()))
+}
+
+/// Expand an abbreviated hash, refusing prefixes that match several objects
+fn resolve_prefix(repo: &Repository, prefix: &str) -> Result<String> {
+    if prefix.len() == 40 {
+        repo.load_object(prefix)?;
+        return Ok(prefix.to_string());
+    }
+
+    let mut matches: Vec<String> = repo.list_objects()?
+        .into_iter()
+        .filter(|h| h.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+
+    match matches.len() {
+        0 => anyhow::bail!("Unknown revision: {}", prefix),
+        1 => Ok(matches.remove(0)),
+        _ => {
+            let candidates: Vec<String> = matches.iter()
+                .map(|h| {
+                    let kind = repo.load_object(h).map(|o| o.obj_type.as_str()).unwrap_or("unknown");
+                    format!("  {} {}", &h[..(prefix.len() + 4).min(h.len())], kind)
+                })
+                .collect();
+            anyhow::bail!("Short hash '{}' is ambiguous; candidates are:\n{}", prefix, candidates.join("\n"))
+        }
+    }
+}
+
+/// Remote-tracking ref a branch is configured to follow
+fn upstream_ref(repo: &Repository, name: &str) -> Result<String> {
+    let branch = branch_or_head(repo, name);
+    if branch == "HEAD" {
+        anyhow::bail!("HEAD is detached and has no upstream");
+    }
+
+    upstream_of(&repo.config()?, &branch)
+        .ok_or_else(|| anyhow::anyhow!("No upstream configured for branch '{}'", branch))
+}
+
+/// The ref a branch tracks according to `branch.<name>.remote` and
+/// `branch.<name>.merge`: a remote-tracking ref, or a local branch when the
+/// remote is `.`
+pub fn upstream_of(config: &GitConfig, branch: &str) -> Option<String> {
+    let remote = config.get(&format!("branch.{}.remote", branch))?;
+    let merge = config.get(&format!("branch.{}.merge", branch))?;
+
+    if remote == "." {
+        return Some(merge.to_string());
+    }
+    let short = merge.strip_prefix("refs/heads/").unwrap_or(merge);
+    Some(format!("refs/remotes/{}/{}", remote, short))
+}
+
+/// `name`, or the current branch when `name` is empty
+fn branch_or_head(repo: &Repository, name: &str) -> String {
+    if !name.is_empty() && name != "@" {
+        return name.strip_prefix("refs/heads/").unwrap_or(name).to_string();
+    }
+    repo.refs().current_branch().ok().flatten().unwrap_or_else(|| "HEAD".to_string())
+}
+
+/// `:path` — the blob staged for a path
+fn staged_blob(repo: &Repository, path: &str) -> Result<String> {
+    let index = repo.index()?;
+    index.get(path)
+        .map(|e| e.hash.clone())
+        .ok_or_else(|| anyhow::anyhow!("Path '{}' is not in the index", path))
+}
+
+/// Walk `path` down from a tree
+fn lookup_path(repo: &Repository, tree: &str, path: &str, spec: &str) -> Result<String> {
+    let mut hash = tree.to_string();
+    for part in path.split('/').filter(|p| !p.is_empty()) {
+        let entries = TreeBuilder::parse(&repo.load_object(&hash)?)?;
+        hash = entries.into_iter()
+            .find(|e| e.name == part)
+            .map(|e| e.hash)
+            .ok_or_else(|| anyhow::anyhow!("Path '{}' does not exist in '{}'", path, spec))?;
+    }
+    Ok(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::*;
+    use crate::native_git::commit::Signature;
+    use crate::native_git::objects::GitObject;
+    use crate::native_git::tag::TagBuilder;
+    use crate::native_git::{testing, IndexEntry};
+
+    /// History used by every test:
+    ///
+    /// ```text
+    /// c1 - c2 - c3 - merge   (main)
+    ///   \          /
+    ///    `- side -'          (side)
+    /// ```
+    struct History {
+        c1: String,
+        c2: String,
+        c3: String,
+        side: String,
+        merge: String,
+        readme: String,
+    }
+
+    fn commit(repo: &Repository, tree: &str, parents: &[&String], message: &str) -> String {
+        let mut builder = CommitBuilder::new(tree.to_string(), message.to_string())
+            .author(Signature::now("Test", "test@example.com"))
+            .committer(Signature::now("Test", "test@example.com"));
+        for parent in parents {
+            builder = builder.parent(parent.to_string());
+        }
+        repo.store_object(&builder.build().unwrap()).unwrap()
+    }
+
+    fn history(repo: &Repository) -> History {
+        let readme = repo.store_object(&GitObject::new(ObjectType::Blob, b"hello\n".to_vec())).unwrap();
+        let tree = TreeBuilder::write_paths(repo.git_dir(), vec![
+            ("docs/README".to_string(), "100644".to_string(), readme.clone()),
+        ]).unwrap();
+
+        let c1 = commit(repo, &tree, &[], "one");
+        repo.refs().update_head(&c1, "commit (initial): one").unwrap();
+        let c2 = commit(repo, &tree, &[&c1], "two");
+        repo.refs().update_head(&c2, "commit: two").unwrap();
+        let c3 = commit(repo, &tree, &[&c2], "three");
+        repo.refs().update_head(&c3, "commit: three").unwrap();
+        let side = commit(repo, &tree, &[&c1], "side");
+        repo.refs().update("refs/heads/side", &side, "branch: side").unwrap();
+        let merge = commit(repo, &tree, &[&c3, &side], "merge");
+        repo.refs().update_head(&merge, "commit (merge): merge").unwrap();
+
+        let tag = TagBuilder::new(c2.clone(), ObjectType::Commit, "v1".to_string(), "first\n".to_string())
+            .tagger(Signature::now("Test", "test@example.com"))
+            .build()
+            .unwrap();
+        let tag = repo.store_object(&tag).unwrap();
+        repo.refs().update("refs/tags/v1", &tag, "tag: v1").unwrap();
+
+        History { c1, c2, c3, side, merge, readme }
+    }
+
+    #[test]
+    fn names_and_hashes_resolve() {
+        let (_dir, repo) = testing::repo("revparse-names");
+        let h = history(&repo);
+
+        for spec in ["HEAD", "@", "main", "refs/heads/main", "heads/main", &h.merge, &h.merge[..7]] {
+            assert_eq!(rev_parse(&repo, spec).unwrap(), h.merge, "{}", spec);
+        }
+        assert_eq!(rev_parse(&repo, &h.side[..10].to_uppercase()).unwrap(), h.side);
+        assert!(rev_parse(&repo, "nope").is_err());
+        assert!(rev_parse(&repo, &h.merge[..3]).is_err());
+    }
+
+    #[test]
+    fn ancestry_operators_follow_parents() {
+        let (_dir, repo) = testing::repo("revparse-ancestry");
+        let h = history(&repo);
+
+        assert_eq!(rev_parse(&repo, "HEAD~").unwrap(), h.c3);
+        assert_eq!(rev_parse(&repo, "HEAD~2").unwrap(), h.c2);
+        assert_eq!(rev_parse(&repo, "HEAD^^").unwrap(), h.c2);
+        assert_eq!(rev_parse(&repo, "HEAD^2").unwrap(), h.side);
+        assert_eq!(rev_parse(&repo, "HEAD^2~1").unwrap(), h.c1);
+        assert_eq!(rev_parse(&repo, "HEAD^0").unwrap(), h.merge);
+        assert_eq!(rev_parse(&repo, "main~3").unwrap(), h.c1);
+
+        assert!(rev_parse(&repo, "HEAD^3").is_err());
+        assert!(rev_parse(&repo, "HEAD~4").is_err());
+        assert!(rev_parse(&repo, "HEAD~0é").is_err());
+        assert!(rev_parse(&repo, "HEAD^x").is_err());
+    }
+
+    #[test]
+    fn tags_peel_to_what_is_asked_for() {
+        let (_dir, repo) = testing::repo("revparse-peel");
+        let h = history(&repo);
+
+        let tag = rev_parse(&repo, "v1").unwrap();
+        assert_eq!(repo.load_object(&tag).unwrap().obj_type, ObjectType::Tag);
+        assert_eq!(rev_parse(&repo, "v1^{}").unwrap(), h.c2);
+        assert_eq!(rev_parse(&repo, "v1^{commit}").unwrap(), h.c2);
+        assert_eq!(rev_parse(&repo, "v1~1").unwrap(), h.c1);
+        assert_eq!(rev_parse(&repo, "v1^{tree}").unwrap(), repo.tree_of(&h.c2).unwrap());
+
+        assert!(rev_parse(&repo, "v1^{blob}").is_err());
+        assert!(rev_parse(&repo, "v1^{bogus}").is_err());
+        assert!(rev_parse(&repo, "v1^{commit").is_err());
+    }
+
+    #[test]
+    fn paths_resolve_in_trees_and_the_index() {
+        let (_dir, repo) = testing::repo("revparse-paths");
+        let h = history(&repo);
+
+        assert_eq!(rev_parse(&repo, "HEAD:docs/README").unwrap(), h.readme);
+        assert_eq!(rev_parse(&repo, "v1:docs/README").unwrap(), h.readme);
+        assert!(rev_parse(&repo, "HEAD:missing").is_err());
+
+        let mut index = repo.index().unwrap();
+        index.add(IndexEntry::without_stat("docs/README".to_string(), 0o100644, h.readme.clone()));
+        index.save().unwrap();
+        assert_eq!(rev_parse(&repo, ":docs/README").unwrap(), h.readme);
+        assert!(rev_parse(&repo, ":missing").is_err());
+    }
+
+    #[test]
+    fn reflog_and_upstream_selectors() {
+        let (_dir, repo) = testing::repo("revparse-selectors");
+        let h = history(&repo);
+
+        assert_eq!(rev_parse(&repo, "main@{0}").unwrap(), h.merge);
+        assert_eq!(rev_parse(&repo, "main@{1}").unwrap(), h.c3);
+        assert_eq!(rev_parse(&repo, "@{3}").unwrap(), h.c1);
+        assert_eq!(rev_parse(&repo, "main@{1}~1").unwrap(), h.c2);
+        assert!(rev_parse(&repo, "main@{9}").is_err());
+        assert!(rev_parse(&repo, "main@{upstream}").is_err());
+
+        let config = fs::read_to_string(repo.git_dir().join("config")).unwrap();
+        fs::write(
+            repo.git_dir().join("config"),
+            config + "[branch \"main\"]\n\tremote = .\n\tmerge = refs/heads/side\n",
+        ).unwrap();
+        assert_eq!(rev_parse(&repo, "main@{upstream}").unwrap(), h.side);
+        assert_eq!(rev_parse(&repo, "@{u}").unwrap(), h.side);
+    }
+}
@@ -0,0 +1,240 @@
+// TriForge/src/native_git/revwalk.rs
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use anyhow::Result;
+
+use super::commit::{CommitBuilder, ParsedCommit};
+use super::merge;
+use super::objects::ObjectType;
+use super::repository::Repository;
+use super::revparse;
+use super::tree::{TreeBuilder, TreeEntry, TREE_MODE};
+
+/// Order in which `RevWalk` emits commits
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Order {
+    /// Newest committer date first
+    Date,
+    /// Children always before parents, keeping each line of history together
+    Topo,
+}
+
+/// A commit produced by the walk
+pub struct WalkCommit {
+    pub hash: String,
+    pub commit: ParsedCommit,
+}
+
+/// Walks history from a set of tips over every parent, hiding anything
+/// reachable from excluded commits
+pub struct RevWalk<'a> {
+    repo: &'a Repository,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    order: Order,
+}
+
+impl<'a> RevWalk<'a> {
+    pub fn new(repo: &'a Repository) -> Self {
+        Self {
+            repo,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            order: Order::Date,
+        }
+    }
+
+    /// Start walking from a commit
+    pub fn push(mut self, hash: String) -> Self {
+        self.include.push(hash);
+        self
+    }
+
+    /// Leave out a commit and all of its ancestors (`^hash`, or `A` in `A..B`)
+    pub fn hide(mut self, hash: String) -> Self {
+        self.exclude.push(hash);
+        self
+    }
+
+    pub fn order(mut self, order: Order) -> Self {
+        self.order = order;
+        self
+    }
+
+    /// Load every selected commit in walk order
+    pub fn run(self) -> Result<Vec<WalkCommit>> {
+        let mut hidden = HashSet::new();
+        for hash in &self.exclude {
+            hidden.extend(merge::ancestors(self.repo, hash)?);
+        }
+
+        // Load the selected part of the graph
+        let mut commits: HashMap<String, ParsedCommit> = HashMap::new();
+        let mut stack: Vec<String> = self.include.clone();
+        while let Some(hash) = stack.pop() {
+            if hidden.contains(&hash) || commits.contains_key(&hash) {
+                continue;
+            }
+            let commit = CommitBuilder::parse(&self.repo.load_object(&hash)?)?;
+            stack.extend(commit.parents.iter().cloned());
+            commits.insert(hash, commit);
+        }
+
+        let order = match self.order {
+            Order::Date => date_order(&self.include, &commits),
+            Order::Topo => topo_order(&commits),
+        };
+
+        Ok(order.into_iter()
+            .filter_map(|hash| commits.remove(&hash).map(|commit| WalkCommit { hash, commit }))
+            .collect())
+    }
+}
+
+/// Newest first by committer time, starting from the tips
+fn date_order(tips: &[String], commits: &HashMap<String, ParsedCommit>) -> Vec<String> {
+    let mut heap = BinaryHeap::new();
+    let mut queued = HashSet::new();
+    let mut order = Vec::new();
+
+    for tip in tips {
+        if let Some(commit) = commits.get(tip) {
+            if queued.insert(tip.clone()) {
+                heap.push((commit.committer.time, tip.clone()));
+            }
+        }
+    }
+
+    while let Some((_, hash)) = heap.pop() {
+        for parent in &commits[&hash].parents {
+            if let Some(commit) = commits.get(parent) {
+                if queued.insert(parent.clone()) {
+                    heap.push((commit.committer.time, parent.clone()));
+                }
+            }
+        }
+        order.push(hash);
+    }
+
+    order
+}
+
+/// Kahn's algorithm over the loaded commits. A commit becomes ready once all
+/// of its children are emitted; ready commits are taken depth-first so a
+/// branch is shown in one piece, first parents before merged branches.
+fn topo_order(commits: &HashMap<String, ParsedCommit>) -> Vec<String> {
+    let mut children: HashMap<&str, usize> = commits.keys().map(|h| (h.as_str(), 0)).collect();
+    for commit in commits.values() {
+        for parent in &commit.parents {
+            if let Some(count) = children.get_mut(parent.as_str()) {
+                *count += 1;
+            }
+        }
+    }
+
+    // Tips, newest on top of the stack
+    let mut tips: Vec<&String> = commits.keys()
+        .filter(|h| children[h.as_str()] == 0)
+        .collect();
+    tips.sort_by_key(|h| (commits[*h].committer.time, (*h).clone()));
+    let mut stack: Vec<String> = tips.into_iter().cloned().collect();
+
+    let mut order = Vec::new();
+    while let Some(hash) = stack.pop() {
+        for parent in commits[&hash].parents.iter().rev() {
+            if let Some(count) = children.get_mut(parent.as_str()) {
+                *count -= 1;
+                if *count == 0 {
+                    stack.push(parent.clone());
+                }
+            }
+        }
+        order.push(hash);
+    }
+
+    order
+}
+
+/// Every object reachable from `tips`: commits with their parents, trees,
+/// blobs and the targets of annotated tags. Submodule commits are skipped.
+pub fn reachable_objects(repo: &Repository, tips: &[String]) -> Result<HashSet<String>> {
+    let mut seen = HashSet::new();
+    let mut stack: Vec<String> = tips.to_vec();
+
+    while let Some(hash) = stack.pop() {
+        if !seen.insert(hash.clone()) {
+            continue;
+        }
+
+        let obj = repo.load_object(&hash)?;
+        match obj.obj_type {
+            ObjectType::Commit => {
+                let commit = CommitBuilder::parse(&obj)?;
+                stack.push(commit.tree);
+                stack.extend(commit.parents);
+            }
+            ObjectType::Tree => {
+                for entry in TreeBuilder::parse(&obj)? {
+                    if entry.mode == "160000" {
+                        continue;
+                    }
+                    // Blobs need no loading, just recording
+                    if entry.is_tree() {
+                        stack.push(entry.hash);
+                    } else {
+                        seen.insert(entry.hash);
+                    }
+                }
+            }
+            ObjectType::Tag => stack.push(revparse::tag_target(&obj.content)?),
+            ObjectType::Blob => {}
+        }
+    }
+
+    Ok(seen)
+}
+
+/// Whether a commit changes anything under `paths` compared with its
+/// parents. A merge counts only if it differs from every parent.
+pub fn touches_paths(repo: &Repository, commit: &ParsedCommit, paths: &[String]) -> Result<bool> {
+    let mine = path_entries(repo, &commit.tree, paths)?;
+
+    if commit.parents.is_empty() {
+        return Ok(mine.iter().any(|entry| entry.is_some()));
+    }
+
+    for parent in &commit.parents {
+        let parent_tree = CommitBuilder::parse(&repo.load_object(parent)?)?.tree;
+        if path_entries(repo, &parent_tree, paths)? == mine {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// The `(mode, hash)` each path names in a tree, or `None` where it is
+/// absent. A subtree's hash stands for everything beneath it, so only the
+/// trees along each path are read.
+fn path_entries(repo: &Repository, tree: &str, paths: &[String]) -> Result<Vec<Option<(String, String)>>> {
+    paths.iter().map(|path| entry_at(repo, tree, path)).collect()
+}
+
+fn entry_at(repo: &Repository, tree: &str, path: &str) -> Result<Option<(String, String)>> {
+    let mut current = TreeEntry {
+        mode: TREE_MODE.to_string(),
+        name: String::new(),
+        hash: tree.to_string(),
+    };
+
+    let path = path.trim_end_matches('/');
+    for component in path.split('/').filter(|c| !c.is_empty() && *c != ".") {
+        if !current.is_tree() {
+            return Ok(None);
+        }
+        let entries = TreeBuilder::parse(&repo.load_object(&current.hash)?)?;
+        match entries.into_iter().find(|entry| entry.name == component) {
+            Some(entry) => current = entry,
+            None => return Ok(None),
+        }
+    }
+    Ok(Some((current.mode, current.hash)))
+}
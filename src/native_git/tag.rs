@@ -0,0 +1,93 @@
+// TriForge/src/native_git/tag.rs
+use anyhow::Result;
+
+use super::commit::Signature;
+use super::{GitObject, ObjectType};
+
+/// Builds an annotated tag object pointing at another object
+pub struct TagBuilder {
+    target: String,
+    target_type: ObjectType,
+    name: String,
+    tagger: Option<Signature>,
+    message: String,
+}
+
+impl TagBuilder {
+    pub fn new(target: String, target_type: ObjectType, name: String, message: String) -> Self {
+        Self {
+            target,
+            target_type,
+            name,
+            tagger: None,
+            message,
+        }
+    }
+
+    pub fn tagger(mut self, tagger: Signature) -> Self {
+        self.tagger = Some(tagger);
+        self
+    }
+
+    pub fn build(self) -> Result<GitObject> {
+        let mut content = String::new();
+        content.push_str(&format!("object {}\n", self.target));
+        content.push_str(&format!("type {}\n", self.target_type.as_str()));
+        content.push_str(&format!("tag {}\n", self.name));
+        if let Some(tagger) = &self.tagger {
+            content.push_str(&format!("tagger {}\n", tagger.to_raw()));
+        }
+        content.push('\n');
+        content.push_str(&self.message);
+        if !self.message.is_empty() && !self.message.ends_with('\n') {
+            content.push('\n');
+        }
+
+        Ok(GitObject::new(ObjectType::Tag, content.into_bytes()))
+    }
+
+    /// Parse a tag object
+    pub fn parse(obj: &GitObject) -> Result<ParsedTag> {
+        if obj.obj_type != ObjectType::Tag {
+            anyhow::bail!("Not a tag object");
+        }
+
+        let content = std::str::from_utf8(&obj.content)?;
+        let (header, message) = content.split_once("\n\n").unwrap_or((content, ""));
+
+        let mut target = None;
+        let mut target_type = None;
+        let mut name = None;
+        let mut tagger = None;
+
+        for line in header.lines() {
+            if let Some(hash) = line.strip_prefix("object ") {
+                target = Some(hash.to_string());
+            } else if let Some(kind) = line.strip_prefix("type ") {
+                target_type = ObjectType::from_str(kind);
+            } else if let Some(tag) = line.strip_prefix("tag ") {
+                name = Some(tag.to_string());
+            } else if let Some(sig) = line.strip_prefix("tagger ") {
+                tagger = Some(Signature::parse(sig)?);
+            }
+        }
+
+        Ok(ParsedTag {
+            target: target.ok_or_else(|| anyhow::anyhow!("No object in tag"))?,
+            target_type: target_type.ok_or_else(|| anyhow::anyhow!("No valid type in tag"))?,
+            name: name.ok_or_else(|| anyhow::anyhow!("No name in tag"))?,
+            tagger,
+            message: message.trim_end().to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub struct ParsedTag {
+    pub target: String,
+    pub target_type: ObjectType,
+    pub name: String,
+    /// Very old tags were written without a tagger
+    pub tagger: Option<Signature>,
+    pub message: String,
+}
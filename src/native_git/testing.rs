@@ -0,0 +1,40 @@
+// TriForge/src/native_git/testing.rs
+//! Helpers shared by the native_git unit tests
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use super::Repository;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+/// An empty directory under the system temp dir, removed on drop
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    pub fn new(label: &str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir()
+            .join(format!("triforge-test-{}-{}-{}", std::process::id(), label, id));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).expect("create scratch dir");
+        Self(path)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A freshly initialized repository inside its own scratch directory
+pub fn repo(label: &str) -> (ScratchDir, Repository) {
+    let dir = ScratchDir::new(label);
+    let repo = Repository::init(dir.path()).expect("init repository");
+    (dir, repo)
+}
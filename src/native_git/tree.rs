@@ -1,7 +1,13 @@
 use super::{GitObject, ObjectType};
 use std::collections::BTreeMap;
+use std::path::Path;
 use anyhow::Result;
 
+/// Mode Git uses for subtree entries
+pub const TREE_MODE: &str = "40000";
+/// Mode Git uses for symbolic links, whose blob holds the link target
+pub const SYMLINK_MODE: &str = "120000";
+
 #[derive(Debug, Clone)]
 pub struct TreeEntry {
     pub mode: String,
@@ -9,6 +15,20 @@ pub struct TreeEntry {
     pub hash: String,
 }
 
+impl TreeEntry {
+    pub fn is_tree(&self) -> bool {
+        self.mode == TREE_MODE || self.mode == "040000"
+    }
+
+    fn sort_key(&self) -> Vec<u8> {
+        let mut key = self.name.as_bytes().to_vec();
+        if self.is_tree() {
+            key.push(b'/');
+        }
+        key
+    }
+}
+
 pub struct TreeBuilder {
     entries: BTreeMap<String, TreeEntry>,
 }
@@ -32,7 +52,7 @@ impl TreeBuilder {
     /// Add a directory (tree) to the tree
     pub fn add_tree(&mut self, name: String, hash: String) {
         self.entries.insert(name.clone(), TreeEntry {
-            mode: "040000".to_string(), // Directory
+            mode: TREE_MODE.to_string(), // Directory
             name,
             hash,
         });
@@ -47,11 +67,29 @@ impl TreeBuilder {
         });
     }
 
+    /// Add a symbolic link to the tree
+    pub fn add_symlink(&mut self, name: String, hash: String) {
+        self.entries.insert(name.clone(), TreeEntry {
+            mode: SYMLINK_MODE.to_string(), // Symbolic link
+            name,
+            hash,
+        });
+    }
+
+    /// Add an entry with an explicit mode
+    pub fn add_entry(&mut self, mode: String, name: String, hash: String) {
+        self.entries.insert(name.clone(), TreeEntry { mode, name, hash });
+    }
+
     /// Build the tree object
     pub fn build(self) -> Result<GitObject> {
         let mut content = Vec::new();
         
-        for entry in self.entries.values() {
+        // Git orders entries as if directory names had a trailing slash
+        let mut entries: Vec<&TreeEntry> = self.entries.values().collect();
+        entries.sort_by_key(|e| e.sort_key());
+        
+        for entry in entries {
             // Format: mode name\0hash_bytes
             content.extend_from_slice(entry.mode.as_bytes());
             content.push(b' ');
@@ -107,4 +145,147 @@ impl TreeBuilder {
 
         Ok(entries)
     }
+
+    /// Build nested tree objects from `(path, mode, hash)` entries with
+    /// `/`-separated paths, storing every subtree. Returns the root tree hash.
+    pub fn write_paths<I>(git_dir: &Path, files: I) -> Result<String>
+    where
+        I: IntoIterator<Item = (String, String, String)>,
+    {
+        let mut root = DirNode::default();
+        for (path, mode, hash) in files {
+            root.insert(&path, mode, hash);
+        }
+        root.write(git_dir)
+    }
+
+    /// Walk a tree recursively, returning blob entries keyed by full path.
+    /// Each entry's `name` is the full `/`-separated path.
+    pub fn flatten(git_dir: &Path, tree_hash: &str) -> Result<BTreeMap<String, TreeEntry>> {
+        let mut files = BTreeMap::new();
+        Self::flatten_into(git_dir, tree_hash, "", &mut files)?;
+        Ok(files)
+    }
+
+    fn flatten_into(
+        git_dir: &Path,
+        tree_hash: &str,
+        prefix: &str,
+        files: &mut BTreeMap<String, TreeEntry>,
+    ) -> Result<()> {
+        let obj = GitObject::load(git_dir, tree_hash)?;
+        for entry in Self::parse(&obj)? {
+            check_entry_name(&entry.name)?;
+            let path = if prefix.is_empty() {
+                entry.name.clone()
+            } else {
+                format!("{}/{}", prefix, entry.name)
+            };
+
+            if entry.is_tree() {
+                Self::flatten_into(git_dir, &entry.hash, &path, files)?;
+            } else {
+                files.insert(path.clone(), TreeEntry {
+                    mode: entry.mode,
+                    name: path,
+                    hash: entry.hash,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reject tree entry names that would escape the worktree or reach into
+/// `.git` once joined onto a path
+fn check_entry_name(name: &str) -> Result<()> {
+    let unsafe_name = name.is_empty()
+        || name == "."
+        || name == ".."
+        || name.eq_ignore_ascii_case(".git")
+        || name.contains('/')
+        || name.contains('\0');
+    if unsafe_name {
+        anyhow::bail!("Refusing tree entry with unsafe name '{}'", name.escape_debug());
+    }
+    Ok(())
+}
+
+/// In-memory directory used while assembling nested trees
+#[derive(Default)]
+struct DirNode {
+    files: Vec<(String, String, String)>,
+    dirs: BTreeMap<String, DirNode>,
+}
+
+impl DirNode {
+    fn insert(&mut self, path: &str, mode: String, hash: String) {
+        match path.split_once('/') {
+            Some((dir, rest)) => {
+                self.dirs.entry(dir.to_string())
+                    .or_default()
+                    .insert(rest, mode, hash);
+            }
+            None => self.files.push((path.to_string(), mode, hash)),
+        }
+    }
+
+    fn write(self, git_dir: &Path) -> Result<String> {
+        let mut builder = TreeBuilder::new();
+
+        for (name, mode, hash) in self.files {
+            if self.dirs.contains_key(&name) {
+                anyhow::bail!("'{}' is both a file and a directory in the index", name);
+            }
+            match mode.as_str() {
+                "100644" => builder.add_file(name, hash),
+                "100755" => builder.add_executable(name, hash),
+                SYMLINK_MODE => builder.add_symlink(name, hash),
+                _ => builder.add_entry(mode, name, hash),
+            }
+        }
+
+        for (name, dir) in self.dirs {
+            let hash = dir.write(git_dir)?;
+            builder.add_tree(name, hash);
+        }
+
+        let obj = builder.build()?;
+        obj.store(git_dir)?;
+        Ok(obj.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::native_git::testing;
+
+    const BLOB: &str = "e69de29bb2d1d6434b8b29ae775ad8c2e48c5391";
+
+    fn file(path: &str) -> (String, String, String) {
+        (path.to_string(), "100644".to_string(), BLOB.to_string())
+    }
+
+    #[test]
+    fn write_paths_nests_and_flattens_back() {
+        let (_dir, repo) = testing::repo("tree-nesting");
+        let root = TreeBuilder::write_paths(repo.git_dir(), vec![
+            file("README"),
+            file("src/main.rs"),
+            file("src/native_git/tree.rs"),
+        ]).unwrap();
+
+        let files = TreeBuilder::flatten(repo.git_dir(), &root).unwrap();
+        let paths: Vec<&str> = files.keys().map(|p| p.as_str()).collect();
+        assert_eq!(paths, ["README", "src/main.rs", "src/native_git/tree.rs"]);
+    }
+
+    #[test]
+    fn path_cannot_be_both_file_and_directory() {
+        let (_dir, repo) = testing::repo("tree-clash");
+        let result = TreeBuilder::write_paths(repo.git_dir(), vec![file("a"), file("a/b")]);
+        assert!(result.is_err());
+    }
 }
+